@@ -0,0 +1,93 @@
+//compares the VecDeque backed BitReader against SliceBitReader, on their own
+//and when decoding a whole ACM stream
+//
+//uses a real Fallout 2 sound effect if the extracted game files are in
+//reference/, otherwise a generated (level 0) stream
+
+use acm::{
+    Acm,
+    decoder::{ Decoder, Header },
+};
+use common::{
+    BitRead,
+    BitReader,
+    SliceBitReader,
+};
+use criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    Criterion,
+};
+
+const REFERENCE_ACM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../reference/f2/master/sound/sfx/ib1p1xx1.acm");
+
+fn acm_data() -> Vec<u8> {
+    if let Ok(data) = std::fs::read(REFERENCE_ACM) {
+        return data;
+    }
+
+    let samples = (0 .. 22050 * 4)
+        .map(|i| ((i as f32 * 0.05).sin() * 8000.0) as i16 & !0xF)
+        .collect();
+    let acm = Acm { channels: 1, sample_rate: 22050, samples };
+    acm.write_to_acm().unwrap()
+}
+
+//decodes every block through `reader`, like AcmDecoder does with a complete stream
+fn decode(reader: &mut impl BitRead) -> usize {
+    let header = Header::read(reader).unwrap();
+    let mut decoder = Decoder::new(header).unwrap();
+    let blocks = (header.total_values as usize).div_ceil(header.block_len());
+
+    let mut sum = 0usize;
+    for _ in 0 .. blocks {
+        if decoder.decode_block(reader).is_err() {
+            break;
+        }
+        sum += decoder.samples().map(|s| s as usize).sum::<usize>();
+    }
+    sum
+}
+
+fn read_pattern(reader: &mut impl BitRead, bits: &[u8]) -> u64 {
+    let mut sum = 0u64;
+    for bits in bits.iter().cycle() {
+        match reader.get_bits(*bits) {
+            Some(value) => sum += value as u64,
+            None => break,
+        }
+    }
+    sum
+}
+
+fn bit_reads(c: &mut Criterion) {
+    let data: Vec<u8> = (0 .. 64 * 1024).map(|i| (i * 31 % 251) as u8).collect();
+    //mix of the widths the acm fillers use
+    let pattern = [1, 1, 2, 3, 5, 7, 4, 16, 1, 2];
+
+    let mut group = c.benchmark_group("bit reads");
+    group.bench_function("BitReader", |b| {
+        b.iter(|| read_pattern(&mut BitReader::new(data.clone()), black_box(&pattern)))
+    });
+    group.bench_function("SliceBitReader", |b| {
+        b.iter(|| read_pattern(&mut SliceBitReader::new(&data), black_box(&pattern)))
+    });
+    group.finish();
+}
+
+fn acm_decode(c: &mut Criterion) {
+    let data = acm_data();
+
+    let mut group = c.benchmark_group("acm decode");
+    group.bench_function("BitReader", |b| {
+        b.iter(|| decode(&mut BitReader::new(black_box(&data).clone())))
+    });
+    group.bench_function("SliceBitReader", |b| {
+        b.iter(|| decode(&mut SliceBitReader::new(black_box(&data))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bit_reads, acm_decode);
+criterion_main!(benches);
@@ -1,7 +1,13 @@
-use std::env;
-use std::path::PathBuf;
-
 fn main() {
+    #[cfg(feature = "libacm")]
+    build_libacm();
+}
+
+#[cfg(feature = "libacm")]
+fn build_libacm() {
+    use std::env;
+    use std::path::PathBuf;
+
     let files = [
         //"acmtool.c",
         "decode.c",
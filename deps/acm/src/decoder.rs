@@ -0,0 +1,259 @@
+use crate::{
+    SampleType,
+    error::AcmError,
+    fillers::{ Fillers, FillerArgs },
+};
+
+use common::{
+    BitRead,
+    BitWriter,
+    Vec2d,
+};
+
+const ACM_ID: u32 = 0x03_28_97;
+const ACM_VERSION: u8 = 1;
+
+//size of the amplitude table, the middle of it is the zero amplitude
+const AMP_BUFFER_SIZE: usize = 0x10_000;
+const AMP_BUFFER_MIDDLE: usize = AMP_BUFFER_SIZE / 2;
+
+//largest block a header may ask for, Fallout's files use 2048 values or fewer
+pub(crate) const MAX_BLOCK_LEN: usize = 0x10_0000;
+
+/// Stream header, packed into the first 14 bytes of the file
+///
+/// * `total_values` - sample count over all channels
+/// * `level` - number of subband levels, each block has `2^level` columns
+/// * `rows` - rows per block
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub total_values: u32,
+    pub channels: u16,
+    pub sample_rate: u16,
+    pub level: u8,
+    pub rows: u16,
+}
+
+macro_rules! get_bits {
+    ($reader:expr, $bits:expr, $f:ident) => {{
+        $reader.$f($bits).ok_or(AcmError::BitError)
+    }};
+}
+
+impl Header {
+    pub const SIZE: usize = 14;
+
+    pub fn read(reader: &mut impl BitRead) -> Result<Self, AcmError> {
+        let id = get_bits!(reader, 24, get_bits_u32)?;
+        let version = get_bits!(reader, 8, get_bits_u8)?;
+        if id != ACM_ID || version != ACM_VERSION {
+            return Err(AcmError::SigError);
+        }
+
+        let values_low = get_bits!(reader, 16, get_bits_u32)?;
+        let values_high = get_bits!(reader, 16, get_bits_u32)?;
+        let channels = get_bits!(reader, 16, get_bits_u16)?;
+        let sample_rate = get_bits!(reader, 16, get_bits_u16)?;
+        let level = get_bits!(reader, 4, get_bits_u8)?;
+        let rows = get_bits!(reader, 12, get_bits_u16)?;
+
+        Ok(Self {
+            total_values: values_low | (values_high << 16),
+            channels,
+            sample_rate,
+            level,
+            rows,
+        })
+    }
+
+    pub fn write(&self, writer: &mut BitWriter) {
+        writer.put_bits(ACM_ID as u64, 24);
+        writer.put_bits(ACM_VERSION as u64, 8);
+        writer.put_bits(self.total_values as u64 & 0xFFFF, 16);
+        writer.put_bits(self.total_values as u64 >> 16, 16);
+        writer.put_bits(self.channels as u64, 16);
+        writer.put_bits(self.sample_rate as u64, 16);
+        writer.put_bits(self.level as u64, 4);
+        writer.put_bits(self.rows as u64, 12);
+    }
+
+    pub fn columns(&self) -> usize {
+        1 << self.level
+    }
+
+    pub fn block_len(&self) -> usize {
+        self.columns() * self.rows as usize
+    }
+
+    /// Lower bound on the packed size of a block in bits, every column zero filled
+    pub fn min_block_bits(&self) -> u64 {
+        4 + 16 + self.columns() as u64 * 5
+    }
+
+    /// Upper bound on the packed size of a block, every value using a 16 bit linear filler
+    pub fn max_block_bytes(&self) -> usize {
+        let bits = 4 + 16 + self.columns() * 5 + self.block_len() * 16;
+        bits.div_ceil(8)
+    }
+}
+
+/// Native port of libacm's block decoder
+///
+/// Each block starts with the amplitude table parameters, then one filler
+/// code per column which unpacks that column's values. The packed block then
+/// goes through the inverse subband transform (`juggle_block`), whose state
+/// carries over between blocks in `wrap_buffer`.
+pub struct Decoder {
+    pub header: Header,
+
+    block: Vec2d<i32>,
+    wrap_buffer: Vec<i32>,
+    amp_buffer: Vec<i32>,
+}
+
+impl Decoder {
+    pub fn new(header: Header) -> Result<Self, AcmError> {
+        if header.rows == 0 {
+            return Err(AcmError::SigError);
+        }
+        if header.block_len() > MAX_BLOCK_LEN {
+            return Err(AcmError::HeaderError);
+        }
+
+        let columns = header.columns();
+        Ok(Self {
+            header,
+            block: Vec2d::new(columns, header.rows as usize),
+            wrap_buffer: vec![0; 2 * columns - 2],
+            amp_buffer: vec![0; AMP_BUFFER_SIZE],
+        })
+    }
+
+    /// Decodes the next block, afterwards its samples are available from `samples`
+    pub fn decode_block(&mut self, reader: &mut impl BitRead) -> Result<(), AcmError> {
+        let power = get_bits!(reader, 4, get_bits_u8)?;
+        let value = get_bits!(reader, 16, get_bits_u16)?;
+
+        self.fill_amp_buffer(power, value as i32);
+        self.fill_block(reader)?;
+        self.juggle_block();
+
+        Ok(())
+    }
+
+    /// Samples of the last decoded block, in output order
+    pub fn samples(&self) -> impl Iterator<Item = SampleType> + '_ {
+        let level = self.header.level;
+        self.block.iter().map(move |v| (v >> level) as SampleType)
+    }
+
+    /// Transform state carried between blocks
+    pub fn wrap_state(&self) -> &[i32] {
+        &self.wrap_buffer
+    }
+
+    pub fn set_wrap_state(&mut self, state: &[i32]) {
+        self.wrap_buffer.copy_from_slice(state);
+    }
+
+    fn fill_amp_buffer(&mut self, power: u8, value: i32) {
+        let count = 1usize << power;
+        let middle = AMP_BUFFER_MIDDLE;
+
+        let mut x = 0i32;
+        for i in 0 .. count {
+            self.amp_buffer[middle + i] = x;
+            x = x.wrapping_add(value);
+        }
+
+        let mut x = value.wrapping_neg();
+        for i in 1 ..= count {
+            self.amp_buffer[middle - i] = x;
+            x = x.wrapping_sub(value);
+        }
+    }
+
+    fn fill_block(&mut self, reader: &mut impl BitRead) -> Result<(), AcmError> {
+        for column in 0 .. self.header.columns() {
+            let index = get_bits!(reader, 5, get_bits_u8)?;
+
+            let args = FillerArgs {
+                reader: &mut *reader,
+                packed_block: &mut self.block,
+                amp_buffer: &mut self.amp_buffer,
+                index: index as usize,
+                column,
+            };
+            Fillers::from(index).fill(args)?;
+        }
+
+        Ok(())
+    }
+
+    fn juggle_block(&mut self) {
+        let level = self.header.level as usize;
+        if level == 0 {
+            return;
+        }
+
+        //2048 / columns
+        let step_subcount = if level > 9 { 1 } else { (2048 >> level) - 2 };
+
+        let mut todo_count = self.header.rows as usize;
+        let mut block_start = 0;
+        loop {
+            let mut wrap_start = 0;
+            let mut sub_count = step_subcount.min(todo_count) * 2;
+            let mut sub_len = self.header.columns() / 2;
+
+            self.juggle(wrap_start, block_start, sub_len, sub_count);
+            wrap_start += sub_len * 2;
+
+            for i in 0 .. sub_count {
+                let idx = block_start + i * sub_len;
+                self.block[idx] = self.block[idx].wrapping_add(1);
+            }
+
+            while sub_len > 1 {
+                sub_len /= 2;
+                sub_count *= 2;
+
+                self.juggle(wrap_start, block_start, sub_len, sub_count);
+                wrap_start += sub_len * 2;
+            }
+
+            if todo_count <= step_subcount {
+                break;
+            }
+            todo_count -= step_subcount;
+            block_start += step_subcount << level;
+        }
+    }
+
+    fn juggle(&mut self, wrap_start: usize, block_start: usize, sub_len: usize, sub_count: usize) {
+        let block = &mut self.block;
+        let wrap = &mut self.wrap_buffer[wrap_start ..];
+
+        for i in 0 .. sub_len {
+            let mut p = block_start + i;
+            let mut r0 = wrap[2 * i];
+            let mut r1 = wrap[2 * i + 1];
+
+            for _ in 0 .. sub_count / 2 {
+                let r2 = block[p];
+                block[p] = r1.wrapping_mul(2).wrapping_add(r0.wrapping_add(r2));
+                p += sub_len;
+
+                let r3 = block[p];
+                block[p] = r2.wrapping_mul(2).wrapping_sub(r1.wrapping_add(r3));
+                p += sub_len;
+
+                r0 = r2;
+                r1 = r3;
+            }
+
+            wrap[2 * i] = r0;
+            wrap[2 * i + 1] = r1;
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use crate::{
+    Acm,
+    error::AcmError,
+    decoder::{ Header, MAX_BLOCK_LEN },
+};
+
+use common::BitWriter;
+
+const DEFAULT_ROWS: u16 = 256;
+const MAX_ROWS: u16 = 0xFFF;
+const DEFAULT_LEVEL: u8 = 3;
+const MAX_LEVEL: u8 = 0xF;
+
+//the amplitude table holds indices -2^15 .. 2^15 - 1 times a 16 bit step
+const MAX_POWER: u32 = 15;
+const MAX_STEP: u32 = 0xFFFF;
+
+//amplitude indices behind each filler's bit patterns, see fillers.rs
+const MAP_1BIT: [i32; 2] = [-1, 1];
+const MAP_2BIT_NEAR: [i32; 4] = [-2, -1, 1, 2];
+const MAP_2BIT_FAR: [i32; 4] = [-3, -2, 2, 3];
+const MAP_3BIT: [i32; 8] = [-4, -3, -2, -1, 1, 2, 3, 4];
+
+/// Packs 16 bit PCM into an ACM stream that `Acm::open` and `AcmDecoder` can read
+///
+/// Audio that fits the amplitude table at the chosen level is stored losslessly,
+/// anything louder is quantized per block until it fits.
+#[derive(Debug, Clone, Copy)]
+pub struct Encoder {
+    rows: u16,
+    level: u8,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self { rows: DEFAULT_ROWS, level: DEFAULT_LEVEL }
+    }
+}
+
+impl Encoder {
+    /// * `rows` - rows per block, between 1 and 4095
+    pub fn new(rows: u16) -> Result<Self, AcmError> {
+        if rows == 0 || rows > MAX_ROWS {
+            return Err(AcmError::FormatError);
+        }
+
+        Self { rows, level: 0 }.with_level(DEFAULT_LEVEL.min(Self::max_level(rows)))
+    }
+
+    /// * `level` - subband levels, each block has `rows * 2^level` samples
+    pub fn with_level(self, level: u8) -> Result<Self, AcmError> {
+        if level > Self::max_level(self.rows) {
+            return Err(AcmError::FormatError);
+        }
+
+        Ok(Self { level, ..self })
+    }
+
+    //the decoder refuses blocks above `MAX_BLOCK_LEN` values
+    fn max_level(rows: u16) -> u8 {
+        (0 ..= MAX_LEVEL)
+            .take_while(|level| (rows as usize) << level <= MAX_BLOCK_LEN)
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Encodes `acm.samples`, interleaved over `acm.channels`
+    pub fn encode(&self, acm: &Acm) -> Result<Vec<u8>, AcmError> {
+        let header = Header {
+            total_values: u32::try_from(acm.samples.len()).map_err(|_| AcmError::FormatError)?,
+            channels: u16::try_from(acm.channels).map_err(|_| AcmError::FormatError)?,
+            sample_rate: u16::try_from(acm.sample_rate).map_err(|_| AcmError::FormatError)?,
+            level: self.level,
+            rows: self.rows,
+        };
+        if header.channels == 0 {
+            return Err(AcmError::FormatError);
+        }
+
+        //level 0 stores the samples as they are, so it always fits
+        let data = encode_exact(header, &acm.samples)
+            .unwrap_or_else(|| encode_quantized(header, &acm.samples));
+
+        Ok(data)
+    }
+}
+
+impl Acm {
+    /// Encodes into an ACM stream with the default `Encoder` settings
+    pub fn write_to_acm(&self) -> Result<Vec<u8>, AcmError> {
+        Encoder::default().encode(self)
+    }
+}
+
+//`None` when some block's transform input doesn't fit the amplitude table
+fn encode_exact(header: Header, samples: &[i16]) -> Option<Vec<u8>> {
+    let mut writer = BitWriter::new();
+    header.write(&mut writer);
+
+    let mut analysis = Analysis::new(header);
+    let mut block = vec![0; header.block_len()];
+    for samples in samples.chunks(header.block_len()) {
+        block.fill(0);
+        for (value, sample) in block.iter_mut().zip(samples) {
+            *value = (*sample as i32) << header.level;
+        }
+
+        analysis.run(&mut block, samples.len());
+        write_block(&mut writer, &block, header.columns())?;
+    }
+
+    Some(writer.into_bytes())
+}
+
+//runs the decoder's subband transform backwards and rounds each block to the
+//smallest step that fits, see `unjuggle`
+fn encode_quantized(header: Header, samples: &[i16]) -> Vec<u8> {
+    let level = header.level;
+    let block_len = header.block_len();
+    let columns = header.columns();
+
+    //aim for the middle of the range that shifts down to each sample
+    let half = (1 << level >> 1) as f64;
+    let mut values = vec![0.0; samples.len().div_ceil(block_len) * block_len];
+    for (value, sample) in values.iter_mut().zip(samples) {
+        *value = ((*sample as i32) << level) as f64 + half;
+    }
+
+    for stage in (0 .. level).rev() {
+        let sub_len = columns >> (stage + 1);
+        if stage == 0 {
+            for value in values.iter_mut().step_by(sub_len) {
+                *value -= 1.0;
+            }
+        }
+        unjuggle(&mut values, sub_len);
+    }
+
+    let mut writer = BitWriter::new();
+    header.write(&mut writer);
+
+    for block in values.chunks(block_len) {
+        let peak = block.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+        let step = (peak / i16::MAX as f64).ceil().clamp(1.0, MAX_STEP as f64);
+
+        let block: Vec<i32> = block.iter()
+            .map(|v| (v / step).round().clamp(-(i16::MAX as f64), i16::MAX as f64) as i32 * step as i32)
+            .collect();
+        write_block(&mut writer, &block, columns).expect("quantized values fit the amplitude table");
+    }
+
+    writer.into_bytes()
+}
+
+/// Undoes one step of `Decoder::juggle` over the whole stream
+///
+/// Each of the `sub_len` interleaved columns runs through the step as one stream
+/// of pairs, from block to block. A pair `a`, `b` comes out as
+/// `a + a' + 2b'` and `2a - b - b'`, with `a'`, `b'` the pair before, so getting
+/// the pairs back divides by `1 + 6z^-1 + z^-2`. Its roots are `-3 ± 2*sqrt(2)`,
+/// and the outer one is only stable running backwards from the end of the stream.
+/// That leaves a pair before the start, where the decoder has 0, so audio that
+/// doesn't start from silence comes out off for its first few samples.
+fn unjuggle(values: &mut [f64], sub_len: usize) {
+    //the inner root's tail dies off to nothing within a few dozen pairs
+    const TAIL: usize = 32;
+    let inner = 3.0 - 2.0 * std::f64::consts::SQRT_2;
+    let outer = 3.0 + 2.0 * std::f64::consts::SQRT_2;
+
+    for column in 0 .. sub_len {
+        let stream: Vec<f64> = values[column ..].iter().step_by(sub_len).copied().collect();
+        let pairs = stream.len() / 2;
+
+        //multiplied by the adjugate first, which is the step itself
+        let mut out = vec![(0.0, 0.0); pairs + TAIL];
+        let mut last = (0.0, 0.0);
+        for (n, out) in out.iter_mut().enumerate() {
+            let (even, odd) = if n < pairs { (stream[2 * n], stream[2 * n + 1]) } else { (0.0, 0.0) };
+            *out = (even + last.0 + 2.0 * last.1, 2.0 * even - odd - last.1);
+            last = (even, odd);
+        }
+
+        for n in 1 .. out.len() {
+            out[n].0 -= inner * out[n - 1].0;
+            out[n].1 -= inner * out[n - 1].1;
+        }
+        let mut next = (0.0, 0.0);
+        for out in out.iter_mut().rev() {
+            (*out, next) = (next, ((out.0 - next.0) / outer, (out.1 - next.1) / outer));
+        }
+
+        for (n, (a, b)) in out[.. pairs].iter().enumerate() {
+            values[column + 2 * n * sub_len] = *a;
+            values[column + (2 * n + 1) * sub_len] = *b;
+        }
+    }
+}
+
+/// Picks the values to pack so the decoder's subband transform gives back the samples
+///
+/// Runs the transform like `Decoder::juggle_block`, but one position at a time
+/// in block order. Every output only depends on the packed values up to its own
+/// position, and on its own one with a factor of 1 or -1, so each packed value
+/// is chosen as the smallest that lands its output in the range that shifts
+/// down to the wanted sample.
+struct Analysis {
+    header: Header,
+    wrap_buffer: Vec<i32>,
+    //first input of the pair each step's column is halfway through
+    pending: Vec<i32>,
+    last_low: i32,
+}
+
+impl Analysis {
+    fn new(header: Header) -> Self {
+        Self {
+            header,
+            wrap_buffer: vec![0; 2 * header.columns() - 2],
+            pending: vec![0; header.columns() - 1],
+            last_low: 0,
+        }
+    }
+
+    //`block` holds the samples shifted up by the level, and gets the values to pack,
+    //past `len` it's padding the decoder drops so any output will do
+    fn run(&mut self, block: &mut [i32], len: usize) {
+        let level = self.header.level as usize;
+        if level == 0 {
+            return;
+        }
+
+        let spare = 1 << level;
+        let step_subcount = if level > 9 { 1 } else { (2048 >> level) - 2 };
+
+        let mut todo_count = self.header.rows as usize;
+        let mut block_start = 0;
+        loop {
+            //wrap start, sub length and sub count of each step, in the decoder's order
+            let mut steps = Vec::with_capacity(level);
+            let mut wrap_start = 0;
+            let mut sub_count = step_subcount.min(todo_count) * 2;
+            let mut sub_len = self.header.columns() / 2;
+
+            steps.push((wrap_start, sub_len));
+            wrap_start += sub_len * 2;
+            while sub_len > 1 {
+                sub_len /= 2;
+                sub_count *= 2;
+                steps.push((wrap_start, sub_len));
+                wrap_start += sub_len * 2;
+            }
+
+            let columns = self.header.columns();
+            for offset in 0 .. sub_count * sub_len {
+                let p = block_start + offset;
+                let (rest, sign) = self.juggle_at(&steps, offset, 0, false);
+
+                //the first column is the lowest band and changes slowly, the others stay near 0
+                let guess = if offset % columns == 0 { self.last_low } else { 0 };
+
+                let low = block[p].wrapping_sub(rest).wrapping_mul(sign);
+                let high = block[p].saturating_add(spare - 1).wrapping_sub(rest).wrapping_mul(sign);
+                let value = if p < len { guess.clamp(low.min(high), low.max(high)) } else { guess };
+
+                block[p] = value;
+                if offset % columns == 0 {
+                    self.last_low = value;
+                }
+                self.juggle_at(&steps, offset, value, true);
+            }
+
+            if todo_count <= step_subcount {
+                break;
+            }
+            todo_count -= step_subcount;
+            block_start += step_subcount << level;
+        }
+    }
+
+    //output of every step at `offset` into the chunk for a packed `value`, and the
+    //sign `value` ends up with, `commit` moves the transform state on
+    fn juggle_at(&mut self, steps: &[(usize, usize)], offset: usize, value: i32, commit: bool) -> (i32, i32) {
+        let mut value = value;
+        let mut sign = 1;
+
+        for (n, &(wrap_start, sub_len)) in steps.iter().enumerate() {
+            let column = offset % sub_len;
+            let w = wrap_start + 2 * column;
+            let (r0, r1) = (self.wrap_buffer[w], self.wrap_buffer[w + 1]);
+
+            value = if (offset / sub_len).is_multiple_of(2) {
+                if commit {
+                    self.pending[wrap_start / 2 + column] = value;
+                }
+                r1.wrapping_mul(2).wrapping_add(r0.wrapping_add(value))
+            } else {
+                let r2 = self.pending[wrap_start / 2 + column];
+                if commit {
+                    self.wrap_buffer[w] = r2;
+                    self.wrap_buffer[w + 1] = value;
+                }
+                sign = -sign;
+                r2.wrapping_mul(2).wrapping_sub(r1.wrapping_add(value))
+            };
+
+            if n == 0 && column == 0 {
+                value = value.wrapping_add(1);
+            }
+        }
+
+        (value, sign)
+    }
+}
+
+//`None` when the values need more than the amplitude table holds
+fn write_block(writer: &mut BitWriter, block: &[i32], columns: usize) -> Option<()> {
+    let step = block.iter().fold(0, |acc, v| gcd(acc, v.unsigned_abs()));
+    if step == 0 {
+        writer.put_bits(0, 4);
+        writer.put_bits(0, 16);
+        for _ in 0 .. columns {
+            writer.put_bits(Packing::Zero.code() as u64, 5);
+        }
+        return Some(());
+    }
+    if step > MAX_STEP {
+        return None;
+    }
+
+    let values: Vec<i32> = block.iter().map(|v| v / step as i32).collect();
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+
+    //the amplitude table holds indices -2^power .. 2^power - 1
+    let needed = (max as i64 + 1).max(-(min as i64)).max(1) as u64;
+    let power = needed.next_power_of_two().trailing_zeros();
+    if power > MAX_POWER {
+        return None;
+    }
+
+    writer.put_bits(power as u64, 4);
+    writer.put_bits(step as u64, 16);
+
+    let rows = values.len() / columns;
+    for column in 0 .. columns {
+        let values: Vec<i32> = (0 .. rows).map(|row| values[row * columns + column]).collect();
+        let min = values.iter().copied().min().unwrap_or(0);
+        let max = values.iter().copied().max().unwrap_or(0);
+
+        let packing = Packing::candidates(min, max)
+            .min_by_key(|packing| {
+                let mut bits = 0usize;
+                packing.pack(&values, &mut |_, n| bits += n as usize);
+                bits
+            })
+            .unwrap_or(Packing::Linear(16));
+
+        writer.put_bits(packing.code() as u64, 5);
+        packing.pack(&values, &mut |value, bits| writer.put_bits(value, bits));
+    }
+
+    Some(())
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Packing {
+    Zero,
+    Linear(u8),
+    K13,
+    K12,
+    T15,
+    K24,
+    K23,
+    T27,
+    K35,
+    K34,
+    K45,
+    K44,
+    T37,
+}
+
+impl Packing {
+    const PACKED: [Self; 11] = [
+        Self::K13, Self::K12, Self::T15,
+        Self::K24, Self::K23, Self::T27,
+        Self::K35, Self::K34,
+        Self::K45, Self::K44,
+        Self::T37,
+    ];
+
+    //every filler able to store amplitude indices in min ..= max
+    fn candidates(min: i32, max: i32) -> impl Iterator<Item = Self> {
+        let linear = (3 ..= 16)
+            .map(Self::Linear)
+            .find(move |p| p.range().0 <= min && max <= p.range().1);
+
+        Self::PACKED.into_iter()
+            .filter(move |p| p.range().0 <= min && max <= p.range().1)
+            .chain(linear)
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Self::Zero => 0,
+            Self::Linear(bits) => bits,
+            Self::K13 => 17,
+            Self::K12 => 18,
+            Self::T15 => 19,
+            Self::K24 => 20,
+            Self::K23 => 21,
+            Self::T27 => 22,
+            Self::K35 => 23,
+            Self::K34 => 24,
+            Self::K45 => 26,
+            Self::K44 => 27,
+            Self::T37 => 29,
+        }
+    }
+
+    fn range(self) -> (i32, i32) {
+        match self {
+            Self::Zero => (0, 0),
+            Self::Linear(bits) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+            Self::K13 | Self::K12 | Self::T15 => (-1, 1),
+            Self::K24 | Self::K23 | Self::T27 => (-2, 2),
+            Self::K35 | Self::K34 => (-3, 3),
+            Self::K45 | Self::K44 => (-4, 4),
+            Self::T37 => (-5, 5),
+        }
+    }
+
+    //k fillers ending in 3 or 5 can store two zeros in a single bit
+    fn zero_pairs(self) -> bool {
+        matches!(self, Self::K13 | Self::K24 | Self::K35 | Self::K45)
+    }
+
+    fn pack(self, values: &[i32], put: &mut impl FnMut(u64, u8)) {
+        match self {
+            Self::Zero => {},
+            Self::Linear(bits) => {
+                let middle = 1 << (bits - 1);
+                for v in values {
+                    put((v + middle) as u64, bits);
+                }
+            },
+            Self::T15 => pack_txx(values, put, 5, 3, 3),
+            Self::T27 => pack_txx(values, put, 7, 5, 3),
+            Self::T37 => pack_txx(values, put, 7, 11, 2),
+            _ => self.pack_k(values, put),
+        }
+    }
+
+    fn pack_k(self, values: &[i32], put: &mut impl FnMut(u64, u8)) {
+        let pairs = self.zero_pairs();
+
+        let mut i = 0;
+        while i < values.len() {
+            if values[i] == 0 {
+                let next_zero = values.get(i + 1).is_none_or(|v| *v == 0);
+                if !pairs {
+                    put(0, 1);
+                } else if next_zero {
+                    put(0, 1);
+                    i += 1;
+                } else {
+                    put(1, 1);
+                    put(0, 1);
+                }
+            } else {
+                put(1, 1);
+                if pairs {
+                    put(1, 1);
+                }
+                self.pack_nonzero(values[i], put);
+            }
+            i += 1;
+        }
+    }
+
+    fn pack_nonzero(self, value: i32, put: &mut impl FnMut(u64, u8)) {
+        let index = |map: &[i32]| map.iter().position(|m| *m == value).unwrap_or(0) as u64;
+
+        match self {
+            Self::K13 | Self::K12 => put(index(&MAP_1BIT), 1),
+            Self::K24 | Self::K23 => put(index(&MAP_2BIT_NEAR), 2),
+            Self::K35 | Self::K34 if value.abs() == 1 => {
+                put(0, 1);
+                put(index(&MAP_1BIT), 1);
+            },
+            Self::K35 | Self::K34 => {
+                put(1, 1);
+                put(index(&MAP_2BIT_FAR), 2);
+            },
+            Self::K45 | Self::K44 => put(index(&MAP_3BIT), 3),
+            _ => unreachable!("{:?} isn't a k filler", self),
+        }
+    }
+}
+
+//`times` values per group, stored as digits offset by base/2, least significant first
+fn pack_txx(values: &[i32], put: &mut impl FnMut(u64, u8), bits: u8, base: i32, times: usize) {
+    let offset = base / 2;
+
+    for group in values.chunks(times) {
+        let packed = group.iter()
+            .rev()
+            .fold(0, |acc, v| acc * base + v + offset);
+        put(packed as u64, bits);
+    }
+}
+
@@ -7,10 +7,12 @@ use std::{
 pub enum AcmError {
     StreamError,
     SigError,
+    HeaderError,
     BitError,
     FillError,
     CorruptBlock,
     WriteError,
+    FormatError,
 }
 
 impl Display for AcmError {
@@ -19,10 +21,12 @@ impl Display for AcmError {
         match self {
             StreamError => write!(f, "Error reading from stream"),
             SigError => write!(f, "Invalid file signature"),
+            HeaderError => write!(f, "Header sizes don't fit the stream"),
             BitError => write!(f, "Error reading bits"),
             FillError => write!(f, "Error filling block"),
             CorruptBlock => write!(f, "Acm block may be corrupt"),
             WriteError => write!(f, "Failed to write data"),
+            FormatError => write!(f, "Audio format can't be stored as acm"),
         }
     }
 }
@@ -1,6 +1,6 @@
 use std::{collections::VecDeque, ops::Range};
 use common::{
-    BitReader,
+    BitRead,
     Vec2d,
 };
 use super::AcmError;
@@ -13,64 +13,22 @@ const MAP_3BIT: [usize; 8] = [ BUFFER_MIDDLE-4, BUFFER_MIDDLE-3, BUFFER_MIDDLE-2
 
 fn get_index_1(bit: u8) -> usize {
     MAP_1BIT[bit as usize]
-
-    /*
-    match bit {
-        0 => BUFFER_MIDDLE - 1,
-        1 => BUFFER_MIDDLE + 1,
-        _ => panic!("bit index: {} out of range", bit),
-    }
-    */
 }
 
 fn get_index_2_near(bits: u8) -> usize {
     MAP_2BIT_NEAR[bits as usize]
-
-    /*
-    match bits {
-        0b00 => BUFFER_MIDDLE - 2,
-        0b10 => BUFFER_MIDDLE - 1,
-        0b01 => BUFFER_MIDDLE + 1,
-        0b11 => BUFFER_MIDDLE + 2,
-        _ => panic!("bit index: {} out of range", bits),
-    }
-    */
 }
 
 fn get_index_2_far(bits: u8) -> usize {
     MAP_2BIT_FAR[bits as usize]
-
-    /*
-    match bits {
-        0b00 => BUFFER_MIDDLE - 3,
-        0b10 => BUFFER_MIDDLE - 2,
-        0b01 => BUFFER_MIDDLE + 2,
-        0b11 => BUFFER_MIDDLE + 3,
-        _ => panic!("bit index: {} out of range", bits),
-    }
-    */
 }
 
 fn get_index_3(bits: u8) -> usize {
     MAP_3BIT[bits as usize]
-
-    /*
-    match bits {
-        0b000 => BUFFER_MIDDLE - 4,
-        0b100 => BUFFER_MIDDLE - 3,
-        0b010 => BUFFER_MIDDLE - 2,
-        0b110 => BUFFER_MIDDLE - 1,
-        0b001 => BUFFER_MIDDLE + 1,
-        0b101 => BUFFER_MIDDLE + 2,
-        0b011 => BUFFER_MIDDLE + 3,
-        0b111 => BUFFER_MIDDLE + 4,
-        _ => panic!("bit index: {} out of range", bits),
-    }
-    */
 }
 
-pub struct FillerArgs<'a> {
-    pub reader: &'a mut BitReader,
+pub struct FillerArgs<'a, R: BitRead> {
+    pub reader: &'a mut R,
     pub packed_block: &'a mut Vec2d<i32>,
     pub amp_buffer: &'a mut [i32],
 
@@ -78,10 +36,9 @@ pub struct FillerArgs<'a> {
     pub column: usize,
 }
 
-impl<'a> FillerArgs<'a> {
+impl<R: BitRead> FillerArgs<'_, R> {
     fn set_in_column(&mut self, row: usize, value: i32) {
         self.packed_block.insert(self.column, row, value);
-        //self.packed_block.insert(row, self.column, value);
     }
 
     fn set_by_index(&mut self, row: usize, index: usize) {
@@ -112,7 +69,7 @@ pub enum Fillers {
 }
 
 impl Fillers {
-    pub fn fill(&self, args: FillerArgs) -> Result<(), AcmError> {
+    pub fn fill<R: BitRead>(&self, args: FillerArgs<R>) -> Result<(), AcmError> {
         match self {
             Self::Zero    =>  fill_zero(args),
             Self::Ret0    =>  fill_ret0(args),
@@ -158,31 +115,26 @@ impl From<u8> for Fillers {
     }
 }
 
-fn fill_zero(mut args: FillerArgs) -> Result<(), AcmError> {
+fn fill_zero<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
     for i in args.block_rows() {
         args.set_in_column(i, 0);
     }
-        Ok(())
+
+    Ok(())
 }
 
-fn fill_ret0(_args: FillerArgs) -> Result<(), AcmError> {
+fn fill_ret0<R: BitRead>(_args: FillerArgs<R>) -> Result<(), AcmError> {
     Err(AcmError::CorruptBlock)
-
-    /*
-    let width = args.packed_block.width();
-    let height = args.packed_block.height();
-
-    *args.packed_block = Vec2d::new(width, height);
-
-    Ok(())
-    */
 }
 
-fn fill_linear(mut args: FillerArgs) -> Result<(), AcmError> {
+fn fill_linear<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
+    //values are stored offset by half their range
+    let middle = 1 << (args.index - 1);
+
     for i in args.block_rows() {
-        let val = args.reader.get_bits_i64(args.index as u8).ok_or(AcmError::FillError)?;
-        let idx = 0x8_000 + val;
-        let amp = args.amp_buffer[idx as usize];
+        let val = args.reader.get_bits_u32(args.index as u8).ok_or(AcmError::FillError)? as usize;
+        let idx = BUFFER_MIDDLE + val - middle;
+        let amp = args.amp_buffer[idx];
         
         args.set_in_column(i, amp);
     }
@@ -196,7 +148,7 @@ macro_rules! get_bit {
     }};
 }
 
-fn fill_k13(mut args: FillerArgs) -> Result<(), AcmError> {
+fn fill_k13<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
     let mut iter = args.block_rows();
     while let Some(i) = iter.next() {
         if get_bit!(args.reader)? == 0 { // 0
@@ -217,9 +169,9 @@ fn fill_k13(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k12(mut args: FillerArgs) -> Result<(), AcmError> {
-    let mut iter = args.block_rows();
-    while let Some(i) = iter.next() {
+fn fill_k12<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
+    let iter = args.block_rows();
+    for i in iter {
         if get_bit!(args.reader)? == 0 { // 0
             args.set_in_column(i, 0);
         } else { // 1, X
@@ -232,7 +184,7 @@ fn fill_k12(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k24(mut args: FillerArgs) -> Result<(), AcmError> {
+fn fill_k24<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
     let mut iter = args.block_rows();
     while let Some(i) = iter.next() {
         if get_bit!(args.reader)? == 0 { // 0 
@@ -252,9 +204,9 @@ fn fill_k24(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k23(mut args: FillerArgs) -> Result<(), AcmError> {
-    let mut iter = args.block_rows();
-    while let Some(i) = iter.next() {
+fn fill_k23<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
+    let iter = args.block_rows();
+    for i in iter {
         if get_bit!(args.reader)? == 0 {
             args.set_in_column(i, 0);
         } else {
@@ -267,7 +219,7 @@ fn fill_k23(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k35(mut args: FillerArgs) -> Result<(), AcmError> {
+fn fill_k35<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
     let mut iter = args.block_rows();
     while let Some(i) = iter.next() {
         if get_bit!(args.reader)? == 0 { // 0
@@ -291,16 +243,16 @@ fn fill_k35(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k34(mut args: FillerArgs) -> Result<(), AcmError> {
-    let mut iter = args.block_rows();
-    while let Some(i) = iter.next() {
+fn fill_k34<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
+    let iter = args.block_rows();
+    for i in iter {
         if get_bit!(args.reader)? == 0 { // 0
             args.set_in_column(i, 0);
         } else if get_bit!(args.reader)? == 0 {
             let index = match get_bit!(args.reader)? { // 1, 0, X
                 0 => BUFFER_MIDDLE - 1,
                 1 => BUFFER_MIDDLE + 1,
-                _ => panic!(),
+                _ => return Err(AcmError::BitError),
             };
             args.set_by_index(i, index);
         } else { // 1, 1, XX
@@ -313,7 +265,7 @@ fn fill_k34(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k45(mut args: FillerArgs) -> Result<(), AcmError> {
+fn fill_k45<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
     let mut iter = args.block_rows();
     while let Some(i) = iter.next() {
         if get_bit!(args.reader)? == 0 {
@@ -333,9 +285,9 @@ fn fill_k45(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_k44(mut args: FillerArgs) -> Result<(), AcmError> {
-    let mut iter = args.block_rows();
-    while let Some(i) = iter.next() {
+fn fill_k44<R: BitRead>(mut args: FillerArgs<R>) -> Result<(), AcmError> {
+    let iter = args.block_rows();
+    for i in iter {
         if get_bit!(args.reader)? == 0 {
             args.set_in_column(i, 0);
         } else {
@@ -349,21 +301,33 @@ fn fill_k44(mut args: FillerArgs) -> Result<(), AcmError> {
     Ok(())
 }
 
-fn fill_txx(mut args: FillerArgs, bits: u8, base: i32, times: usize) -> Result<(), AcmError> {
+//packs `times` values in [-base/2, base/2] into one number with `bits` bits
+fn fill_txx<R: BitRead>(mut args: FillerArgs<R>, bits: u8, base: i32, times: usize) -> Result<(), AcmError> {
+    let offset = base / 2;
+    let max = base.pow(times as u32);
+
     let mut iter = args.block_rows();
     while let Some(i) = iter.next() {
-        let bits = args.reader.get_bits_u8(bits).ok_or(AcmError::FillError)?;
-        let mut digits = change_base(bits as i32, base);
+        let value = args.reader.get_bits_u8(bits).ok_or(AcmError::FillError)? as i32;
+        if value >= max {
+            return Err(AcmError::CorruptBlock);
+        }
 
-        let digit = digits.pop_back().unwrap_or(0);
-        let index = BUFFER_MIDDLE + digit as usize;
-        args.set_by_index(i, index);
+        //least significant digit goes in the first row
+        let mut digits = change_base(value, base);
+        let mut row = i;
 
-        for _ in 0 .. times-1 {
-            let digit = digits.pop_back().unwrap_or(0);
-            let index = BUFFER_MIDDLE + digit as usize;
-            if let Some(next_i) = iter.next() {
-                args.set_by_index(next_i, index);
+        for t in 0 .. times {
+            let digit = digits.pop_front().unwrap_or(0);
+            let index = (BUFFER_MIDDLE as i32 + digit - offset) as usize;
+            args.set_by_index(row, index);
+
+            if t + 1 == times {
+                break;
+            }
+            match iter.next() {
+                Some(next_i) => row = next_i,
+                None => break,
             }
         }
     }
@@ -372,15 +336,15 @@ fn fill_txx(mut args: FillerArgs, bits: u8, base: i32, times: usize) -> Result<(
 }
 
 
-fn fill_t15(args: FillerArgs) -> Result<(), AcmError> {
+fn fill_t15<R: BitRead>(args: FillerArgs<R>) -> Result<(), AcmError> {
     fill_txx(args, 5, 3, 3)
 }
 
-fn fill_t27(args: FillerArgs) -> Result<(), AcmError> {
+fn fill_t27<R: BitRead>(args: FillerArgs<R>) -> Result<(), AcmError> {
     fill_txx(args, 7, 5, 3)
 }
 
-fn fill_t37(args: FillerArgs) -> Result<(), AcmError> {
+fn fill_t37<R: BitRead>(args: FillerArgs<R>) -> Result<(), AcmError> {
     fill_txx(args, 7, 11, 2)
 }
 
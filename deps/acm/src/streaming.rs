@@ -0,0 +1,332 @@
+use crate::{
+    SampleType,
+    error::AcmError,
+    decoder::{ Decoder, Header },
+};
+
+use common::{
+    BitReader,
+    SliceBitReader,
+    Stream,
+};
+use std::io::{
+    self,
+    Read,
+    SeekFrom,
+};
+
+//blocks between saved decoder states, a seek decodes at most this many blocks
+const CHECKPOINT_INTERVAL: usize = 32;
+const READ_SIZE: usize = 4096;
+
+//everything needed to restart decoding at the start of `block`
+struct Checkpoint {
+    block: usize,
+    bit_position: u64,
+    wrap_state: Vec<i32>,
+}
+
+/// Decodes an ACM stream block by block as it is read
+///
+/// Blocks come out of the iterator as interleaved PCM samples, or through `Read`
+/// as little endian 16 bit samples. Because each block depends on the ones
+/// before it, the decoder state is saved every few blocks so that seeking back
+/// (e.g. to a loop point) doesn't have to decode the track from the start again.
+pub struct AcmDecoder<S: Stream> {
+    stream: S,
+    //bytes read but not yet decoded, `buffer_bit` bits of which are already used
+    buffer: Vec<u8>,
+    buffer_bit: u64,
+    decoder: Decoder,
+    channels: u32,
+
+    //stream offset just past the bytes in `buffer`
+    stream_offset: u64,
+    stream_end: bool,
+    //the data ran out, the rest of the track is silence
+    exhausted: bool,
+
+    checkpoints: Vec<Checkpoint>,
+    next_block: usize,
+    block: Vec<SampleType>,
+    block_pos: usize,
+    position: u64,
+
+    //second byte of a sample split across two reads
+    partial_byte: Option<u8>,
+}
+
+impl<S: Stream> AcmDecoder<S> {
+    pub fn new(mut stream: S, force_channels: Option<i32>) -> Result<Self, AcmError> {
+        let start = stream.stream_position().map_err(|_| AcmError::StreamError)?;
+
+        let mut header_data = [0u8; Header::SIZE];
+        stream.read_exact(&mut header_data).map_err(|_| AcmError::SigError)?;
+        let header = Header::read(&mut BitReader::new(header_data))?;
+        let decoder = Decoder::new(header)?;
+
+        //a corrupt sample count would otherwise pad gigabytes of silence after the data
+        let end = stream.seek(SeekFrom::End(0)).map_err(|_| AcmError::StreamError)?;
+        let data_bits = end.saturating_sub(start + Header::SIZE as u64) * 8;
+        let max_blocks = data_bits / header.min_block_bits() + 1;
+        if header.total_values as u64 > max_blocks * header.block_len() as u64 {
+            return Err(AcmError::HeaderError);
+        }
+        stream.seek(SeekFrom::Start(start + Header::SIZE as u64)).map_err(|_| AcmError::StreamError)?;
+
+        //like libacm, a forced count overrides the header (many Fallout sfx claim
+        //to be stereo but are mono), and a header without channels reads as mono
+        let channels = match force_channels {
+            Some(c) if c > 0 => c as u32,
+            _ => (header.channels as u32).max(1),
+        };
+
+        let stream_offset = start + Header::SIZE as u64;
+        let checkpoints = vec![Checkpoint {
+            block: 0,
+            bit_position: stream_offset * 8,
+            wrap_state: decoder.wrap_state().to_vec(),
+        }];
+
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            buffer_bit: 0,
+            decoder,
+            channels,
+
+            stream_offset,
+            stream_end: false,
+            exhausted: false,
+
+            checkpoints,
+            next_block: 0,
+            block: Vec::new(),
+            block_pos: 0,
+            position: 0,
+
+            partial_byte: None,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.decoder.header
+    }
+
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.decoder.header.sample_rate as u32
+    }
+
+    /// Sample count over all channels
+    pub fn total_samples(&self) -> u64 {
+        self.decoder.header.total_values as u64
+    }
+
+    /// Index of the next sample to be returned, counted over all channels
+    pub fn sample_position(&self) -> u64 {
+        self.position
+    }
+
+    /// Moves playback to `sample`, counted like `sample_position`
+    pub fn seek_to_sample(&mut self, sample: u64) -> Result<(), AcmError> {
+        let sample = sample.min(self.total_samples());
+        let block_len = self.decoder.header.block_len() as u64;
+        let target_block = (sample / block_len) as usize;
+        let offset = (sample % block_len) as usize;
+
+        self.partial_byte = None;
+
+        if sample == self.total_samples() {
+            self.block.clear();
+            self.block_pos = 0;
+            self.position = sample;
+            return Ok(());
+        }
+
+        let in_current_block = !self.block.is_empty() && self.next_block == target_block + 1;
+        if !in_current_block {
+            let checkpoint = self.checkpoints.partition_point(|c| c.block <= target_block) - 1;
+            let ahead_of_checkpoint = self.next_block >= self.checkpoints[checkpoint].block;
+
+            if !(ahead_of_checkpoint && self.next_block <= target_block) {
+                self.restore(checkpoint)?;
+            }
+            while self.next_block <= target_block {
+                self.decode_next()?;
+            }
+        }
+
+        self.block_pos = offset;
+        self.position = sample;
+        Ok(())
+    }
+
+    /// Returns the samples up to the end of the current block
+    pub fn read_block(&mut self) -> Result<Option<Vec<SampleType>>, AcmError> {
+        let remaining = self.total_samples() - self.position;
+        if remaining == 0 {
+            return Ok(None);
+        }
+
+        if self.block_pos >= self.block.len() {
+            self.decode_next()?;
+        }
+
+        let end = self.block.len().min(self.block_pos + remaining as usize);
+        let samples = self.block[self.block_pos .. end].to_vec();
+
+        self.position += samples.len() as u64;
+        self.block_pos = end;
+        Ok(Some(samples))
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn next_sample(&mut self) -> Result<Option<SampleType>, AcmError> {
+        if self.position >= self.total_samples() {
+            return Ok(None);
+        }
+
+        if self.block_pos >= self.block.len() {
+            self.decode_next()?;
+        }
+
+        let sample = self.block[self.block_pos];
+        self.block_pos += 1;
+        self.position += 1;
+        Ok(Some(sample))
+    }
+
+    fn bit_position(&self) -> u64 {
+        self.stream_offset * 8 - (self.buffer.len() as u64 * 8 - self.buffer_bit)
+    }
+
+    fn decode_next(&mut self) -> Result<(), AcmError> {
+        let is_new_checkpoint = self.checkpoints.last()
+            .is_none_or(|c| c.block < self.next_block);
+
+        if self.next_block.is_multiple_of(CHECKPOINT_INTERVAL) && is_new_checkpoint {
+            self.checkpoints.push(Checkpoint {
+                block: self.next_block,
+                bit_position: self.bit_position(),
+                wrap_state: self.decoder.wrap_state().to_vec(),
+            });
+        }
+
+        self.block.clear();
+        if !self.exhausted {
+            self.refill()?;
+
+            let mut reader = SliceBitReader::new(&self.buffer);
+            let decoded = reader.skip_bits(self.buffer_bit)
+                .ok_or(AcmError::BitError)
+                .and_then(|_| self.decoder.decode_block(&mut reader));
+
+            match decoded {
+                Ok(()) => {
+                    self.buffer_bit = reader.position();
+                    self.block.extend(self.decoder.samples());
+                },
+                Err(AcmError::BitError | AcmError::FillError) => self.exhausted = true,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.exhausted {
+            self.block.resize(self.decoder.header.block_len(), 0);
+        }
+
+        self.next_block += 1;
+        self.block_pos = 0;
+        Ok(())
+    }
+
+    //makes sure the buffer holds a whole block, unless the stream ends first
+    fn refill(&mut self) -> Result<(), AcmError> {
+        let used = (self.buffer_bit / 8) as usize;
+        self.buffer.drain(.. used);
+        self.buffer_bit %= 8;
+
+        let needed = self.decoder.header.max_block_bytes();
+        let mut buf = [0u8; READ_SIZE];
+
+        while !self.stream_end && self.buffer.len() < needed {
+            let want = (needed - self.buffer.len()).min(READ_SIZE);
+            let read = self.stream.read(&mut buf[.. want]).map_err(|_| AcmError::StreamError)?;
+
+            if read == 0 {
+                self.stream_end = true;
+            } else {
+                self.buffer.extend_from_slice(&buf[.. read]);
+                self.stream_offset += read as u64;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn restore(&mut self, checkpoint: usize) -> Result<(), AcmError> {
+        let Checkpoint { block, bit_position, ref wrap_state } = self.checkpoints[checkpoint];
+        self.decoder.set_wrap_state(wrap_state);
+
+        let byte = bit_position / 8;
+        self.stream.seek(SeekFrom::Start(byte)).map_err(|_| AcmError::StreamError)?;
+        self.stream_offset = byte;
+        self.stream_end = false;
+        self.exhausted = false;
+        self.buffer.clear();
+        self.buffer_bit = bit_position % 8;
+
+        self.next_block = block;
+        self.block.clear();
+        self.block_pos = 0;
+
+        Ok(())
+    }
+}
+
+impl<S: Stream> Iterator for AcmDecoder<S> {
+    type Item = Result<Vec<SampleType>, AcmError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_block().transpose()
+    }
+}
+
+impl<S: Stream> Read for AcmDecoder<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        if !buf.is_empty() {
+            if let Some(byte) = self.partial_byte.take() {
+                buf[0] = byte;
+                written = 1;
+            }
+        }
+
+        while written < buf.len() {
+            let sample = self.next_sample()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let Some(sample) = sample else { break };
+
+            let [low, high] = sample.to_le_bytes();
+            buf[written] = low;
+            written += 1;
+
+            if written < buf.len() {
+                buf[written] = high;
+                written += 1;
+            } else {
+                self.partial_byte = Some(high);
+            }
+        }
+
+        Ok(written)
+    }
+}
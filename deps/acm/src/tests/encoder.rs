@@ -0,0 +1,155 @@
+use std::io::Cursor;
+use crate::{
+    Acm,
+    AcmDecoder,
+    Encoder,
+    error::AcmError,
+};
+
+fn roundtrip(acm: &Acm, encoder: Encoder) -> Vec<u8> {
+    let data = encoder.encode(acm).unwrap();
+    let decoded = Acm::open(Cursor::new(data.clone()), None).unwrap();
+
+    assert_eq!(decoded.channels, acm.channels);
+    assert_eq!(decoded.sample_rate, acm.sample_rate);
+    assert_eq!(decoded.samples, acm.samples);
+    data
+}
+
+//largest difference from the input past the first block, where `Encoder` has to
+//quantize audio that doesn't start from silence
+fn lossy_roundtrip(acm: &Acm, encoder: Encoder) -> (Vec<u8>, i32) {
+    let data = encoder.encode(acm).unwrap();
+    let decoder = AcmDecoder::new(Cursor::new(data.clone()), None).unwrap();
+    let skip = decoder.header().block_len();
+    let decoded = Acm::open(Cursor::new(data.clone()), None).unwrap();
+
+    assert_eq!(decoded.samples.len(), acm.samples.len());
+    let error = decoded.samples.iter()
+        .zip(&acm.samples)
+        .skip(skip)
+        .map(|(a, b)| (*a as i32 - *b as i32).abs())
+        .max()
+        .unwrap_or(0);
+    (data, error)
+}
+
+fn noise(len: usize, seed: u32, f: impl Fn(u32) -> i16) -> Vec<i16> {
+    let mut seed = seed;
+    (0 .. len)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            f(seed >> 8)
+        })
+        .collect()
+}
+
+#[test]
+fn stereo_test() {
+    let samples = (0 .. 22050)
+        .flat_map(|i| {
+            let t = i as f32 / 22050.0;
+            let left = (t * 440.0 * std::f32::consts::TAU).sin() * 12000.0;
+            let right = (t * 660.0 * std::f32::consts::TAU).sin() * 3000.0;
+            [left as i16, right as i16]
+        })
+        .collect();
+
+    let acm = Acm { channels: 2, sample_rate: 22050, samples };
+    let (_, error) = lossy_roundtrip(&acm, Encoder::default());
+    assert!(error <= 2, "{error}");
+
+    let data = acm.write_to_acm().unwrap();
+    assert!(data.len() < acm.samples.len() * 2);
+}
+
+#[test]
+fn fillers_test() {
+    //small amplitudes with lots of zeros, so every packed filler gets picked for some block
+    for range in 1 ..= 6u32 {
+        for zeros in [0, 2, 4] {
+            let samples = noise(1000, range * 7 + zeros, |x| {
+                if x % 5 < zeros {
+                    0
+                } else {
+                    ((x / 5) % (2 * range + 1)) as i16 - range as i16
+                }
+            });
+
+            let scaled = samples.iter().map(|s| s * 37).collect();
+            for samples in [samples, scaled] {
+                let acm = Acm { channels: 1, sample_rate: 22050, samples };
+                roundtrip(&acm, Encoder::new(16).unwrap().with_level(0).unwrap());
+            }
+        }
+    }
+
+    let acm = Acm {
+        channels: 1,
+        sample_rate: 11025,
+        samples: noise(5000, 1, |x| x as i16),
+    };
+    roundtrip(&acm, Encoder::new(4095).unwrap().with_level(0).unwrap());
+
+    let acm = Acm {
+        channels: 1,
+        sample_rate: 11025,
+        samples: vec![i16::MIN, i16::MAX, -1, 0, 1, i16::MIN, 5],
+    };
+    roundtrip(&acm, Encoder::new(3).unwrap().with_level(0).unwrap());
+}
+
+#[test]
+fn silence_test() {
+    let acm = Acm { channels: 2, sample_rate: 22050, samples: vec![0; 2560 - 7] };
+    let data = roundtrip(&acm, Encoder::new(256).unwrap().with_level(0).unwrap());
+
+    //header, then 25 bits per block
+    assert_eq!(data.len(), 14 + (10 * 25usize).div_ceil(8));
+
+    //8 columns per block, so 60 bits each
+    let data = roundtrip(&acm, Encoder::new(256).unwrap());
+    assert_eq!(data.len(), 14 + (2 * 60usize).div_ceil(8));
+
+    let empty = Acm { channels: 1, sample_rate: 22050, samples: Vec::new() };
+    assert_eq!(roundtrip(&empty, Encoder::default()).len(), 14);
+}
+
+#[test]
+fn format_test() {
+    assert!(matches!(Encoder::new(0), Err(AcmError::FormatError)));
+    assert!(matches!(Encoder::new(4096), Err(AcmError::FormatError)));
+
+    let acm = Acm { channels: 1, sample_rate: 96000, samples: vec![0; 4] };
+    assert!(matches!(acm.write_to_acm(), Err(AcmError::FormatError)));
+
+    let acm = Acm { channels: 0, sample_rate: 22050, samples: vec![0; 4] };
+    assert!(matches!(acm.write_to_acm(), Err(AcmError::FormatError)));
+}
+
+#[test]
+fn levels_test() {
+    let level_of = |samples: Vec<i16>, encoder: Encoder| {
+        let acm = Acm { channels: 1, sample_rate: 22050, samples };
+        let data = roundtrip(&acm, encoder);
+        AcmDecoder::new(Cursor::new(data), None).unwrap().header().level
+    };
+
+    for level in 1 ..= 6 {
+        let encoder = Encoder::new(64).unwrap().with_level(level).unwrap();
+        assert_eq!(level_of(vec![0; 5000], encoder), level);
+    }
+
+    //the transform input grows too fast for louder or noisier audio, which gets quantized
+    let encoder = Encoder::new(64).unwrap();
+    for samples in [vec![50; 5000], noise(5000, 3, |x| (x % 3) as i16 - 1), noise(5000, 4, |x| x as i16)] {
+        let acm = Acm { channels: 1, sample_rate: 22050, samples };
+        let (data, error) = lossy_roundtrip(&acm, encoder);
+        assert!(error <= 4, "{error}");
+        assert_eq!(AcmDecoder::new(Cursor::new(data), None).unwrap().header().level, 3);
+    }
+
+    assert!(Encoder::new(4095).unwrap().with_level(8).is_ok());
+    assert!(matches!(Encoder::new(4095).unwrap().with_level(9), Err(AcmError::FormatError)));
+}
+
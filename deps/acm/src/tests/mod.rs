@@ -0,0 +1,227 @@
+use std::io::Cursor;
+use common::Wav;
+use crate::{
+    Acm,
+    error::AcmError,
+};
+
+mod encoder;
+mod streaming;
+
+//packs values least significant bit first, like the decoder reads them
+#[derive(Default)]
+struct Bits {
+    data: Vec<u8>,
+    used: usize,
+}
+
+impl Bits {
+    fn put(&mut self, value: u32, bits: usize) -> &mut Self {
+        for i in 0 .. bits {
+            if self.used.is_multiple_of(8) {
+                self.data.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.data.last_mut().unwrap() |= bit << (self.used % 8);
+            self.used += 1;
+        }
+        self
+    }
+
+    fn header(&mut self, total_values: u32, level: u32, rows: u32) -> &mut Self {
+        self.put(0x03_28_97, 24)
+            .put(1, 8)
+            .put(total_values & 0xFFFF, 16)
+            .put(total_values >> 16, 16)
+            .put(1, 16)
+            .put(22050, 16)
+            .put(level, 4)
+            .put(rows, 12)
+    }
+
+    fn open(&self) -> Result<Acm, AcmError> {
+        Acm::open(Cursor::new(self.data.clone()), None)
+    }
+}
+
+#[test]
+fn header_test() {
+    let mut bits = Bits::default();
+    bits.header(0, 0, 1);
+    assert_eq!(bits.data.len(), crate::decoder::Header::SIZE);
+
+    let acm = bits.open().unwrap();
+    assert_eq!(acm.channels, 1);
+    assert_eq!(acm.sample_rate, 22050);
+    assert!(acm.samples.is_empty());
+
+    let mut bad = Bits::default();
+    bad.put(0x03_28_98, 24).put(1, 8);
+    assert!(matches!(bad.open(), Err(AcmError::SigError)));
+}
+
+#[test]
+fn corrupt_header_test() {
+    //2^15 columns of 4095 rows would need half a gigabyte per block
+    let mut bits = Bits::default();
+    bits.header(1, 15, 0xFFF);
+    assert!(matches!(bits.open(), Err(AcmError::HeaderError)));
+
+    //billions of samples with no data behind them
+    let mut bits = Bits::default();
+    bits.header(u32::MAX, 0, 1);
+    assert!(matches!(bits.open(), Err(AcmError::HeaderError)));
+}
+
+#[test]
+fn linear_filler_test() {
+    let mut bits = Bits::default();
+    bits.header(4, 0, 4)
+        //amplitude table: 2^2 steps of 10
+        .put(2, 4).put(10, 16)
+        //3 bit linear filler, stored offset by 4
+        .put(3, 5)
+        .put(0, 3).put(4, 3).put(7, 3).put(5, 3);
+
+    let acm = bits.open().unwrap();
+    assert_eq!(acm.samples, [-40, 0, 30, 10]);
+}
+
+#[test]
+fn packed_fillers_test() {
+    let mut bits = Bits::default();
+    bits.header(6, 0, 3)
+        //t15: three values in [-1, 1] from 5 bits, 0 + 2*3 + 1*9
+        .put(1, 4).put(7, 16)
+        .put(19, 5)
+        .put(15, 5)
+        //k13: 0 -> two zeros, 1 1 x -> +-1
+        .put(1, 4).put(3, 16)
+        .put(17, 5)
+        .put(0, 1)
+        .put(0b1, 1).put(0b1, 1).put(1, 1);
+
+    let acm = bits.open().unwrap();
+    assert_eq!(acm.samples, [-7, 7, 0, 0, 0, 3]);
+}
+
+#[test]
+fn juggle_test() {
+    let (a, b, c, d) = (5, -3, 12, 7);
+
+    //two columns, one row per block, values carry over between blocks
+    let mut bits = Bits::default();
+    bits.header(4, 1, 1);
+    for (x, y) in [(a, b), (c, d)] {
+        bits.put(4, 4).put(1, 16)
+            .put(6, 5).put((x + 32) as u32, 6)
+            .put(6, 5).put((y + 32) as u32, 6);
+    }
+
+    let acm = bits.open().unwrap();
+    let expected = [
+        (a + 1) >> 1,
+        (2 * a - b + 1) >> 1,
+        (2 * b + a + c + 1) >> 1,
+        (2 * c - (b + d) + 1) >> 1,
+    ];
+    assert_eq!(acm.samples, expected.map(|x| x as i16));
+}
+
+#[test]
+fn change_base_test() {
+    use crate::fillers::tests::change_base;
+
+    //least significant digit first, as the packed fillers spread them over rows
+    assert_eq!(change_base(17, 3), [2, 2, 1]);
+    assert_eq!(change_base(4, 5), [4]);
+    assert_eq!(change_base(0, 11), [0]);
+}
+
+#[test]
+fn truncated_test() {
+    let mut bits = Bits::default();
+    bits.header(8, 0, 4)
+        .put(2, 4).put(10, 16)
+        .put(3, 5)
+        .put(0, 3).put(4, 3).put(7, 3).put(5, 3)
+        //second block is cut off
+        .put(2, 4);
+
+    let acm = bits.open().unwrap();
+    assert_eq!(acm.samples, [-40, 0, 30, 10, 0, 0, 0, 0]);
+
+    //filler codes that libacm rejects
+    let mut bits = Bits::default();
+    bits.header(4, 0, 4)
+        .put(2, 4).put(10, 16)
+        .put(1, 5);
+    assert!(matches!(bits.open(), Err(AcmError::CorruptBlock)));
+}
+
+#[test]
+fn wav_test() {
+    let mut bits = Bits::default();
+    bits.header(3, 0, 4)
+        .put(2, 4).put(10, 16)
+        .put(3, 5)
+        .put(0, 3).put(4, 3).put(7, 3).put(5, 3);
+
+    let acm = bits.open().unwrap();
+    let wav = Wav::open(&mut Cursor::new(acm.write_to_wav().unwrap())).unwrap();
+    assert_eq!(wav.channels, 1);
+    assert_eq!(wav.block_align(), 2);
+    assert_eq!(wav.samples, [-40, 0, 30]);
+
+    //the forced channel count wins, the data is padded to whole frames
+    let acm = Acm::open(Cursor::new(bits.data.clone()), Some(2)).unwrap();
+    let data = acm.write_to_wav().unwrap();
+    assert_eq!(u16::from_le_bytes([data[32], data[33]]), 4);
+
+    let wav = Wav::open(&mut Cursor::new(data)).unwrap();
+    assert_eq!(wav.channels, 2);
+    assert_eq!(wav.samples, [-40, 0, 30, 0]);
+}
+
+//stereo, level 3 with 32 rows, three blocks of linear filled columns cut short at 700 values
+const TEST_DATA: &[u8] = include_bytes!("./stereo.acm");
+
+#[test]
+fn subband_test() {
+    let acm = Acm::open(Cursor::new(TEST_DATA.to_vec()), None).unwrap();
+    assert_eq!(acm.channels, 2);
+    assert_eq!(acm.samples.len(), 700);
+    assert_eq!(acm.samples[.. 12], [2, 2, 7, 2, 16, 9, 16, 2, 31, 18, 22, 18]);
+    assert_eq!(acm.samples.iter().map(|s| *s as i64).sum::<i64>(), 145_066);
+}
+
+//stereo, level 6 with 40 rows so the transform runs in two steps, every
+//filler code used and cut short at 5000 values
+const FILLERS_DATA: &[u8] = include_bytes!("./fillers.acm");
+//the same file decoded by a separate C decoder laid out like libacm's, 16 bit little endian
+const FILLERS_PCM: &[u8] = include_bytes!("./fillers.pcm");
+
+#[test]
+fn fillers_reference_test() {
+    let acm = Acm::open(Cursor::new(FILLERS_DATA.to_vec()), None).unwrap();
+    let expected: Vec<i16> = FILLERS_PCM.chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+
+    assert_eq!(acm.channels, 2);
+    assert_eq!(acm.samples, expected);
+}
+
+#[cfg(feature = "libacm")]
+#[test]
+fn matches_libacm_test() {
+    for data in [TEST_DATA, FILLERS_DATA] {
+        let native = Acm::open(Cursor::new(data.to_vec()), None).unwrap();
+        let c = Acm::open_libacm(Cursor::new(data.to_vec()), None).unwrap();
+
+        assert_eq!(native.channels, c.channels);
+        assert_eq!(native.sample_rate, c.sample_rate);
+        assert_eq!(native.samples, c.samples);
+    }
+}
+
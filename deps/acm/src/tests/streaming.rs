@@ -0,0 +1,96 @@
+use std::io::{ Cursor, Read };
+use super::Bits;
+use crate::{
+    Acm,
+    AcmDecoder,
+};
+
+const BLOCKS: usize = 100;
+const BLOCK_LEN: usize = 4 * 3;
+
+//a long stream of pseudo random blocks, level 2 so blocks depend on each other
+fn make_stream(total_values: u32) -> Vec<u8> {
+    let mut bits = Bits::default();
+    bits.header(total_values, 2, 3);
+
+    let mut seed = 12345u32;
+    for _ in 0 .. BLOCKS {
+        bits.put(7, 4).put(3, 16);
+        for _ in 0 .. 4 {
+            bits.put(8, 5);
+            for _ in 0 .. 3 {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                bits.put(seed >> 24, 8);
+            }
+        }
+    }
+
+    bits.data
+}
+
+fn open(data: &[u8]) -> AcmDecoder<Cursor<Vec<u8>>> {
+    AcmDecoder::new(Cursor::new(data.to_vec()), None).unwrap()
+}
+
+#[test]
+fn blocks_test() {
+    let total = (BLOCKS * BLOCK_LEN) as u32 - 5;
+    let data = make_stream(total);
+    let full = Acm::open(Cursor::new(data.clone()), None).unwrap();
+    assert_eq!(full.samples.len(), total as usize);
+
+    let blocks: Vec<_> = open(&data).collect::<Result<_, _>>().unwrap();
+    assert_eq!(blocks.len(), BLOCKS);
+    assert!(blocks[.. BLOCKS - 1].iter().all(|b| b.len() == BLOCK_LEN));
+    assert_eq!(blocks.last().unwrap().len(), BLOCK_LEN - 5);
+    assert_eq!(blocks.concat(), full.samples);
+}
+
+#[test]
+fn read_test() {
+    let data = make_stream((BLOCKS * BLOCK_LEN) as u32);
+    let full = Acm::open(Cursor::new(data.clone()), None).unwrap();
+    let expected: Vec<u8> = full.samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+    //odd sized reads split samples between calls
+    let mut decoder = open(&data);
+    let mut output = Vec::new();
+    let mut buf = [0u8; 7];
+    loop {
+        let read = decoder.read(&mut buf).unwrap();
+        if read == 0 { break; }
+        output.extend_from_slice(&buf[.. read]);
+    }
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn seek_test() {
+    let data = make_stream((BLOCKS * BLOCK_LEN) as u32);
+    let full = Acm::open(Cursor::new(data.clone()), None).unwrap().samples;
+
+    let mut decoder = open(&data);
+    let check = |decoder: &mut AcmDecoder<_>, sample: usize| {
+        decoder.seek_to_sample(sample as u64).unwrap();
+        assert_eq!(decoder.sample_position(), sample as u64);
+
+        let block = decoder.read_block().unwrap().unwrap();
+        assert_eq!(block, &full[sample .. sample + block.len()]);
+        assert!(!block.is_empty());
+    };
+
+    //forwards, backwards past checkpoints, and within a block
+    for sample in [5, 700, 1190, 13, 400, 401, 399, 0, 1199] {
+        check(&mut decoder, sample);
+    }
+
+    decoder.seek_to_sample(u64::MAX).unwrap();
+    assert_eq!(decoder.sample_position(), full.len() as u64);
+    assert!(decoder.read_block().unwrap().is_none());
+
+    //loop back to the start after playing to the end
+    decoder.seek_to_sample(0).unwrap();
+    let again: Vec<_> = decoder.collect::<Result<Vec<_>, _>>().unwrap().concat();
+    assert_eq!(again, full);
+}
@@ -0,0 +1,134 @@
+//! Critter animation numbers and the art suffix letters they use
+//!
+//! Critter art is named `<base><weapon letter><animation letter>.frm`,
+//! e.g. `hmjmpsab.frm` is the unarmed walk of `hmjmps`.
+
+/// Critter animations, numbered like the engine's animation ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Anim {
+    Stand = 0,
+    Walk = 1,
+    JumpBegin = 2,
+    JumpEnd = 3,
+    ClimbLadder = 4,
+    Falling = 5,
+    UpStairsRight = 6,
+    UpStairsLeft = 7,
+    DownStairsRight = 8,
+    DownStairsLeft = 9,
+    MagicHandsGround = 10,
+    MagicHandsMiddle = 11,
+    MagicHandsUp = 12,
+    Dodge = 13,
+    HitFromFront = 14,
+    HitFromBack = 15,
+    ThrowPunch = 16,
+    KickLeg = 17,
+    Throw = 18,
+    Running = 19,
+    FallBack = 20,
+    FallFront = 21,
+    BadLanding = 22,
+    BigHole = 23,
+    CharredBody = 24,
+    ChunksOfFlesh = 25,
+    DancingAutofire = 26,
+    Electrify = 27,
+    SlicedInHalf = 28,
+    BurnedToNothing = 29,
+    ElectrifiedToNothing = 30,
+    ExplodedToNothing = 31,
+    MeltedToNothing = 32,
+    FireDance = 33,
+    FallBackBlood = 34,
+    FallFrontBlood = 35,
+    ProneToStanding = 36,
+    BackToStanding = 37,
+    TakeOut = 38,
+    PutAway = 39,
+    Parry = 40,
+    Thrust = 41,
+    Swing = 42,
+    Point = 43,
+    Unpoint = 44,
+    FireSingle = 45,
+    FireBurst = 46,
+    FireContinuous = 47,
+    FallBackSf = 48,
+    FallFrontSf = 49,
+    BadLandingSf = 50,
+    BigHoleSf = 51,
+    CharredBodySf = 52,
+    ChunksOfFleshSf = 53,
+    DancingAutofireSf = 54,
+    ElectrifySf = 55,
+    SlicedInHalfSf = 56,
+    BurnedToNothingSf = 57,
+    ElectrifiedToNothingSf = 58,
+    ExplodedToNothingSf = 59,
+    MeltedToNothingSf = 60,
+    FireDanceSf = 61,
+    FallBackBloodSf = 62,
+    FallFrontBloodSf = 63,
+    CalledShotPic = 64,
+}
+
+/// Weapon a critter holds, numbered like the engine's weapon animation codes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WeaponAnim {
+    #[default]
+    None = 0,
+    Knife = 1,
+    Club = 2,
+    Hammer = 3,
+    Spear = 4,
+    Pistol = 5,
+    Smg = 6,
+    Shotgun = 7,
+    LaserRifle = 8,
+    Minigun = 9,
+    Launcher = 10,
+}
+
+/// The two letter suffix of critter art for an animation
+///
+/// Returns `None` for weapon animations without a weapon
+pub fn art_code(anim: Anim, weapon: WeaponAnim) -> Option<(char, char)> {
+    suffix(anim as u8, weapon as u8)
+}
+
+//`art_code` for the raw numbers stored in a FID, unknown animations and weapons give `None`
+pub(crate) fn suffix(anim: u8, weapon: u8) -> Option<(char, char)> {
+    if anim > Anim::CalledShotPic as u8 || weapon > WeaponAnim::Launcher as u8 {
+        return None;
+    }
+
+    let offset = |base: u8, n: u8| (base + n) as char;
+    let weapon_code = |weapon: u8| offset(b'd', weapon - 1);
+    let knife = WeaponAnim::Knife as u8;
+    let spear = WeaponAnim::Spear as u8;
+
+    let code = match anim {
+        38 ..= 47 => {
+            if weapon == 0 {
+                return None;
+            }
+            (weapon_code(weapon), offset(b'c', anim - 38))
+        },
+        36 => ('c', 'h'),
+        37 => ('c', 'j'),
+        64 => ('n', 'a'),
+        48 .. => ('r', offset(b'a', anim - 48)),
+        20 .. => ('b', offset(b'a', anim - 20)),
+        18 if weapon == knife => ('d', 'm'),
+        18 if weapon == spear => ('g', 'm'),
+        //armed critters have their own dodge, stand and walk
+        13 if weapon > 0 => (weapon_code(weapon), 'e'),
+        0 | 1 if weapon > 0 => (weapon_code(weapon), offset(b'a', anim)),
+        _ => ('a', offset(b'a', anim)),
+    };
+
+    Some(code)
+}
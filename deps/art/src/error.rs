@@ -0,0 +1,41 @@
+use crate::Fid;
+
+use frm::error::FrmError;
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+    io,
+};
+
+#[derive(Debug)]
+pub enum ArtError {
+    ReadError(io::Error),
+    /// No file with this path in the archive
+    NotFound(String),
+    /// The archive entry couldn't be unpacked
+    UnpackError(Box<dyn Error>),
+    /// The FID's type, id or animation isn't in the art lists
+    InvalidFid(Fid),
+    FrmError(FrmError),
+}
+
+impl Display for ArtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use ArtError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading art list: {}", e),
+            NotFound(path) => write!(f, "Art file {} not found", path),
+            UnpackError(e) => write!(f, "Error unpacking art file: {}", e),
+            InvalidFid(fid) => write!(f, "No art for FID {:#010x}", fid.0),
+            FrmError(e) => write!(f, "Error reading frm: {}", e),
+        }
+    }
+}
+
+impl Error for ArtError {}
+
+impl From<FrmError> for ArtError {
+    fn from(e: FrmError) -> Self {
+        ArtError::FrmError(e)
+    }
+}
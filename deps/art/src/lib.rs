@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests;
+
+pub mod anim;
+pub mod error;
+pub mod lst;
+
+pub use anim::{ Anim, WeaponAnim };
+pub use error::ArtError;
+pub use lst::{ LstEntry, LstFile };
+
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReadError,
+    BinaryWriteError,
+    Stream,
+    readers::ReadMode,
+};
+use dat::DatFile;
+use frm::FrmFile;
+
+use std::io::{ Cursor, Seek, Write };
+
+/// Art categories, numbered like the type bits of a FID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ArtType {
+    Items = 0,
+    Critters = 1,
+    Scenery = 2,
+    Walls = 3,
+    Tiles = 4,
+    Misc = 5,
+    Interface = 6,
+    Inventory = 7,
+    Heads = 8,
+    Backgrounds = 9,
+    Skilldex = 10,
+}
+
+impl ArtType {
+    pub const ALL: [ArtType; 11] = [
+        Self::Items,
+        Self::Critters,
+        Self::Scenery,
+        Self::Walls,
+        Self::Tiles,
+        Self::Misc,
+        Self::Interface,
+        Self::Inventory,
+        Self::Heads,
+        Self::Backgrounds,
+        Self::Skilldex,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    /// Directory under `art`, also the name of the type's `.lst`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Items => "items",
+            Self::Critters => "critters",
+            Self::Scenery => "scenery",
+            Self::Walls => "walls",
+            Self::Tiles => "tiles",
+            Self::Misc => "misc",
+            Self::Interface => "intrface",
+            Self::Inventory => "inven",
+            Self::Heads => "heads",
+            Self::Backgrounds => "backgrnd",
+            Self::Skilldex => "skilldex",
+        }
+    }
+
+    /// e.g. `art\intrface\intrface.lst`
+    pub fn lst_path(self) -> String {
+        format!("art\\{0}\\{0}.lst", self.name())
+    }
+}
+
+/// Art reference packed into 32 bits
+///
+/// * bits 0-11 - id, the line of the type's `.lst`
+/// * bits 12-15 - weapon animation for critters, fidget number for heads
+/// * bits 16-23 - animation
+/// * bits 24-27 - `ArtType`
+/// * bits 28-30 - direction + 1 for art stored a direction per file, 0 for a single `.frm`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fid(pub u32);
+
+impl Fid {
+    pub fn new(art_type: ArtType, id: u16, anim: u8, weapon: u8, direction: u8) -> Self {
+        Self(
+            ((direction as u32 & 0x7) << 28) |
+            ((art_type as u32 & 0xF) << 24) |
+            ((anim as u32) << 16) |
+            ((weapon as u32 & 0xF) << 12) |
+            (id as u32 & 0xFFF)
+        )
+    }
+
+    pub fn art_type(self) -> Option<ArtType> {
+        ArtType::from_u8(((self.0 >> 24) & 0xF) as u8)
+    }
+
+    pub fn id(self) -> u16 {
+        (self.0 & 0xFFF) as u16
+    }
+
+    pub fn weapon(self) -> u8 {
+        ((self.0 >> 12) & 0xF) as u8
+    }
+
+    pub fn anim(self) -> u8 {
+        ((self.0 >> 16) & 0xFF) as u8
+    }
+
+    pub fn direction(self) -> u8 {
+        ((self.0 >> 28) & 0x7) as u8
+    }
+
+    fn with_id(self, id: u32) -> Self {
+        Self((self.0 & !0xFFF) | (id & 0xFFF))
+    }
+}
+
+impl BinRead for Fid {
+    fn bin_read<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode) -> Result<Self, BinaryReadError> {
+        u32::bin_read(stream, mode).map(Fid)
+    }
+}
+
+impl BinWrite for Fid {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> {
+        self.0.bin_write(output, mode)
+    }
+}
+
+//talking head animations, first and second letter of the file suffix
+const HEAD_CODE_1: &[u8; 12] = b"gggnnnbbbgnb";
+const HEAD_CODE_2: &[u8; 12] = b"vfngfbnfvppp";
+
+//deaths every critter shares with its alias instead of having their own
+const ALIASED_ANIMS: [Anim; 8] = [
+    Anim::Electrify,
+    Anim::BurnedToNothing,
+    Anim::ElectrifiedToNothing,
+    Anim::ElectrifySf,
+    Anim::BurnedToNothingSf,
+    Anim::ElectrifiedToNothingSf,
+    Anim::FireDance,
+    Anim::CalledShotPic,
+];
+
+/// The `.lst` of every art type, resolves FIDs to file paths
+///
+/// Paths are built like the original engine builds them:
+///
+/// * critters - `<name><weapon letter><anim letter>.frm`, or `.fr0` to `.fr5`
+///   for art stored a direction per file, see `anim::art_code`
+/// * heads - `<name><reaction><kind>.frm`, fidgets add their number
+/// * everything else - the `.lst` name as is
+#[derive(Debug, Default, Clone)]
+pub struct ArtLists {
+    lists: [LstFile; 11],
+}
+
+impl ArtLists {
+    /// Loads every art list from a DAT archive, lists that aren't there stay empty
+    pub fn load(dat: &DatFile) -> Result<Self, ArtError> {
+        let mut this = Self::default();
+        for art_type in ArtType::ALL {
+            match read_file(dat, &art_type.lst_path()) {
+                Ok(data) => this.set(art_type, LstFile::from_bytes(&data)),
+                Err(ArtError::NotFound(_)) => { },
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(this)
+    }
+
+    pub fn list(&self, art_type: ArtType) -> &LstFile {
+        &self.lists[art_type as usize]
+    }
+
+    pub fn set(&mut self, art_type: ArtType, list: LstFile) {
+        self.lists[art_type as usize] = list;
+    }
+
+    /// Path of the art `fid` refers to, lower case like DAT paths
+    pub fn path(&self, fid: Fid) -> Option<String> {
+        let fid = self.alias(fid);
+        let art_type = fid.art_type()?;
+        let name = &self.list(art_type).get(fid.id() as usize)?.name;
+        let dir = art_type.name();
+
+        let path = match art_type {
+            ArtType::Critters => {
+                let (first, second) = anim::suffix(fid.anim(), fid.weapon())?;
+                match fid.direction() {
+                    0 => format!("art\\{}\\{}{}{}.frm", dir, name, first, second),
+                    d => format!("art\\{}\\{}{}{}.fr{}", dir, name, first, second, d - 1),
+                }
+            },
+            ArtType::Heads => {
+                let anim = fid.anim() as usize;
+                let first = *HEAD_CODE_1.get(anim)? as char;
+                let second = HEAD_CODE_2[anim] as char;
+                if second == 'f' {
+                    format!("art\\{}\\{}{}{}{}.frm", dir, name, first, second, fid.weapon())
+                } else {
+                    format!("art\\{}\\{}{}{}.frm", dir, name, first, second)
+                }
+            },
+            _ => format!("art\\{}\\{}", dir, name),
+        };
+
+        Some(path.to_ascii_lowercase())
+    }
+
+    /// Reads the art `fid` refers to from a DAT archive
+    pub fn load_frm(&self, dat: &DatFile, fid: Fid) -> Result<FrmFile, ArtError> {
+        let path = self.path(fid).ok_or(ArtError::InvalidFid(fid))?;
+        let data = read_file(dat, &path)?;
+
+        Ok(FrmFile::open(&mut Cursor::new(data))?)
+    }
+
+    //critters take some deaths from the critter named by their alias
+    fn alias(&self, fid: Fid) -> Fid {
+        let aliased = fid.art_type() == Some(ArtType::Critters) &&
+            ALIASED_ANIMS.iter().any(|a| *a as u8 == fid.anim());
+        if !aliased {
+            return fid;
+        }
+
+        self.list(ArtType::Critters)
+            .get(fid.id() as usize)
+            .and_then(|entry| entry.alias)
+            .map_or(fid, |alias| fid.with_id(alias))
+    }
+}
+
+fn read_file(dat: &DatFile, path: &str) -> Result<Vec<u8>, ArtError> {
+    let entry = dat.registry.get(path)
+        .and_then(|node| node.read().ok()?.get_file_entry().cloned())
+        .ok_or_else(|| ArtError::NotFound(path.to_string()))?;
+
+    dat.unpack_file(&entry).map_err(ArtError::UnpackError)
+}
@@ -0,0 +1,92 @@
+use crate::error::ArtError;
+
+use std::io::Read;
+
+/// A line of a `.lst` file
+///
+/// * `name` - file name, e.g. `grid000.frm`, or the base name of critter art
+/// * `alias` - critters only, index of the critter whose art stands in for
+///   death animations this one doesn't have
+/// * `can_run` - critters only, whether the critter has running animations
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LstEntry {
+    pub name: String,
+    pub alias: Option<u32>,
+    pub can_run: bool,
+}
+
+/// Art index, as stored in `art/<type>/<type>.lst`
+///
+/// The line number of an entry is its id in a FID. Like the original engine,
+/// the name ends at the first space, tab, `,` or `;`, and anything after that
+/// is a comment except for the critter fields. Blank lines still take up an id.
+#[derive(Debug, Default, Clone)]
+pub struct LstFile {
+    entries: Vec<LstEntry>,
+}
+
+impl LstFile {
+    pub fn open(stream: &mut impl Read) -> Result<Self, ArtError> {
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).map_err(ArtError::ReadError)?;
+
+        Ok(Self::from_bytes(&data))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let data = data.strip_suffix(b"\n").unwrap_or(data);
+        if data.is_empty() {
+            return Self::default();
+        }
+
+        let entries = data
+            .split(|b| *b == b'\n')
+            .map(parse_line)
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&LstEntry> {
+        self.entries.get(id)
+    }
+
+    pub fn entries(&self) -> &[LstEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Id of the entry called `name`, ignoring case
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name.eq_ignore_ascii_case(name))
+    }
+}
+
+fn parse_line(line: &[u8]) -> LstEntry {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_start();
+
+    let name_end = line.find([' ', '\t', '\r', ',', ';']).unwrap_or(line.len());
+    let name = line[.. name_end].to_string();
+
+    //critters.lst lines are `name,alias,run`
+    let mut fields = line[name_end ..]
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .skip(1)
+        .map(|f| f.trim().parse::<u32>().ok());
+
+    let alias = fields.next().flatten();
+    let can_run = fields.next().flatten().is_some_and(|run| run != 0);
+
+    LstEntry { name, alias, can_run }
+}
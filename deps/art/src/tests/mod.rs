@@ -0,0 +1,127 @@
+use std::io::Cursor;
+use dat::Dat2Writer;
+use frm::{ Frame, FrmFile };
+use crate::*;
+
+const CRITTERS_LST: &[u8] = b"\
+reserv,11,1
+HMJMPS,11,1 ; male jumpsuit
+HFJMPS ,12
+
+MAMRAT,11
+";
+
+fn lists() -> ArtLists {
+    let mut lists = ArtLists::default();
+    lists.set(ArtType::Critters, LstFile::from_bytes(CRITTERS_LST));
+    lists.set(ArtType::Items, LstFile::from_bytes(b"reserved.frm\r\nKNIFE.FRM ;knife\r\n"));
+    lists.set(ArtType::Heads, LstFile::from_bytes(b"reserved\nmyron,21\n"));
+
+    lists
+}
+
+#[test]
+fn lst_test() {
+    let lst = LstFile::from_bytes(CRITTERS_LST);
+    assert_eq!(lst.len(), 5);
+
+    assert_eq!(lst.get(1).unwrap(), &LstEntry { name: "HMJMPS".into(), alias: Some(11), can_run: true });
+    assert_eq!(lst.get(2).unwrap(), &LstEntry { name: "HFJMPS".into(), alias: Some(12), can_run: false });
+    //blank lines keep their id
+    assert_eq!(lst.get(3).unwrap().name, "");
+    assert_eq!(lst.find("mamrat"), Some(4));
+
+    assert!(LstFile::from_bytes(b"").is_empty());
+}
+
+#[test]
+fn fid_test() {
+    let fid = Fid::new(ArtType::Critters, 0x123, Anim::FireBurst as u8, WeaponAnim::Smg as u8, 3);
+    assert_eq!(fid.0, 0x312E_6123);
+    assert_eq!(fid.art_type(), Some(ArtType::Critters));
+    assert_eq!(fid.id(), 0x123);
+    assert_eq!(fid.anim(), 46);
+    assert_eq!(fid.weapon(), 6);
+    assert_eq!(fid.direction(), 3);
+
+    assert_eq!(Fid(0x0F00_0000).art_type(), None);
+    assert_eq!(ArtType::Interface.lst_path(), "art\\intrface\\intrface.lst");
+}
+
+#[test]
+fn path_test() {
+    let lists = lists();
+    let critter = |anim: Anim, weapon: WeaponAnim, direction| {
+        lists.path(Fid::new(ArtType::Critters, 1, anim as u8, weapon as u8, direction))
+    };
+
+    assert_eq!(lists.path(Fid::new(ArtType::Items, 1, 0, 0, 0)).unwrap(), "art\\items\\knife.frm");
+    assert_eq!(lists.path(Fid::new(ArtType::Items, 2, 0, 0, 0)), None);
+
+    assert_eq!(critter(Anim::Walk, WeaponAnim::None, 0).unwrap(), "art\\critters\\hmjmpsab.frm");
+    assert_eq!(critter(Anim::Stand, WeaponAnim::Smg, 0).unwrap(), "art\\critters\\hmjmpsia.frm");
+    assert_eq!(critter(Anim::FireBurst, WeaponAnim::Smg, 0).unwrap(), "art\\critters\\hmjmpsik.frm");
+    assert_eq!(critter(Anim::Dodge, WeaponAnim::Knife, 0).unwrap(), "art\\critters\\hmjmpsde.frm");
+    assert_eq!(critter(Anim::Dodge, WeaponAnim::None, 0).unwrap(), "art\\critters\\hmjmpsan.frm");
+    assert_eq!(critter(Anim::FallBack, WeaponAnim::None, 0).unwrap(), "art\\critters\\hmjmpsba.frm");
+    //directions stored in their own files
+    assert_eq!(critter(Anim::Walk, WeaponAnim::None, 1).unwrap(), "art\\critters\\hmjmpsab.fr0");
+    assert_eq!(critter(Anim::Walk, WeaponAnim::None, 6).unwrap(), "art\\critters\\hmjmpsab.fr5");
+    //weapon animations need a weapon
+    assert_eq!(critter(Anim::FireBurst, WeaponAnim::None, 0), None);
+    //FIDs have room for weapons past the last one
+    for weapon in 11 ..= 15 {
+        for anim in [Anim::Stand, Anim::Dodge, Anim::FireBurst] {
+            assert_eq!(lists.path(Fid::new(ArtType::Critters, 1, anim as u8, weapon, 0)), None);
+        }
+    }
+
+    let head = |anim, fidget| lists.path(Fid::new(ArtType::Heads, 1, anim, fidget, 0)).unwrap();
+    assert_eq!(head(0, 0), "art\\heads\\myrongv.frm");
+    assert_eq!(head(4, 2), "art\\heads\\myronnf2.frm");
+    assert_eq!(head(11, 0), "art\\heads\\myronbp.frm");
+}
+
+#[test]
+fn alias_test() {
+    let mut lists = lists();
+    let mut critters = CRITTERS_LST.to_vec();
+    critters.extend_from_slice(b"a\na\na\na\na\na\nHMWARR,11,1\n");
+    lists.set(ArtType::Critters, LstFile::from_bytes(&critters));
+
+    //mamrat has no electrified death of its own, it uses hmwarr's
+    let fid = Fid::new(ArtType::Critters, 4, Anim::Electrify as u8, 0, 0);
+    assert_eq!(lists.path(fid).unwrap(), "art\\critters\\hmwarrbh.frm");
+
+    let fid = Fid::new(ArtType::Critters, 4, Anim::Walk as u8, 0, 0);
+    assert_eq!(lists.path(fid).unwrap(), "art\\critters\\mamratab.frm");
+}
+
+#[test]
+fn load_test() {
+    let frm = FrmFile {
+        fps: 10,
+        frames_per_direction: 1,
+        frames: vec![Frame { width: 2, height: 1, size: 2, color_index: vec![1, 2], ..Default::default() }],
+        ..Default::default()
+    };
+    let mut frm_data = Cursor::new(Vec::new());
+    frm.write(&mut frm_data).unwrap();
+
+    let dat = Dat2Writer::build(&[
+        ("art\\items\\items.lst", b"reserved.frm\nknife.frm\n".to_vec()),
+        ("art\\items\\knife.frm", frm_data.into_inner()),
+    ]).unwrap();
+
+    let lists = ArtLists::load(&dat).unwrap();
+    assert_eq!(lists.list(ArtType::Items).len(), 2);
+    assert!(lists.list(ArtType::Critters).is_empty());
+
+    let loaded = lists.load_frm(&dat, Fid::new(ArtType::Items, 1, 0, 0, 0)).unwrap();
+    assert_eq!(loaded.frames[0].color_index, [1, 2]);
+
+    let missing = lists.load_frm(&dat, Fid::new(ArtType::Items, 0, 0, 0, 0));
+    assert!(matches!(missing, Err(ArtError::NotFound(_))));
+    let invalid = lists.load_frm(&dat, Fid::new(ArtType::Items, 5, 0, 0, 0));
+    assert!(matches!(invalid, Err(ArtError::InvalidFid(_))));
+}
@@ -0,0 +1,268 @@
+//! `#[derive(BinRead, BinWrite)]` for structs with a fixed binary layout
+//!
+//! Fields are read and written in declaration order. Attributes go in `#[bin(...)]`:
+//!
+//! * `big` / `little` - byte order, on the struct for every field or on a
+//!   single field. Without one the order passed to `bin_read` is used
+//! * `magic = b"..."` - on the struct, bytes expected before the first field
+//! * `magic = expr` - on a field, the value it must have
+//! * `count = expr` - on a `Vec` or `String` field, how many items to read.
+//!   Earlier fields can be used by name, a count that doesn't fit `usize`
+//!   is a read error. Writing doesn't check it, so the field holding the
+//!   count has to be kept in step by hand
+//!
+//! Read values are kept in `__field_`-prefixed locals, so field names can't
+//! shadow the stream or the byte order the generated code works with.
+
+use proc_macro::TokenStream;
+use proc_macro2::{ TokenStream as TokenStream2, TokenTree };
+use quote::quote;
+use syn::{
+    parse_macro_input,
+    Attribute,
+    Data,
+    DeriveInput,
+    Expr,
+    Fields,
+    Ident,
+    LitByteStr,
+};
+
+#[proc_macro_derive(BinRead, attributes(bin))]
+pub fn derive_bin_read(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_read(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(BinWrite, attributes(bin))]
+pub fn derive_bin_write(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_write(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Options {
+    mode: Option<TokenStream2>,
+    magic: Option<Expr>,
+    count: Option<Expr>,
+}
+
+impl Options {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("bin")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("big") {
+                    options.mode = Some(quote!(::common::readers::ReadMode::BE));
+                } else if meta.path.is_ident("little") {
+                    options.mode = Some(quote!(::common::readers::ReadMode::LE));
+                } else if meta.path.is_ident("magic") {
+                    options.magic = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("count") {
+                    options.count = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `big`, `little`, `magic` or `count`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+
+    //the mode fields use, given the name of the one passed in
+    fn mode(&self, outer: &Ident) -> TokenStream2 {
+        self.mode.clone().unwrap_or_else(|| quote!(#outer))
+    }
+}
+
+struct Field<'a> {
+    name: &'a Ident,
+    ty: &'a syn::Type,
+    options: Options,
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<Vec<Field<'_>>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "binary layouts can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(&input.ident, "binary layouts need named fields"));
+    };
+
+    fields.named.iter()
+        .map(|f| Ok(Field {
+            name: f.ident.as_ref().expect("named field"),
+            ty: &f.ty,
+            options: Options::parse(&f.attrs)?,
+        }))
+        .collect()
+}
+
+//local holding a field's value while the struct is read
+fn local(name: &Ident) -> Ident {
+    quote::format_ident!("__field_{}", name)
+}
+
+//binds the earlier fields `expr` mentions to their own names, for count and magic expressions
+fn bind_fields(expr: &Expr, earlier: &[Field]) -> TokenStream2 {
+    fn idents(tokens: TokenStream2, found: &mut Vec<Ident>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => found.push(ident),
+                TokenTree::Group(group) => idents(group.stream(), found),
+                _ => {},
+            }
+        }
+    }
+
+    let mut used = Vec::new();
+    idents(quote!(#expr), &mut used);
+
+    let bindings = earlier.iter()
+        .filter(|f| used.contains(f.name))
+        .map(|f| {
+            let (name, local) = (f.name, local(f.name));
+            quote!(let #name = ::std::clone::Clone::clone(&#local);)
+        });
+
+    quote!({ #(#bindings)* #expr })
+}
+
+fn struct_magic(options: &Options) -> syn::Result<Option<LitByteStr>> {
+    match &options.magic {
+        None => Ok(None),
+        Some(Expr::Lit(syn::ExprLit { lit: syn::Lit::ByteStr(bytes), .. })) => Ok(Some(bytes.clone())),
+        Some(other) => Err(syn::Error::new_spanned(other, "struct magic has to be a byte string")),
+    }
+}
+
+fn expand_read(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let options = Options::parse(&input.attrs)?;
+    let fields = named_fields(input)?;
+
+    let outer = Ident::new("__mode", proc_macro2::Span::call_site());
+    let mode = options.mode(&outer);
+
+    let magic = struct_magic(&options)?.map(|bytes| {
+        let len = bytes.value().len();
+        quote! {
+            let __position = ::std::io::Seek::stream_position(stream).ok();
+            let __magic: [u8; #len] = ::common::BinaryReader::read_array(stream)?;
+            if &__magic != #bytes {
+                return Err(::common::BinaryReadError {
+                    position: __position,
+                    error: ::std::io::Error::new(::std::io::ErrorKind::InvalidData, "bad magic"),
+                });
+            }
+        }
+    });
+
+    let reads = fields.iter().enumerate().map(|(i, f)| {
+        let Field { name, ty, options } = f;
+        let mode = options.mode(&Ident::new("__struct_mode", proc_macro2::Span::call_site()));
+        let local = local(name);
+
+        let read = match &options.count {
+            Some(count) => {
+                let count = bind_fields(count, &fields[.. i]);
+                quote! {{
+                    let __count = ::std::convert::TryInto::<usize>::try_into(#count).map_err(|_| ::common::BinaryReadError {
+                        position: __position,
+                        error: ::std::io::Error::new(
+                            ::std::io::ErrorKind::InvalidData,
+                            concat!("count of `", stringify!(#name), "` out of range"),
+                        ),
+                    })?;
+                    <#ty as ::common::binary::BinReadCount>::bin_read_count(stream, #mode, __count)?
+                }}
+            },
+            None => quote! {
+                <#ty as ::common::BinRead>::bin_read(stream, #mode)?
+            },
+        };
+
+        let check = options.magic.as_ref().map(|magic| {
+            let magic = bind_fields(magic, &fields[.. i]);
+            quote! {
+                if #local != #magic {
+                    return Err(::common::BinaryReadError {
+                        position: __position,
+                        error: ::std::io::Error::new(
+                            ::std::io::ErrorKind::InvalidData,
+                            concat!("unexpected value for `", stringify!(#name), "`"),
+                        ),
+                    });
+                }
+            }
+        });
+
+        quote! {
+            let __position = ::std::io::Seek::stream_position(stream).ok();
+            let #local: #ty = #read;
+            #check
+        }
+    });
+    let names = fields.iter().map(|f| {
+        let (name, local) = (f.name, local(f.name));
+        quote!(#name: #local)
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::common::BinRead for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn bin_read<S: ::common::Stream + ?Sized>(stream: &mut S, #outer: ::common::readers::ReadMode)
+                -> ::std::result::Result<Self, ::common::BinaryReadError>
+            {
+                let __struct_mode = #mode;
+                #magic
+                #(#reads)*
+                Ok(Self { #(#names),* })
+            }
+        }
+    })
+}
+
+fn expand_write(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let options = Options::parse(&input.attrs)?;
+    let fields = named_fields(input)?;
+
+    let outer = Ident::new("__mode", proc_macro2::Span::call_site());
+    let mode = options.mode(&outer);
+
+    let magic = struct_magic(&options)?.map(|bytes| quote! {
+        ::common::BinaryWriter::write_bytes(output, #bytes)?;
+    });
+
+    let writes = fields.iter().map(|f| {
+        let Field { name, ty, options } = f;
+        let mode = options.mode(&Ident::new("__struct_mode", proc_macro2::Span::call_site()));
+
+        quote! {
+            <#ty as ::common::BinWrite>::bin_write(&self.#name, output, #mode)?;
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::common::BinWrite for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn bin_write<W: ::std::io::Write + ::std::io::Seek + ?Sized>(&self, output: &mut W, #outer: ::common::readers::ReadMode)
+                -> ::std::result::Result<(), ::common::BinaryWriteError>
+            {
+                let __struct_mode = #mode;
+                #magic
+                #(#writes)*
+                Ok(())
+            }
+        }
+    })
+}
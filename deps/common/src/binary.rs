@@ -0,0 +1,126 @@
+use crate::{
+    BinaryReader,
+    BinaryReadError,
+    BinaryWriter,
+    BinaryWriteError,
+    Stream,
+    readers::ReadMode,
+};
+use std::io::{ Seek, Write };
+
+/// A type with a fixed binary layout, usually from `#[derive(BinRead)]`
+///
+/// * `mode` - byte order for fields that don't set their own
+pub trait BinRead: Sized {
+    fn bin_read<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode) -> Result<Self, BinaryReadError>;
+
+    /// Reads `count` values in a row, overridden for bytes so they're read in one go
+    fn bin_read_vec<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode, count: usize) -> Result<Vec<Self>, BinaryReadError> {
+        (0 .. count)
+            .map(|_| Self::bin_read(stream, mode))
+            .collect()
+    }
+}
+
+/// Counterpart of `BinRead`, usually from `#[derive(BinWrite)]`
+pub trait BinWrite {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError>;
+
+    /// Writes `items` in a row, overridden for bytes so they're written in one go
+    fn bin_write_slice<W: Write + Seek + ?Sized>(items: &[Self], output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> where Self: Sized {
+        items.iter().try_for_each(|item| item.bin_write(output, mode))
+    }
+}
+
+/// Types whose length is stored elsewhere, read with `#[bin(count = ...)]`
+pub trait BinReadCount: Sized {
+    fn bin_read_count<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode, count: usize) -> Result<Self, BinaryReadError>;
+}
+
+macro_rules! impl_bin_value {
+    ($($t:ty),*) => {
+        $(
+            impl BinRead for $t {
+                fn bin_read<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode) -> Result<Self, BinaryReadError> {
+                    stream.read_value(mode)
+                }
+            }
+
+            impl BinWrite for $t {
+                fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> {
+                    output.write_value(self, mode)
+                }
+            }
+        )*
+    };
+}
+
+impl_bin_value!(i8, u16, i16, u32, i32, u64, i64, f32, f64, bool);
+
+impl BinRead for u8 {
+    fn bin_read<S: Stream + ?Sized>(stream: &mut S, _mode: ReadMode) -> Result<Self, BinaryReadError> {
+        stream.read_u8()
+    }
+
+    fn bin_read_vec<S: Stream + ?Sized>(stream: &mut S, _mode: ReadMode, count: usize) -> Result<Vec<Self>, BinaryReadError> {
+        stream.read_vec(count)
+    }
+}
+
+impl BinWrite for u8 {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, _mode: ReadMode) -> Result<(), BinaryWriteError> {
+        output.write_u8(*self)
+    }
+
+    fn bin_write_slice<W: Write + Seek + ?Sized>(items: &[Self], output: &mut W, _mode: ReadMode) -> Result<(), BinaryWriteError> {
+        output.write_bytes(items)
+    }
+}
+
+impl<T: BinRead, const N: usize> BinRead for [T; N] {
+    fn bin_read<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode) -> Result<Self, BinaryReadError> {
+        let items = Vec::<T>::bin_read_count(stream, mode, N)?;
+        match items.try_into() {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!("read {} items into an array of {}", N, N),
+        }
+    }
+}
+
+impl<T: BinWrite, const N: usize> BinWrite for [T; N] {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> {
+        self.as_slice().bin_write(output, mode)
+    }
+}
+
+impl<T: BinWrite> BinWrite for [T] {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> {
+        T::bin_write_slice(self, output, mode)
+    }
+}
+
+impl<T: BinRead> BinReadCount for Vec<T> {
+    fn bin_read_count<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode, count: usize) -> Result<Self, BinaryReadError> {
+        T::bin_read_vec(stream, mode, count)
+    }
+}
+
+impl<T: BinWrite> BinWrite for Vec<T> {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> {
+        self.as_slice().bin_write(output, mode)
+    }
+}
+
+//`count` bytes, cut at the first null
+impl BinReadCount for String {
+    fn bin_read_count<S: Stream + ?Sized>(stream: &mut S, _mode: ReadMode, count: usize) -> Result<Self, BinaryReadError> {
+        stream.read_fixed_string(count)
+    }
+}
+
+//written without padding or a terminator, the count field decides the size
+impl BinWrite for String {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, _mode: ReadMode) -> Result<(), BinaryWriteError> {
+        output.write_bytes(self.as_bytes())
+    }
+}
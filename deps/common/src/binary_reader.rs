@@ -0,0 +1,194 @@
+use crate::{
+    Stream,
+    readers::{ FromBytes, ReadMode },
+};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{ self, Read },
+};
+
+/// Most `read_vec` allocates up front, longer reads grow as the data arrives
+///
+/// Lengths come from file headers, so a corrupt one can't reserve more memory
+/// than the stream actually holds.
+pub const MAX_PREALLOC: usize = 0x10_000;
+
+/// A failed `BinaryReader` read
+///
+/// * `position` - stream offset the read started at, `None` if the stream
+///   couldn't report it
+#[derive(Debug)]
+pub struct BinaryReadError {
+    pub position: Option<u64>,
+    pub error: io::Error,
+}
+
+impl BinaryReadError {
+    pub fn is_eof(&self) -> bool {
+        self.error.kind() == io::ErrorKind::UnexpectedEof
+    }
+}
+
+impl Display for BinaryReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} at offset {:#x}", self.error, position),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl Error for BinaryReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<io::Error> for BinaryReadError {
+    fn from(error: io::Error) -> Self {
+        Self { position: None, error }
+    }
+}
+
+impl From<BinaryReadError> for io::Error {
+    fn from(e: BinaryReadError) -> Self {
+        io::Error::new(e.error.kind(), e)
+    }
+}
+
+/// Integer type in front of a length-prefixed string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    U8,
+    U16Le,
+    U16Be,
+    U32Le,
+    U32Be,
+}
+
+impl LengthPrefix {
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16Le | Self::U16Be => 2,
+            Self::U32Le | Self::U32Be => 4,
+        }
+    }
+
+    /// Longest string the prefix can describe
+    pub fn max_len(self) -> usize {
+        match self {
+            Self::U8 => u8::MAX as usize,
+            Self::U16Le | Self::U16Be => u16::MAX as usize,
+            Self::U32Le | Self::U32Be => u32::MAX as usize,
+        }
+    }
+}
+
+macro_rules! read_fns {
+    ($($t:ty => $le:ident, $be:ident;)*) => {
+        $(
+            fn $le(&mut self) -> Result<$t, BinaryReadError> {
+                BinaryReader::read_array(self).map(<$t>::from_le_bytes)
+            }
+
+            fn $be(&mut self) -> Result<$t, BinaryReadError> {
+                BinaryReader::read_array(self).map(<$t>::from_be_bytes)
+            }
+        )*
+    };
+}
+
+/// Checked reads of fixed size values
+///
+/// Every read either fills the whole value or fails, a stream that ends early
+/// gives an `UnexpectedEof` error rather than a partly zeroed number.
+pub trait BinaryReader: Stream {
+    /// `Read` has an unstable method with the same name, call this as
+    /// `BinaryReader::read_array(stream)` to avoid the ambiguity lint
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], BinaryReadError> {
+        let mut buf = [0u8; N];
+        self.read_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_vec(&mut self, len: usize) -> Result<Vec<u8>, BinaryReadError> {
+        if len <= MAX_PREALLOC {
+            let mut buf = vec![0u8; len];
+            self.read_into(&mut buf)?;
+            return Ok(buf);
+        }
+
+        let position = self.stream_position().ok();
+        let mut buf = Vec::with_capacity(MAX_PREALLOC);
+        Read::take(&mut *self, len as u64)
+            .read_to_end(&mut buf)
+            .map_err(|error| BinaryReadError { position, error })?;
+
+        if buf.len() < len {
+            let error = io::Error::from(io::ErrorKind::UnexpectedEof);
+            return Err(BinaryReadError { position, error });
+        }
+        Ok(buf)
+    }
+
+    /// Like `read_exact`, with the error annotated with where the read started
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<(), BinaryReadError> {
+        let position = self.stream_position().ok();
+        self.read_exact(buf)
+            .map_err(|error| BinaryReadError { position, error })
+    }
+
+    /// Reads `len` bytes holding a string, cut at the first null
+    ///
+    /// Bytes that aren't valid UTF-8 are replaced rather than failing the read,
+    /// since names in Fallout's files use the DOS code page.
+    fn read_fixed_string(&mut self, len: usize) -> Result<String, BinaryReadError> {
+        let bytes = self.read_vec(len)?;
+        let text = bytes.split(|b| *b == 0).next().unwrap_or_default();
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+
+    /// Reads a length followed by that many bytes of string
+    fn read_prefixed_string(&mut self, prefix: LengthPrefix) -> Result<String, BinaryReadError> {
+        let len = match prefix {
+            LengthPrefix::U8 => self.read_u8()? as usize,
+            LengthPrefix::U16Le => self.read_u16_le()? as usize,
+            LengthPrefix::U16Be => self.read_u16_be()? as usize,
+            LengthPrefix::U32Le => self.read_u32_le()? as usize,
+            LengthPrefix::U32Be => self.read_u32_be()? as usize,
+        };
+        self.read_fixed_string(len)
+    }
+
+    /// Reads any `FromBytes` type in the given byte order
+    fn read_value<T: FromBytes<N>, const N: usize>(&mut self, mode: ReadMode) -> Result<T, BinaryReadError> {
+        let bytes = BinaryReader::read_array(self)?;
+        Ok(match mode {
+            ReadMode::BE => T::from_bytes_be(bytes),
+            ReadMode::LE => T::from_bytes_le(bytes),
+            ReadMode::NE => T::from_bytes_ne(bytes),
+        })
+    }
+
+    fn read_u8(&mut self) -> Result<u8, BinaryReadError> {
+        BinaryReader::read_array(self).map(|[b]| b)
+    }
+
+    fn read_i8(&mut self) -> Result<i8, BinaryReadError> {
+        BinaryReader::read_array(self).map(i8::from_le_bytes)
+    }
+
+    read_fns! {
+        u16 => read_u16_le, read_u16_be;
+        i16 => read_i16_le, read_i16_be;
+        u32 => read_u32_le, read_u32_be;
+        i32 => read_i32_le, read_i32_be;
+        u64 => read_u64_le, read_u64_be;
+        i64 => read_i64_le, read_i64_be;
+        f32 => read_f32_le, read_f32_be;
+    }
+}
+
+impl<S: Stream + ?Sized> BinaryReader for S {}
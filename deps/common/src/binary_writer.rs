@@ -0,0 +1,187 @@
+use crate::{
+    binary_reader::LengthPrefix,
+    readers::{ ReadMode, ToBytes },
+};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{ self, Seek, SeekFrom, Write },
+};
+
+/// A failed `BinaryWriter` write
+///
+/// * `position` - stream offset the write started at, `None` if the stream
+///   couldn't report it
+#[derive(Debug)]
+pub struct BinaryWriteError {
+    pub position: Option<u64>,
+    pub error: io::Error,
+}
+
+impl Display for BinaryWriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} at offset {:#x}", self.error, position),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl Error for BinaryWriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<io::Error> for BinaryWriteError {
+    fn from(error: io::Error) -> Self {
+        Self { position: None, error }
+    }
+}
+
+impl From<BinaryWriteError> for io::Error {
+    fn from(e: BinaryWriteError) -> Self {
+        io::Error::new(e.error.kind(), e)
+    }
+}
+
+macro_rules! write_fns {
+    ($($t:ty => $le:ident, $be:ident;)*) => {
+        $(
+            fn $le(&mut self, value: $t) -> Result<(), BinaryWriteError> {
+                self.write_bytes(&value.to_le_bytes())
+            }
+
+            fn $be(&mut self, value: $t) -> Result<(), BinaryWriteError> {
+                self.write_bytes(&value.to_be_bytes())
+            }
+        )*
+    };
+}
+
+macro_rules! patch_fns {
+    ($($t:ty => $le:ident, $be:ident;)*) => {
+        $(
+            fn $le(&mut self, position: u64, value: $t) -> Result<(), BinaryWriteError> {
+                self.write_at(position, &value.to_le_bytes())
+            }
+
+            fn $be(&mut self, position: u64, value: $t) -> Result<(), BinaryWriteError> {
+                self.write_at(position, &value.to_be_bytes())
+            }
+        )*
+    };
+}
+
+/// Writes fixed size values, the counterpart of `BinaryReader`
+///
+/// Size fields that come before the data they describe can be written as
+/// zeros with `reserve`, then filled in with one of the `patch_*` functions
+/// once the size is known.
+pub trait BinaryWriter: Write + Seek {
+    /// Like `write_all`, with the error annotated with where the write started
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), BinaryWriteError> {
+        let position = self.stream_position().ok();
+        self.write_all(bytes)
+            .map_err(|error| BinaryWriteError { position, error })
+    }
+
+    /// Writes `text` into a field of `len` bytes, padded with nulls
+    ///
+    /// Fails if the text doesn't fit, a string filling the whole field is
+    /// stored without a terminating null.
+    fn write_fixed_string(&mut self, text: &str, len: usize) -> Result<(), BinaryWriteError> {
+        if text.len() > len {
+            return Err(BinaryWriteError {
+                position: self.stream_position().ok(),
+                error: io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is longer than {} bytes", text, len)),
+            });
+        }
+
+        let mut field = vec![0u8; len];
+        field[.. text.len()].copy_from_slice(text.as_bytes());
+        self.write_bytes(&field)
+    }
+
+    /// Writes the length of `text` followed by the text, without a null
+    fn write_prefixed_string(&mut self, text: &str, prefix: LengthPrefix) -> Result<(), BinaryWriteError> {
+        let len = text.len();
+        if len > prefix.max_len() {
+            return Err(BinaryWriteError {
+                position: self.stream_position().ok(),
+                error: io::Error::new(io::ErrorKind::InvalidInput, format!("string of {} bytes is too long for a {:?} length", len, prefix)),
+            });
+        }
+
+        match prefix {
+            LengthPrefix::U8 => self.write_u8(len as u8)?,
+            LengthPrefix::U16Le => self.write_u16_le(len as u16)?,
+            LengthPrefix::U16Be => self.write_u16_be(len as u16)?,
+            LengthPrefix::U32Le => self.write_u32_le(len as u32)?,
+            LengthPrefix::U32Be => self.write_u32_be(len as u32)?,
+        }
+        self.write_bytes(text.as_bytes())
+    }
+
+    /// Writes `value` until the position is a multiple of `alignment`
+    fn pad_to_alignment(&mut self, alignment: u64, value: u8) -> Result<(), BinaryWriteError> {
+        let position = self.stream_position()?;
+        if alignment == 0 {
+            return Ok(());
+        }
+
+        let padding = (alignment - position % alignment) % alignment;
+        self.write_bytes(&vec![value; padding as usize])
+    }
+
+    /// Writes `len` zeros to be patched later, returns their position
+    fn reserve(&mut self, len: usize) -> Result<u64, BinaryWriteError> {
+        let position = self.stream_position()?;
+        self.write_bytes(&vec![0; len])?;
+        Ok(position)
+    }
+
+    /// Overwrites the bytes at `position`, then returns to where writing left off
+    fn write_at(&mut self, position: u64, bytes: &[u8]) -> Result<(), BinaryWriteError> {
+        let end = self.stream_position()?;
+        self.seek(SeekFrom::Start(position))?;
+        self.write_bytes(bytes)?;
+        self.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+
+    /// Writes any `ToBytes` type in the given byte order
+    fn write_value<T: ToBytes<N>, const N: usize>(&mut self, value: &T, mode: ReadMode) -> Result<(), BinaryWriteError> {
+        let bytes = match mode {
+            ReadMode::BE => value.to_bytes_be(),
+            ReadMode::LE => value.to_bytes_le(),
+            ReadMode::NE => value.to_bytes_ne(),
+        };
+        self.write_bytes(&bytes)
+    }
+
+    fn write_u8(&mut self, value: u8) -> Result<(), BinaryWriteError> {
+        self.write_bytes(&[value])
+    }
+
+    fn write_i8(&mut self, value: i8) -> Result<(), BinaryWriteError> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    write_fns! {
+        u16 => write_u16_le, write_u16_be;
+        i16 => write_i16_le, write_i16_be;
+        u32 => write_u32_le, write_u32_be;
+        i32 => write_i32_le, write_i32_be;
+        u64 => write_u64_le, write_u64_be;
+        i64 => write_i64_le, write_i64_be;
+        f32 => write_f32_le, write_f32_be;
+    }
+
+    patch_fns! {
+        u16 => patch_u16_le, patch_u16_be;
+        u32 => patch_u32_le, patch_u32_be;
+    }
+}
+
+impl<W: Write + Seek + ?Sized> BinaryWriter for W {}
@@ -4,6 +4,26 @@ use std::{
     num::Wrapping,
 };
 
+/// Bit reading shared by `BitReader` and `SliceBitReader`, for code that works with either
+pub trait BitRead {
+    fn get_bit(&mut self) -> Option<u8>;
+
+    /// Reads up to 32 bits
+    fn get_bits(&mut self, bits: u8) -> Option<u32>;
+
+    fn get_bits_u8(&mut self, bits: u8) -> Option<u8> {
+        if bits > 8 { return None; }
+        self.get_bits(bits).map(|x| x as u8)
+    }
+    fn get_bits_u16(&mut self, bits: u8) -> Option<u16> {
+        if bits > 16 { return None; }
+        self.get_bits(bits).map(|x| x as u16)
+    }
+    fn get_bits_u32(&mut self, bits: u8) -> Option<u32> {
+        self.get_bits(bits)
+    }
+}
+
 pub struct BitReader {
     data: VecDeque<u8>,
     bit_index: usize,
@@ -39,13 +59,18 @@ impl BitReader {
         self.data.len()
     }
 
+    /// Appends more data after what is left to read
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes);
+    }
+
     pub fn get_bit(&mut self) -> Option<u8> {
         let byte = self.data.front()?;
         let shift = self.bit_index;
         let mask = 1 << shift;
 
         let mut bit = byte & mask;
-        bit = bit >> shift;
+        bit >>= shift;
 
         self.bit_index += 1;
         if self.bit_index == 8 {
@@ -133,3 +158,13 @@ impl BitReader {
     }
 
 }
+
+impl BitRead for BitReader {
+    fn get_bit(&mut self) -> Option<u8> {
+        BitReader::get_bit(self)
+    }
+
+    fn get_bits(&mut self, bits: u8) -> Option<u32> {
+        BitReader::get_bits_u32(self, bits)
+    }
+}
@@ -0,0 +1,42 @@
+/// Writes bits least significant first, the counterpart of `BitReader`
+#[derive(Debug, Default, Clone)]
+pub struct BitWriter {
+    data: Vec<u8>,
+    bit_index: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of bits written
+    pub fn bit_size(&self) -> usize {
+        match self.bit_index {
+            0 => self.data.len() * 8,
+            i => (self.data.len() - 1) * 8 + i,
+        }
+    }
+
+    pub fn put_bit(&mut self, bit: u8) {
+        if self.bit_index == 0 {
+            self.data.push(0);
+        }
+
+        let last = self.data.len() - 1;
+        self.data[last] |= (bit & 1) << self.bit_index;
+        self.bit_index = (self.bit_index + 1) % 8;
+    }
+
+    /// Writes the lowest `bits` bits of `value`
+    pub fn put_bits(&mut self, value: u64, bits: u8) {
+        for i in 0 .. bits {
+            self.put_bit(((value >> i) & 1) as u8);
+        }
+    }
+
+    /// Returns the written data, the last byte is padded with zeros
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
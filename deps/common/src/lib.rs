@@ -1,50 +1,30 @@
+//lets `#[derive(BinRead, BinWrite)]` refer to `::common` from inside this crate
+extern crate self as common;
+
 #[cfg(test)]
 mod tests;
 
+pub mod binary;
+pub mod binary_reader;
+pub mod binary_writer;
 pub mod bit_reader;
+pub mod bit_writer;
+pub mod slice_bit_reader;
 pub mod vec_2d;
 pub mod stream;
 pub mod readers;
+pub mod wav;
 
 pub use self::{
-    bit_reader::BitReader,
+    binary::{ BinRead, BinWrite },
+    binary_reader::{ BinaryReader, BinaryReadError, LengthPrefix },
+    binary_writer::{ BinaryWriter, BinaryWriteError },
+    bit_reader::{ BitRead, BitReader },
+    slice_bit_reader::SliceBitReader,
+    bit_writer::BitWriter,
     vec_2d::Vec2d,
     stream::Stream,
+    wav::Wav,
 };
 
-#[macro_export]
-macro_rules! read_num {
-    ($reader:expr, $itype:ty, be) => {
-        {
-            let mut buf = [0u8; std::mem::size_of::<$itype>()];
-            $reader.read(&mut buf)
-                .ok()
-                .and_then(|_| { Some(<$itype>::from_be_bytes(buf)) })
-        }
-    };
-    ($reader:expr, $itype:ty, le) => {
-        {
-            let mut buf = [0u8; std::mem::size_of::<$itype>()];
-            $reader.read(&mut buf)
-                .ok()
-                .and_then(|_| { Some(<$itype>::from_le_bytes(buf)) })
-        }
-    };
-    ($reader:expr, $itype:ty) => {
-        {
-            let mut buf = [0u8; std::mem::size_of::<$itype>()];
-            $reader.read(&mut buf)
-                .ok()
-                .and_then(|_| { Some(<$itype>::from_ne_bytes(buf)) })
-        }
-    };
-}
-
-#[macro_export]
-macro_rules! read_bytes {
-    ($reader: expr, $bytes: expr) => {{
-        let mut buf = [0u8; $bytes];
-        $reader.read(&mut buf)
-            .map(|_| buf)
-    }};
-}
+pub use binary_derive::{ BinRead, BinWrite };
@@ -6,6 +6,13 @@ pub trait FromBytes<const N: usize> {
     fn from_bytes_le(bytes: [u8; N]) -> Self;
 }
 
+/// Counterpart of `FromBytes`, used for writing
+pub trait ToBytes<const N: usize> {
+    fn to_bytes_ne(&self) -> [u8; N];
+    fn to_bytes_be(&self) -> [u8; N];
+    fn to_bytes_le(&self) -> [u8; N];
+}
+
 macro_rules! impl_from_bytes {
     ($t:ty) => {
         impl_from_bytes!($t, { std::mem::size_of::<$t>() });
@@ -24,6 +31,20 @@ macro_rules! impl_from_bytes {
                 <$t>::from_le_bytes(bytes)
             }
         }
+
+        impl ToBytes<$sz> for $t {
+            fn to_bytes_ne(&self) -> [u8; $sz] {
+                self.to_ne_bytes()
+            }
+
+            fn to_bytes_be(&self) -> [u8; $sz] {
+                self.to_be_bytes()
+            }
+
+            fn to_bytes_le(&self) -> [u8; $sz] {
+                self.to_le_bytes()
+            }
+        }
     };
 }
 
@@ -52,6 +73,20 @@ impl FromBytes<1> for bool {
     }
 }
 
+impl ToBytes<1> for bool {
+    fn to_bytes_ne(&self) -> [u8; 1] {
+        [*self as u8]
+    }
+    fn to_bytes_be(&self) -> [u8; 1] {
+        self.to_bytes_ne()
+    }
+    fn to_bytes_le(&self) -> [u8; 1] {
+        self.to_bytes_ne()
+    }
+}
+
+/// Byte order of a value, also used when writing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReadMode {
     BE,
     LE,
@@ -61,11 +96,11 @@ pub enum ReadMode {
 pub fn read_type<R, const N: usize>(read_mode: ReadMode, data: &mut impl Read) -> std::io::Result<R> where R: FromBytes<N> {
     let mut buf = [0u8; N];
 
-    let _ = data.read_exact(&mut buf)?;
+    data.read_exact(&mut buf)?;
     match read_mode {
         ReadMode::BE => Ok(R::from_bytes_be(buf)),
-        ReadMode::LE => Ok(R::from_bytes_ne(buf)),
-        ReadMode::NE => Ok(R::from_bytes_le(buf)),
+        ReadMode::LE => Ok(R::from_bytes_le(buf)),
+        ReadMode::NE => Ok(R::from_bytes_ne(buf)),
     }
 }
 
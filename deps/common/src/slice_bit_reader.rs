@@ -0,0 +1,135 @@
+use crate::bit_reader::BitRead;
+
+/// Reads bits least significant first from a borrowed slice
+///
+/// Bits are loaded 8 bytes at a time into a 64 bit buffer, so most reads
+/// are a shift and a mask. Unlike `BitReader`, a failed read consumes nothing.
+#[derive(Debug, Clone)]
+pub struct SliceBitReader<'a> {
+    data: &'a [u8],
+    //next byte to load into `buffer`
+    byte_pos: usize,
+
+    //bits above `buffered` may hold the following bytes already, they are
+    //the same bits the next refill loads so or-ing them again is harmless
+    buffer: u64,
+    buffered: u32,
+}
+
+impl<'a> SliceBitReader<'a> {
+    /// Largest read `peek_bits` and `get_bits` allow
+    pub const MAX_BITS: u8 = 32;
+
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut reader = Self {
+            data,
+            byte_pos: 0,
+            buffer: 0,
+            buffered: 0,
+        };
+        reader.refill();
+        reader
+    }
+
+    /// Bits read so far
+    pub fn position(&self) -> u64 {
+        self.byte_pos as u64 * 8 - self.buffered as u64
+    }
+
+    pub fn bits_remaining(&self) -> u64 {
+        self.data.len() as u64 * 8 - self.position()
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.buffered.is_multiple_of(8)
+    }
+
+    #[inline]
+    fn refill(&mut self) {
+        if let Some(bytes) = self.data.get(self.byte_pos .. self.byte_pos + 8) {
+            let word = u64::from_le_bytes(bytes.try_into().unwrap());
+            self.buffer |= word << self.buffered;
+            self.byte_pos += (63 - self.buffered as usize) / 8;
+            self.buffered |= 56;
+        } else {
+            while self.buffered <= 56 && self.byte_pos < self.data.len() {
+                self.buffer |= (self.data[self.byte_pos] as u64) << self.buffered;
+                self.byte_pos += 1;
+                self.buffered += 8;
+            }
+        }
+    }
+
+    #[inline]
+    fn consume(&mut self, bits: u32) {
+        self.buffer = self.buffer.checked_shr(bits).unwrap_or(0);
+        self.buffered -= bits;
+    }
+
+    /// Returns the next `bits` bits (at most 32) without moving past them
+    #[inline]
+    pub fn peek_bits(&mut self, bits: u8) -> Option<u32> {
+        if bits > Self::MAX_BITS {
+            return None;
+        }
+        if self.buffered < bits as u32 {
+            self.refill();
+            if self.buffered < bits as u32 {
+                return None;
+            }
+        }
+
+        let mask = (1u64 << bits) - 1;
+        Some((self.buffer & mask) as u32)
+    }
+
+    #[inline]
+    pub fn get_bits(&mut self, bits: u8) -> Option<u32> {
+        let value = self.peek_bits(bits)?;
+        self.consume(bits as u32);
+        Some(value)
+    }
+
+    #[inline]
+    pub fn get_bit(&mut self) -> Option<u8> {
+        self.get_bits(1).map(|b| b as u8)
+    }
+
+    /// Moves past `bits` bits, fails without moving if there aren't that many left
+    pub fn skip_bits(&mut self, bits: u64) -> Option<()> {
+        if bits > self.bits_remaining() {
+            return None;
+        }
+
+        if bits <= self.buffered as u64 {
+            self.consume(bits as u32);
+        } else {
+            let position = self.position() + bits;
+            self.byte_pos = (position / 8) as usize;
+            self.buffer = 0;
+            self.buffered = 0;
+            self.refill();
+            self.consume((position % 8) as u32);
+        }
+
+        Some(())
+    }
+
+    /// Skips to the start of the next byte, unless already there
+    pub fn align_to_byte(&mut self) {
+        let partial = self.buffered % 8;
+        self.consume(partial);
+    }
+}
+
+impl BitRead for SliceBitReader<'_> {
+    #[inline]
+    fn get_bit(&mut self) -> Option<u8> {
+        SliceBitReader::get_bit(self)
+    }
+
+    #[inline]
+    fn get_bits(&mut self, bits: u8) -> Option<u32> {
+        SliceBitReader::get_bits(self, bits)
+    }
+}
@@ -44,3 +44,11 @@ impl Stream for Cursor<Vec<u8>> {
     }
 }
 impl Stream for File {}
+impl Stream for Cursor<&[u8]> {
+    fn eof(&mut self) -> Result<bool, std::io::Error> {
+        let pos = self.stream_position()?;
+
+        let size = self.get_ref().len() as u64;
+        Ok(pos >= size)
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    BinRead,
+    BinWrite,
+    readers::ReadMode,
+};
+use std::io::{ Cursor, ErrorKind };
+
+#[derive(Debug, PartialEq, BinRead, BinWrite)]
+struct Point {
+    x: i16,
+    y: i16,
+}
+
+#[derive(Debug, PartialEq, BinRead, BinWrite)]
+#[bin(big, magic = b"TST")]
+struct Record {
+    #[bin(magic = 2)]
+    version: u8,
+    #[bin(little)]
+    flags: u16,
+    points: [Point; 2],
+
+    name_size: u32,
+    #[bin(count = name_size)]
+    name: String,
+    count: u8,
+    #[bin(count = count * 2)]
+    data: Vec<u8>,
+}
+
+fn record_bytes() -> Vec<u8> {
+    let mut data = b"TST".to_vec();
+    data.push(2);
+    data.extend_from_slice(&[0x01, 0x80]);
+    data.extend_from_slice(&[0, 1, 0xFF, 0xFE, 0, 3, 0, 4]);
+    data.extend_from_slice(&[0, 0, 0, 4]);
+    data.extend_from_slice(b"name");
+    data.push(2);
+    data.extend_from_slice(&[9, 8, 7, 6]);
+    data
+}
+
+fn record() -> Record {
+    Record {
+        version: 2,
+        flags: 0x8001,
+        points: [Point { x: 1, y: -2 }, Point { x: 3, y: 4 }],
+        name_size: 4,
+        name: "name".into(),
+        count: 2,
+        data: vec![9, 8, 7, 6],
+    }
+}
+
+#[test]
+fn derive_read_test() {
+    //the struct's own byte order wins over the one passed in
+    let read = Record::bin_read(&mut Cursor::new(record_bytes()), ReadMode::LE).unwrap();
+    assert_eq!(read, record());
+
+    let point = Point::bin_read(&mut Cursor::new(vec![1, 0, 0, 2]), ReadMode::LE).unwrap();
+    assert_eq!(point, Point { x: 1, y: 0x200 });
+}
+
+#[test]
+fn derive_write_test() {
+    let mut out = Cursor::new(Vec::new());
+    record().bin_write(&mut out, ReadMode::LE).unwrap();
+    assert_eq!(out.into_inner(), record_bytes());
+
+    let mut out = Cursor::new(Vec::new());
+    Point { x: 1, y: 0x200 }.bin_write(&mut out, ReadMode::BE).unwrap();
+    assert_eq!(out.into_inner(), [0, 1, 2, 0]);
+}
+
+#[test]
+fn derive_errors_test() {
+    let mut data = record_bytes();
+    data[0] = b'X';
+    let e = Record::bin_read(&mut Cursor::new(data), ReadMode::BE).unwrap_err();
+    assert_eq!(e.error.kind(), ErrorKind::InvalidData);
+    assert_eq!(e.position, Some(0));
+
+    let mut data = record_bytes();
+    data[3] = 3;
+    let e = Record::bin_read(&mut Cursor::new(data), ReadMode::BE).unwrap_err();
+    assert_eq!(e.error.kind(), ErrorKind::InvalidData);
+    assert_eq!(e.position, Some(3));
+    assert!(e.to_string().contains("version"));
+
+    let data = record_bytes();
+    let e = Record::bin_read(&mut Cursor::new(&data[.. data.len() - 1]), ReadMode::BE).unwrap_err();
+    assert!(e.is_eof());
+}
+
+//field names the generated code uses itself
+#[derive(Debug, PartialEq, BinRead, BinWrite)]
+#[bin(little)]
+struct Shadowing {
+    stream: i32,
+    #[bin(count = stream)]
+    output: Vec<u8>,
+    #[bin(magic = stream + 1)]
+    check: i32,
+}
+
+#[test]
+fn derive_locals_test() {
+    let data = [2, 0, 0, 0, 7, 8, 3, 0, 0, 0];
+    let read = Shadowing::bin_read(&mut Cursor::new(&data[..]), ReadMode::BE).unwrap();
+    assert_eq!(read, Shadowing { stream: 2, output: vec![7, 8], check: 3 });
+
+    let mut out = Cursor::new(Vec::new());
+    read.bin_write(&mut out, ReadMode::BE).unwrap();
+    assert_eq!(out.into_inner(), data);
+
+    //a negative count is an error rather than a huge read
+    let data = [0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+    let e = Shadowing::bin_read(&mut Cursor::new(&data[..]), ReadMode::BE).unwrap_err();
+    assert_eq!(e.error.kind(), ErrorKind::InvalidData);
+    assert_eq!(e.position, Some(4));
+    assert!(e.to_string().contains("output"));
+}
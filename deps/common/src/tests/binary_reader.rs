@@ -0,0 +1,81 @@
+use crate::{
+    binary_reader::{ BinaryReader, MAX_PREALLOC },
+    readers::{ read_type, ReadMode },
+};
+use std::io::{ Cursor, ErrorKind };
+
+#[test]
+fn read_numbers_test() {
+    let mut data = Cursor::new(vec![
+        0x12, 0x34,
+        0x12, 0x34,
+        0xFF, 0xFF, 0xFF, 0xFE,
+        0x01, 0x00, 0x00, 0x80,
+        0x00, 0x00, 0x80, 0x3F,
+        0x80,
+    ]);
+
+    assert_eq!(data.read_u16_le().unwrap(), 0x3412);
+    assert_eq!(data.read_u16_be().unwrap(), 0x1234);
+    assert_eq!(data.read_i32_be().unwrap(), -2);
+    assert_eq!(data.read_u32_le().unwrap(), 0x8000_0001);
+    assert_eq!(data.read_f32_le().unwrap(), 1.0);
+    assert_eq!(data.read_i8().unwrap(), -128);
+}
+
+#[test]
+fn short_read_test() {
+    let mut data = Cursor::new(vec![1, 2, 3, 4, 5]);
+
+    assert_eq!(data.read_u32_be().unwrap(), 0x0102_0304);
+
+    let e = data.read_u16_le().unwrap_err();
+    assert!(e.is_eof());
+    assert_eq!(e.position, Some(4));
+    assert_eq!(e.error.kind(), ErrorKind::UnexpectedEof);
+    assert!(e.to_string().ends_with("at offset 0x4"));
+}
+
+#[test]
+fn huge_length_test() {
+    let mut data = Cursor::new(vec![0xAB; MAX_PREALLOC * 2 + 1]);
+    data.set_position(1);
+
+    //a length read from a corrupt header fails without reserving it first
+    let e = data.read_vec(usize::MAX).unwrap_err();
+    assert!(e.is_eof());
+    assert_eq!(e.position, Some(1));
+
+    data.set_position(1);
+    let bytes = data.read_vec(MAX_PREALLOC * 2).unwrap();
+    assert_eq!(bytes.len(), MAX_PREALLOC * 2);
+    assert!(bytes.iter().all(|b| *b == 0xAB));
+}
+
+#[test]
+fn read_bytes_test() {
+    let mut data = Cursor::new(b"ART\0xyzWORLDMAP1234".to_vec());
+
+    assert_eq!(data.read_fixed_string(7).unwrap(), "ART");
+    assert_eq!(data.read_fixed_string(8).unwrap(), "WORLDMAP");
+    assert_eq!(BinaryReader::read_array::<2>(&mut data).unwrap(), *b"12");
+    assert_eq!(data.read_vec(2).unwrap(), b"34");
+    assert!(data.read_vec(1).is_err());
+
+    //works through trait objects too
+    let stream: &mut dyn crate::Stream = &mut Cursor::new(vec![7u8]);
+    assert_eq!(stream.read_u8().unwrap(), 7);
+}
+
+#[test]
+fn read_type_endianness_test() {
+    let data: &[u8] = &[1, 2];
+
+    let le: u16 = read_type(ReadMode::LE, &mut &data[..]).unwrap();
+    let be: u16 = read_type(ReadMode::BE, &mut &data[..]).unwrap();
+    let ne: u16 = read_type(ReadMode::NE, &mut &data[..]).unwrap();
+
+    assert_eq!(le, 0x0201);
+    assert_eq!(be, 0x0102);
+    assert_eq!(ne, u16::from_ne_bytes([1, 2]));
+}
@@ -0,0 +1,89 @@
+use crate::{
+    BinaryReader,
+    BinaryWriter,
+    LengthPrefix,
+};
+use std::io::{ Cursor, ErrorKind };
+
+#[test]
+fn write_numbers_test() {
+    let mut out = Cursor::new(Vec::new());
+
+    out.write_u16_le(0x3412).unwrap();
+    out.write_u16_be(0x1234).unwrap();
+    out.write_i32_be(-2).unwrap();
+    out.write_u32_le(0x8000_0001).unwrap();
+    out.write_f32_le(1.0).unwrap();
+    out.write_i8(-128).unwrap();
+
+    assert_eq!(out.into_inner(), [
+        0x12, 0x34,
+        0x12, 0x34,
+        0xFF, 0xFF, 0xFF, 0xFE,
+        0x01, 0x00, 0x00, 0x80,
+        0x00, 0x00, 0x80, 0x3F,
+        0x80,
+    ]);
+}
+
+#[test]
+fn strings_test() {
+    let mut out = Cursor::new(Vec::new());
+
+    out.write_fixed_string("ART", 8).unwrap();
+    out.write_fixed_string("WORLDMAP", 8).unwrap();
+    out.write_prefixed_string("color.pal", LengthPrefix::U32Le).unwrap();
+    out.write_prefixed_string("art", LengthPrefix::U8).unwrap();
+
+    let e = out.write_fixed_string("too long", 4).unwrap_err();
+    assert_eq!(e.error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(e.position, Some(33));
+
+    let long = "x".repeat(256);
+    assert!(out.write_prefixed_string(&long, LengthPrefix::U8).is_err());
+
+    //failed writes leave the stream alone
+    let data = out.into_inner();
+    assert_eq!(data.len(), 33);
+    assert_eq!(&data[.. 8], b"ART\0\0\0\0\0");
+
+    let mut data = Cursor::new(data);
+    assert_eq!(data.read_fixed_string(8).unwrap(), "ART");
+    assert_eq!(data.read_fixed_string(8).unwrap(), "WORLDMAP");
+    assert_eq!(data.read_prefixed_string(LengthPrefix::U32Le).unwrap(), "color.pal");
+    assert_eq!(data.read_prefixed_string(LengthPrefix::U8).unwrap(), "art");
+}
+
+#[test]
+fn alignment_test() {
+    let mut out = Cursor::new(Vec::new());
+
+    out.pad_to_alignment(4, 0xFF).unwrap();
+    assert_eq!(out.position(), 0);
+
+    out.write_u8(1).unwrap();
+    out.pad_to_alignment(4, 0xFF).unwrap();
+    out.write_u8(2).unwrap();
+    out.pad_to_alignment(2, 0).unwrap();
+
+    assert_eq!(out.into_inner(), [1, 0xFF, 0xFF, 0xFF, 2, 0]);
+}
+
+#[test]
+fn back_patch_test() {
+    let mut out = Cursor::new(Vec::new());
+
+    out.write_bytes(b"RIFF").unwrap();
+    let size = out.reserve(4).unwrap();
+    assert_eq!(size, 4);
+
+    out.write_bytes(b"WAVEdata").unwrap();
+    let len = out.position() - size - 4;
+    out.patch_u32_le(size, len as u32).unwrap();
+
+    //writing carries on from the end
+    out.write_u8(0).unwrap();
+    out.patch_u16_be(10, 0x4142).unwrap();
+
+    assert_eq!(out.into_inner(), b"RIFF\x08\0\0\0WAABdata\0");
+}
@@ -56,3 +56,16 @@ fn get_bits_test() {
     let value = reader.get_bits_u16(16).unwrap();
     assert_eq!(value, 33);
 }
+
+#[test]
+fn extend_test() {
+    let mut reader = BitReader::new(vec![0b1010_0101]);
+
+    assert_eq!(reader.get_bits_u8(4).unwrap(), 0b0101);
+    reader.extend_from_slice(&[0b0000_0011]);
+    assert_eq!(reader.bit_size(), 12);
+
+    assert_eq!(reader.get_bits_u8(6).unwrap(), 0b11_1010);
+    assert_eq!(reader.get_bits_u8(6).unwrap(), 0);
+    assert_eq!(reader.get_bit(), None);
+}
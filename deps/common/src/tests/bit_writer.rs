@@ -0,0 +1,21 @@
+use crate::{
+    BitReader,
+    BitWriter,
+};
+
+#[test]
+fn put_bits_test() {
+    let mut writer = BitWriter::new();
+    writer.put_bits(9, 4);
+    writer.put_bits(33, 16);
+    writer.put_bit(1);
+    assert_eq!(writer.bit_size(), 21);
+
+    let data = writer.into_bytes();
+    assert_eq!(data, [0b0001_1001, 0b0000_0010, 0b0001_0000]);
+
+    let mut reader = BitReader::new(data);
+    assert_eq!(reader.get_bits_u8(4).unwrap(), 9);
+    assert_eq!(reader.get_bits_u16(16).unwrap(), 33);
+    assert_eq!(reader.get_bit().unwrap(), 1);
+}
@@ -1,3 +1,9 @@
+mod binary;
+mod binary_reader;
+mod binary_writer;
 mod bit_reader;
+mod bit_writer;
 mod vec_2d;
 mod readers;
+mod wav;
+mod slice_bit_reader;
@@ -3,6 +3,8 @@ use std::error::Error;
 use crate::readers::*;
 
 #[test]
+//the shifts and bool literals spell out the expected values
+#[allow(clippy::identity_op, clippy::bool_assert_comparison)]
 fn type_reader_test() -> Result<(), Box<dyn Error>> {
     fn make_data() -> &'static [u8] {
         &[
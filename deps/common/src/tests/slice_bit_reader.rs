@@ -0,0 +1,98 @@
+use crate::{
+    bit_reader::BitReader,
+    slice_bit_reader::SliceBitReader,
+};
+
+#[test]
+fn get_bits_test() {
+    let bits = [
+        25, 2, 128, 204,
+        8, 255, 2, 5,
+    ];
+
+    let mut reader = SliceBitReader::new(&bits);
+
+    assert_eq!(reader.get_bits(4), Some(9));
+    assert_eq!(reader.get_bits(16), Some(33));
+    assert_eq!(reader.position(), 20);
+    assert_eq!(reader.bits_remaining(), 44);
+}
+
+#[test]
+fn peek_test() {
+    let mut reader = SliceBitReader::new(&[0b1010_0101, 0b0000_0011]);
+
+    assert_eq!(reader.peek_bits(4), Some(0b0101));
+    assert_eq!(reader.peek_bits(4), Some(0b0101));
+    assert_eq!(reader.position(), 0);
+
+    assert_eq!(reader.get_bits(6), Some(0b10_0101));
+    assert_eq!(reader.peek_bits(6), Some(0b00_1110));
+    assert_eq!(reader.position(), 6);
+}
+
+#[test]
+fn skip_and_align_test() {
+    let data: Vec<u8> = (0 .. 32).collect();
+    let mut reader = SliceBitReader::new(&data);
+
+    reader.get_bits(3).unwrap();
+    assert!(!reader.is_aligned());
+    reader.align_to_byte();
+    assert!(reader.is_aligned());
+    assert_eq!(reader.position(), 8);
+
+    //aligning when already aligned does nothing
+    reader.align_to_byte();
+    assert_eq!(reader.position(), 8);
+
+    //within the buffered bits and past them
+    reader.skip_bits(8).unwrap();
+    assert_eq!(reader.get_bits(8), Some(2));
+    reader.skip_bits(20 * 8 + 4).unwrap();
+    assert_eq!(reader.get_bits(4), Some(23 >> 4));
+    assert_eq!(reader.get_bits(8), Some(24));
+}
+
+#[test]
+fn end_of_data_test() {
+    let mut reader = SliceBitReader::new(&[0xFF, 0x01]);
+
+    reader.get_bits(12).unwrap();
+    assert_eq!(reader.get_bits(5), None);
+    assert_eq!(reader.peek_bits(5), None);
+    assert_eq!(reader.skip_bits(5), None);
+
+    //failed reads don't consume anything
+    assert_eq!(reader.position(), 12);
+    assert_eq!(reader.get_bits(4), Some(0));
+    assert_eq!(reader.get_bit(), None);
+    assert_eq!(reader.bits_remaining(), 0);
+
+    assert_eq!(SliceBitReader::new(&[]).get_bit(), None);
+    assert_eq!(SliceBitReader::new(&[0; 8]).get_bits(33), None);
+}
+
+#[test]
+fn matches_bit_reader_test() {
+    //simple lcg, so the test doesn't need a rand dependency
+    let mut state = 0x1234_5678u32;
+    let mut next = || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        state >> 8
+    };
+
+    let data: Vec<u8> = (0 .. 257).map(|_| next() as u8).collect();
+    let mut expected = BitReader::new(data.clone());
+    let mut reader = SliceBitReader::new(&data);
+
+    loop {
+        let bits = (next() % 33) as u8;
+        let value = expected.get_bits_u32(bits);
+        assert_eq!(reader.get_bits(bits), value, "reading {} bits at {}", bits, reader.position());
+
+        if value.is_none() {
+            break;
+        }
+    }
+}
@@ -11,6 +11,8 @@ fn insert_test() {
 }
 
 #[test]
+//the leading zeros keep the grid lined up
+#[allow(clippy::zero_prefixed_literal)]
 fn get_test() {
     let data = [
         00, 01, 02, 03,
@@ -33,3 +35,113 @@ fn get_test() {
 
     assert_eq!(v.get(10, 10), None);
 }
+
+fn grid() -> Vec2d<u8> {
+    let data: Vec<u8> = (0 .. 16).collect();
+    Vec2d::from_slice(4, 4, &data)
+}
+
+#[test]
+fn get_edges_test() {
+    let v = grid();
+
+    //one past the last column doesn't wrap onto the next row
+    assert_eq!(v.get(4, 0), None);
+    assert_eq!(v.get(0, 4), None);
+    assert_eq!(v.get(3, 3), Some(&15));
+}
+
+#[test]
+fn rows_test() {
+    let mut v = grid();
+
+    assert_eq!(v.row(1).unwrap(), &[4, 5, 6, 7]);
+    assert_eq!(v.row(4), None);
+    assert_eq!(v.rows().count(), 4);
+    assert_eq!(v.rows().last().unwrap(), &[12, 13, 14, 15]);
+
+    for row in v.rows_mut() {
+        row[0] = 0xFF;
+    }
+    v.row_mut(2).unwrap()[3] = 0xEE;
+
+    assert_eq!(v.row(2).unwrap(), &[0xFF, 9, 10, 0xEE]);
+}
+
+#[test]
+fn zero_width_test() {
+    //the height is kept, but there's nothing to iterate
+    let mut v = Vec2d::<u8>::new(0, 3);
+    assert_eq!((v.width(), v.height(), v.size()), (0, 3, 0));
+    assert_eq!(v.rows().count(), 0);
+    assert_eq!(v.rows_mut().count(), 0);
+    assert_eq!(v.row(0), None);
+    assert_eq!(v.get(0, 0), None);
+
+    //zero width views and blits are empty too
+    let g = grid();
+    let view = g.view(0, 0, 0, 3).unwrap();
+    assert_eq!(view.rows().count(), 0);
+    assert_eq!(view.row(0), None);
+    assert_eq!(view.iter().count(), 0);
+
+    let mut d = grid();
+    d.blit(&v, 0, 0, |_| false);
+    d.blit(view, 1, 1, |_| false);
+    assert_eq!(d, grid());
+}
+
+#[test]
+fn view_test() {
+    let v = grid();
+    let view = v.view(1, 2, 2, 2).unwrap();
+
+    assert_eq!((view.width(), view.height()), (2, 2));
+    assert_eq!(view.get(0, 0), Some(&9));
+    assert_eq!(view.get(2, 0), None);
+    assert_eq!(view.iter().copied().collect::<Vec<_>>(), [9, 10, 13, 14]);
+    assert_eq!(view.to_vec2d(), Vec2d::from_slice(2, 2, &[9, 10, 13, 14]));
+
+    assert!(v.view(3, 0, 2, 1).is_none());
+    assert!(v.view(0, 0, 4, 4).is_some());
+}
+
+#[test]
+fn blit_test() {
+    let sprite = Vec2d::from_slice(2, 2, &[1, 0, 0, 2]);
+
+    let mut dst = Vec2d::new_with(4, 3, 9u8);
+    dst.blit(&sprite, 1, 1, |&c| c == 0);
+    assert_eq!(dst.as_slice(), &[
+        9, 9, 9, 9,
+        9, 1, 9, 9,
+        9, 9, 2, 9,
+    ]);
+
+    //clipped on the top left and bottom right
+    let mut dst = Vec2d::new_with(3, 3, 9u8);
+    dst.blit(&sprite, -1, -1, |_| false);
+    dst.blit(&sprite, 2, 2, |_| false);
+    assert_eq!(dst.as_slice(), &[
+        2, 9, 9,
+        9, 9, 9,
+        9, 9, 1,
+    ]);
+
+    //entirely outside
+    dst.blit(&sprite, 5, -5, |_| false);
+    assert_eq!(dst.get(2, 0), Some(&9));
+
+    let src = grid();
+    let mut dst = Vec2d::new(2, 2);
+    dst.blit(src.view(2, 1, 2, 2).unwrap(), 0, 0, |_| false);
+    assert_eq!(dst.as_slice(), &[6, 7, 10, 11]);
+}
+
+#[test]
+fn map_test() {
+    let v = grid().map(|&c| c as u32 * 2);
+
+    assert_eq!((v.width(), v.height()), (4, 4));
+    assert_eq!(v.get(3, 3), Some(&30));
+}
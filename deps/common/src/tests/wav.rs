@@ -0,0 +1,121 @@
+use std::io::Cursor;
+use crate::{
+    Wav,
+    wav::{ InfoEntry, WavError },
+};
+
+//canonical header as written by sox, 22050 Hz mono 16 bit
+const MONO_16: &[u8] = &[
+    0x52, 0x49, 0x46, 0x46, 0x2A, 0x00, 0x00, 0x00, 0x57, 0x41, 0x56, 0x45,
+    0x66, 0x6D, 0x74, 0x20, 0x10, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x22, 0x56, 0x00, 0x00, 0x44, 0xAC, 0x00, 0x00, 0x02, 0x00, 0x10, 0x00,
+    0x64, 0x61, 0x74, 0x61, 0x06, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0xFF, 0xFF, 0x00, 0x01,
+];
+
+//11025 Hz stereo 8 bit with a title, odd sized text is padded
+const STEREO_8_INFO: &[u8] = &[
+    0x52, 0x49, 0x46, 0x46, 0x40, 0x00, 0x00, 0x00, 0x57, 0x41, 0x56, 0x45,
+    0x66, 0x6D, 0x74, 0x20, 0x10, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x02, 0x00, 0x11, 0x2B, 0x00, 0x00, 0x22, 0x56, 0x00, 0x00, 0x02, 0x00, 0x08, 0x00,
+    0x64, 0x61, 0x74, 0x61, 0x04, 0x00, 0x00, 0x00,
+    0x80, 0x00, 0xFF, 0x81,
+    0x4C, 0x49, 0x53, 0x54, 0x10, 0x00, 0x00, 0x00, 0x49, 0x4E, 0x46, 0x4F,
+    0x49, 0x4E, 0x41, 0x4D, 0x03, 0x00, 0x00, 0x00, b'a', b'b', 0x00, 0x00,
+];
+
+fn stereo_8() -> Wav {
+    Wav {
+        channels: 2,
+        sample_rate: 11025,
+        bits_per_sample: 8,
+        samples: vec![0, -32768, 32512, 256],
+        info: Vec::new(),
+    }
+    .with_info(*b"INAM", "ab")
+}
+
+#[test]
+fn write_test() {
+    let wav = Wav::new(1, 22050, vec![1, -1, 256]);
+    assert_eq!(wav.block_align(), 2);
+    assert_eq!(wav.to_bytes().unwrap(), MONO_16);
+
+    let wav = stereo_8();
+    assert_eq!(wav.block_align(), 2);
+    assert_eq!(wav.bytes_per_sec(), 22050);
+    assert_eq!(wav.to_bytes().unwrap(), STEREO_8_INFO);
+}
+
+#[test]
+fn read_test() {
+    let wav = Wav::open(&mut Cursor::new(MONO_16)).unwrap();
+    assert_eq!(wav, Wav::new(1, 22050, vec![1, -1, 256]));
+
+    let wav = Wav::open(&mut Cursor::new(STEREO_8_INFO)).unwrap();
+    assert_eq!(wav, stereo_8());
+    assert_eq!(wav.info, [InfoEntry { id: *b"INAM", text: "ab".into() }]);
+}
+
+#[test]
+fn extensible_test() {
+    let mut data = b"RIFF\0\0\0\0WAVE".to_vec();
+
+    //WAVE_FORMAT_EXTENSIBLE with a PCM sub format, and a wrong block align
+    data.extend_from_slice(b"fmt \x28\0\0\0");
+    data.extend_from_slice(&[0xFE, 0xFF, 0x01, 0x00, 0x22, 0x56, 0x00, 0x00, 0, 0, 0, 0, 0x00, 0x01, 0x10, 0x00]);
+    data.extend_from_slice(&[22, 0, 16, 0, 4, 0, 0, 0, 0x01, 0x00]);
+    data.extend_from_slice(&[0; 14]);
+
+    //unknown odd sized chunk
+    data.extend_from_slice(b"fact\x03\0\0\0abc\0");
+    data.extend_from_slice(b"data\x04\0\0\0\x10\x00\x20\x00");
+
+    let wav = Wav::open(&mut Cursor::new(data)).unwrap();
+    assert_eq!(wav.channels, 1);
+    assert_eq!(wav.block_align(), 2);
+    assert_eq!(wav.samples, [16, 32]);
+}
+
+#[test]
+fn partial_frame_test() {
+    //forcing a channel count that doesn't divide the sample count
+    let wav = Wav::new(2, 22050, vec![1, 2, 3]);
+    let data = wav.to_bytes().unwrap();
+    assert_eq!(&data[40 .. 44], &8u32.to_le_bytes());
+
+    let read = Wav::open(&mut Cursor::new(data)).unwrap();
+    assert_eq!(read.samples, [1, 2, 3, 0]);
+}
+
+#[test]
+fn error_test() {
+    assert!(matches!(Wav::new(0, 22050, vec![]).to_bytes(), Err(WavError::BadFormat)));
+
+    let mut wav = Wav::new(1, 22050, vec![]);
+    wav.bits_per_sample = 24;
+    assert!(matches!(wav.to_bytes(), Err(WavError::UnsupportedFormat { bits_per_sample: 24, .. })));
+
+    assert!(matches!(Wav::open(&mut Cursor::new(b"RIFX\0\0\0\0WAVE")), Err(WavError::BadMagic)));
+    assert!(matches!(Wav::open(&mut Cursor::new(&MONO_16[.. 36])), Err(WavError::MissingChunk("data"))));
+
+    //too many channels for the 16 bit block align field
+    let wide = Wav::new(u16::MAX, 22050, vec![0; 4]);
+    assert_eq!(wide.block_align(), 131_070);
+    assert!(matches!(wide.to_bytes(), Err(WavError::BadFormat)));
+
+    //a data chunk claiming far more than the file holds
+    let mut huge = MONO_16.to_vec();
+    huge[40 .. 44].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        Wav::open(&mut Cursor::new(huge)),
+        Err(WavError::ReadError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+    ));
+
+    let mut float = MONO_16.to_vec();
+    float[20] = 3;
+    assert!(matches!(
+        Wav::open(&mut Cursor::new(float)),
+        Err(WavError::UnsupportedFormat { format: 3, bits_per_sample: 16 })
+    ));
+}
@@ -25,44 +25,131 @@ impl<T> Vec2d<T> {
         self._height
     }
 
-    pub fn size(&self) -> usize { 
+    pub fn size(&self) -> usize {
         self._width * self._height
     }
 
-    fn get_index(&self, x: usize, y: usize) -> usize {
-        (y * self._width) + x
+    fn get_index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self._width && y < self._height {
+            Some((y * self._width) + x)
+        } else {
+            None
+        }
     }
 
     pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
-        let idx = self.get_index(x, y);
+        let idx = self.get_index(x, y)?;
         self.data.get_mut(idx)
     }
 
     pub fn get(&self, x: usize, y: usize) -> Option<&T> {
-        let idx = self.get_index(x, y);
+        let idx = self.get_index(x, y)?;
         self.data.get(idx)
     }
 
     pub fn insert(&mut self, x: usize, y: usize, val: T) -> Option<&mut T> {
-        let idx = self.get_index(x, y);
-        let elem = self.data.get_mut(idx)?;
+        let elem = self.get_mut(x, y)?;
 
         *elem = val;
         Some(elem)
     }
 
-    pub fn iter(&self) -> std::slice::Iter<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.data.iter()
     }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.data.iter_mut()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        let start = self.get_index(0, y)?;
+        Some(&self.data[start .. start + self._width])
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        let start = self.get_index(0, y)?;
+        Some(&mut self.data[start .. start + self._width])
+    }
+
+    /// Iterates rows top to bottom, each `width` elements long
+    ///
+    /// A zero width grid holds no elements, so it has no rows whatever its
+    /// `height`, the same as `row` returning `None` for every `y`.
+    pub fn rows(&self) -> std::slice::ChunksExact<'_, T> {
+        //`max` only avoids the chunk size panic, the data is empty anyway
+        self.data.chunks_exact(self._width.max(1))
+    }
+
+    pub fn rows_mut(&mut self) -> std::slice::ChunksExactMut<'_, T> {
+        self.data.chunks_exact_mut(self._width.max(1))
+    }
+
+    /// Borrows the rectangle at `x`, `y`, or `None` if it doesn't fit inside the grid
+    pub fn view(&self, x: usize, y: usize, width: usize, height: usize) -> Option<View<'_, T>> {
+        if x.checked_add(width)? > self._width || y.checked_add(height)? > self._height {
+            return None;
+        }
+
+        Some(View { grid: self, x, y, width, height })
+    }
+
+    /// Copies `src` so its top left corner lands at `x`, `y`
+    ///
+    /// The copy is clipped to this grid, so `x` and `y` may be negative or run
+    /// past the edges. Elements for which `is_transparent` returns true are skipped.
+    ///
+    /// * `src` - a whole `Vec2d` or a `View` into one
+    /// * `is_transparent` - transparent key test, e.g. `|&c| c == 0` for palette index 0
+    pub fn blit<'a>(
+        &mut self,
+        src: impl Into<View<'a, T>>,
+        x: isize,
+        y: isize,
+        mut is_transparent: impl FnMut(&T) -> bool,
+    ) where T: Clone + 'a {
+        let src = src.into();
+
+        //first source column and row that land inside this grid
+        let src_x = x.min(0).unsigned_abs();
+        let src_y = y.min(0).unsigned_abs();
+        let dst_x = x.max(0) as usize;
+        let dst_y = y.max(0) as usize;
+
+        let width = src.width.saturating_sub(src_x).min(self._width.saturating_sub(dst_x));
+        let height = src.height.saturating_sub(src_y).min(self._height.saturating_sub(dst_y));
+        if width == 0 {
+            return;
+        }
+
+        for row in 0 .. height {
+            let src_row = &src.row(src_y + row).unwrap()[src_x .. src_x + width];
+            let start = self.get_index(dst_x, dst_y + row).unwrap();
+            let dst_row = &mut self.data[start .. start + width];
+
+            for (dst, value) in dst_row.iter_mut().zip(src_row) {
+                if !is_transparent(value) {
+                    *dst = value.clone();
+                }
+            }
+        }
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Vec2d<U> {
+        let data = self.data.iter().map(f).collect();
+        Vec2d { data, _width: self._width, _height: self._height }
+    }
 }
 
 impl<T> Vec2d<T> where T: Default {
     pub fn new(width: usize, height: usize) -> Self {
         let size = Self::calc_size(width, height);
         let mut data = Self::with_capacity(width, height);
-        for _ in 0..size {
-            data.push(T::default());
-        }
+        data.resize_with(size, T::default);
 
         Self { data, _width: width, _height: height }
     }
@@ -71,10 +158,7 @@ impl<T> Vec2d<T> where T: Default {
 impl<T> Vec2d<T> where T: Clone {
     pub fn from_slice(width: usize, height: usize, slice: &[T]) -> Self {
         let size = Self::calc_size(width, height);
-        let mut data = Self::with_capacity(width, height);
-        for i in 0..size {
-            data.push(slice[i].clone());
-        }
+        let data = slice[.. size].to_vec();
 
         Self { data, _width: width, _height: height }
     }
@@ -96,15 +180,13 @@ impl<T> PartialEq for Vec2d<T> where T: PartialEq {
 
         same_elems && same_width && same_height
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        !self.eq(other)
-    }
 }
 
-impl<T> Into<Vec<T>> for Vec2d<T> {
-    fn into(self) -> Vec<T> {
-        self.data
+impl<T> Eq for Vec2d<T> where T: Eq {}
+
+impl<T> From<Vec2d<T>> for Vec<T> {
+    fn from(value: Vec2d<T>) -> Self {
+        value.data
     }
 }
 
@@ -120,3 +202,72 @@ impl<T> IndexMut<usize> for Vec2d<T> {
         &mut self.data[index]
     }
 }
+
+/// A borrowed rectangle inside a `Vec2d`, see `Vec2d::view`
+#[derive(Debug)]
+pub struct View<'a, T> {
+    grid: &'a Vec2d<T>,
+
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Clone for View<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for View<'_, T> {}
+
+impl<'a, T> View<'a, T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&'a T> {
+        if x < self.width && y < self.height {
+            self.grid.get(self.x + x, self.y + y)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> Option<&'a [T]> {
+        if y < self.height && self.width > 0 {
+            let row = self.grid.row(self.y + y)?;
+            Some(&row[self.x .. self.x + self.width])
+        } else {
+            None
+        }
+    }
+
+    /// Iterates rows top to bottom, like `Vec2d::rows` a zero width view has none
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> + 'a {
+        let view = *self;
+        let height = if self.width == 0 { 0 } else { self.height };
+        (0 .. height).map(move |y| view.row(y).unwrap())
+    }
+
+    /// Iterates the rectangle row by row
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + 'a {
+        self.rows().flatten()
+    }
+
+    pub fn to_vec2d(&self) -> Vec2d<T> where T: Clone {
+        let data = self.iter().cloned().collect();
+        Vec2d { data, _width: self.width, _height: self.height }
+    }
+}
+
+impl<'a, T> From<&'a Vec2d<T>> for View<'a, T> {
+    fn from(value: &'a Vec2d<T>) -> Self {
+        View { grid: value, x: 0, y: 0, width: value._width, height: value._height }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    binary_reader::MAX_PREALLOC,
+    readers::read_bytes,
+};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{ self, Read, Write },
+};
+
+const FORMAT_PCM: u16 = 0x0001;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+const FMT_SIZE: u32 = 16;
+
+#[derive(Debug)]
+pub enum WavError {
+    ReadError(io::Error),
+    WriteError(io::Error),
+    BadMagic,
+    MissingChunk(&'static str),
+    /// Only 8 and 16 bit PCM is supported
+    UnsupportedFormat{ format: u16, bits_per_sample: u16 },
+    /// Header values that can't describe any audio, e.g. no channels
+    BadFormat,
+}
+
+impl Display for WavError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use WavError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading wav data: {}", e),
+            WriteError(e) => write!(f, "Error writing wav data: {}", e),
+            BadMagic => write!(f, "Not a RIFF WAVE file"),
+            MissingChunk(id) => write!(f, "Missing '{}' chunk", id),
+            UnsupportedFormat { format, bits_per_sample } =>
+                write!(f, "Unsupported wav format {:#06x} with {} bits per sample", format, bits_per_sample),
+            BadFormat => write!(f, "Invalid wav format"),
+        }
+    }
+}
+
+impl Error for WavError {}
+
+/// Entry of the `LIST` `INFO` chunk, e.g. `INAM` for the title or `ICMT` for a comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfoEntry {
+    pub id: [u8; 4],
+    pub text: String,
+}
+
+/// PCM audio in a RIFF WAVE container
+///
+/// * `samples` - interleaved over `channels`. 8 bit audio is kept scaled up to
+///   the 16 bit range, so writing it back as 8 bit is lossless
+/// * `bits_per_sample` - 8 or 16, used when writing
+/// * `info` - optional `LIST` metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wav {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+
+    pub samples: Vec<i16>,
+    pub info: Vec<InfoEntry>,
+}
+
+impl Wav {
+    /// 16 bit audio without metadata
+    pub fn new(channels: u16, sample_rate: u32, samples: Vec<i16>) -> Self {
+        Self {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            samples,
+            info: Vec::new(),
+        }
+    }
+
+    pub fn with_info(mut self, id: [u8; 4], text: impl Into<String>) -> Self {
+        self.info.push(InfoEntry { id, text: text.into() });
+        self
+    }
+
+    /// Bytes per sample frame, one sample for every channel
+    ///
+    /// Wider than the header field, `write` fails if it doesn't fit.
+    pub fn block_align(&self) -> u32 {
+        self.channels as u32 * self.bits_per_sample as u32 / 8
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.sample_rate as u64 * self.block_align() as u64
+    }
+
+    /// Reads a wav file, chunks other than `fmt `, `data` and `LIST` are skipped
+    ///
+    /// The block align and byte rate fields are ignored, since some writers
+    /// (including older versions of this project) got them wrong.
+    pub fn open(stream: &mut impl Read) -> Result<Self, WavError> {
+        let riff: [u8; 4] = read_bytes(stream).map_err(WavError::ReadError)?;
+        let _size = read_u32(stream)?;
+        let wave: [u8; 4] = read_bytes(stream).map_err(WavError::ReadError)?;
+        if &riff != b"RIFF" || &wave != b"WAVE" {
+            return Err(WavError::BadMagic);
+        }
+
+        let mut format = None;
+        let mut samples = None;
+        let mut info = Vec::new();
+
+        while let Some((id, size)) = read_chunk_header(stream)? {
+            let data = read_chunk(stream, size)?;
+            //chunks are padded to an even size
+            if size % 2 == 1 {
+                let _ = read_bytes::<1>(stream);
+            }
+
+            match &id {
+                b"fmt " => format = Some(read_format(&data)?),
+                b"data" => samples = Some(data),
+                b"LIST" => info.extend(read_info(&data)),
+                _ => {},
+            }
+        }
+
+        let (channels, sample_rate, bits_per_sample) = format.ok_or(WavError::MissingChunk("fmt "))?;
+        let data = samples.ok_or(WavError::MissingChunk("data"))?;
+
+        let samples = match bits_per_sample {
+            8 => data.iter().map(|b| (*b as i16 - 128) << 8).collect(),
+            _ => data.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect(),
+        };
+
+        Ok(Self { channels, sample_rate, bits_per_sample, samples, info })
+    }
+
+    /// Writes a canonical wav file, with the `LIST` chunk after the audio data
+    ///
+    /// A trailing partial sample frame (e.g. from forcing the wrong channel
+    /// count on a source) is padded with silence, so the data stays block aligned.
+    pub fn write(&self, output: &mut impl Write) -> Result<(), WavError> {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return Err(WavError::BadFormat);
+        }
+        if self.bits_per_sample != 8 && self.bits_per_sample != 16 {
+            return Err(WavError::UnsupportedFormat { format: FORMAT_PCM, bits_per_sample: self.bits_per_sample });
+        }
+        let block_align = u16::try_from(self.block_align()).map_err(|_| WavError::BadFormat)?;
+        let bytes_per_sec = u32::try_from(self.bytes_per_sec()).map_err(|_| WavError::BadFormat)?;
+
+        let channels = self.channels as usize;
+        let frames = self.samples.len().div_ceil(channels);
+        let sample_size = self.bits_per_sample as usize / 8;
+        let data_size = frames * channels * sample_size;
+        let list = self.info_chunk();
+
+        let riff_size = 4
+            + 8 + FMT_SIZE as usize
+            + 8 + data_size + data_size % 2
+            + list.len();
+
+        let mut out = Vec::with_capacity(8 + riff_size);
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(riff_size as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+
+        out.extend_from_slice(b"fmt ");
+        out.extend_from_slice(&FMT_SIZE.to_le_bytes());
+        out.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+        out.extend_from_slice(&self.channels.to_le_bytes());
+        out.extend_from_slice(&self.sample_rate.to_le_bytes());
+        out.extend_from_slice(&bytes_per_sec.to_le_bytes());
+        out.extend_from_slice(&block_align.to_le_bytes());
+        out.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&(data_size as u32).to_le_bytes());
+        let padding = frames * channels - self.samples.len();
+        for s in self.samples.iter().chain(std::iter::repeat_n(&0, padding)) {
+            match self.bits_per_sample {
+                8 => out.push(((s >> 8) + 128) as u8),
+                _ => out.extend_from_slice(&s.to_le_bytes()),
+            }
+        }
+        if data_size % 2 == 1 {
+            out.push(0);
+        }
+
+        out.extend_from_slice(&list);
+
+        output.write_all(&out).map_err(WavError::WriteError)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WavError> {
+        let mut output = Vec::new();
+        self.write(&mut output)?;
+        Ok(output)
+    }
+
+    fn info_chunk(&self) -> Vec<u8> {
+        if self.info.is_empty() {
+            return Vec::new();
+        }
+
+        let mut body = b"INFO".to_vec();
+        for entry in &self.info {
+            //text is stored null terminated
+            let size = entry.text.len() + 1;
+            body.extend_from_slice(&entry.id);
+            body.extend_from_slice(&(size as u32).to_le_bytes());
+            body.extend_from_slice(entry.text.as_bytes());
+            body.push(0);
+            if size % 2 == 1 {
+                body.push(0);
+            }
+        }
+
+        let mut chunk = b"LIST".to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        chunk.extend(body);
+        chunk
+    }
+}
+
+fn read_u32(stream: &mut impl Read) -> Result<u32, WavError> {
+    read_bytes(stream)
+        .map(u32::from_le_bytes)
+        .map_err(WavError::ReadError)
+}
+
+//the size comes from the file, so the buffer only grows as data actually arrives
+fn read_chunk(stream: &mut impl Read, size: u32) -> Result<Vec<u8>, WavError> {
+    let mut data = Vec::with_capacity((size as usize).min(MAX_PREALLOC));
+    stream.take(size as u64)
+        .read_to_end(&mut data)
+        .map_err(WavError::ReadError)?;
+
+    if data.len() < size as usize {
+        return Err(WavError::ReadError(io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(data)
+}
+
+//returns None at the end of the stream
+fn read_chunk_header(stream: &mut impl Read) -> Result<Option<([u8; 4], u32)>, WavError> {
+    let mut id = [0u8; 4];
+    let mut read = 0;
+    while read < id.len() {
+        match stream.read(&mut id[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => return Err(WavError::ReadError(io::ErrorKind::UnexpectedEof.into())),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(WavError::ReadError(e)),
+        }
+    }
+
+    Ok(Some((id, read_u32(stream)?)))
+}
+
+fn read_format(data: &[u8]) -> Result<(u16, u32, u16), WavError> {
+    if data.len() < FMT_SIZE as usize {
+        return Err(WavError::BadFormat);
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+    let mut format = u16_at(0);
+    let channels = u16_at(2);
+    let sample_rate = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let bits_per_sample = u16_at(14);
+
+    //the first two bytes of the sub format guid are the actual format
+    if format == FORMAT_EXTENSIBLE && data.len() >= 26 {
+        format = u16_at(24);
+    }
+
+    if format != FORMAT_PCM || (bits_per_sample != 8 && bits_per_sample != 16) {
+        return Err(WavError::UnsupportedFormat { format, bits_per_sample });
+    }
+    if channels == 0 || sample_rate == 0 {
+        return Err(WavError::BadFormat);
+    }
+
+    Ok((channels, sample_rate, bits_per_sample))
+}
+
+//LIST chunks of other types than INFO are ignored
+fn read_info(data: &[u8]) -> Vec<InfoEntry> {
+    let mut entries = Vec::new();
+    let Some(mut data) = data.strip_prefix(b"INFO") else {
+        return entries;
+    };
+
+    while data.len() >= 8 {
+        let id = [data[0], data[1], data[2], data[3]];
+        let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let Some(text) = data.get(8 .. 8 + size) else { break };
+
+        let text = text.split(|b| *b == 0).next().unwrap_or_default();
+        entries.push(InfoEntry {
+            id,
+            text: String::from_utf8_lossy(text).into_owned(),
+        });
+
+        let next = (8 + size + size % 2).min(data.len());
+        data = &data[next..];
+    }
+
+    entries
+}
@@ -1,13 +1,16 @@
+use common::{ BinaryReadError, BinaryWriteError };
 use std::error::Error;
-use std::fmt::{self, write};
+use std::fmt;
 use DatError::*;
 
-pub type Result<T> = std::result::Result<T, DatError>;
-
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum DatError {
     InvalidSig,
-    ReadError,
+    InvalidHeader,
+    ReadError(BinaryReadError),
+    WriteError(BinaryWriteError),
+    /// Archive contents past the 4 GiB the format can address
+    TooLarge,
     LZSSError,
     TreeError,
     TreeNodeError,
@@ -17,7 +20,10 @@ impl fmt::Display for DatError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             InvalidSig => write!(f, "Invalid Signature"),
-            ReadError => write!(f, "Error reading file"),
+            InvalidHeader => write!(f, "Header sizes don't fit the file"),
+            ReadError(e) => write!(f, "Error reading file: {}", e),
+            WriteError(e) => write!(f, "Error writing archive: {}", e),
+            TooLarge => write!(f, "Archive too large"),
             LZSSError => write!(f, "Error unpacking LZSS"),
             TreeError => write!(f, "Error creating tree"),
             TreeNodeError => write!(f, "Incorrect Node type"),
@@ -26,3 +32,15 @@ impl fmt::Display for DatError {
 }
 
 impl Error for DatError {}
+
+impl From<BinaryReadError> for DatError {
+    fn from(e: BinaryReadError) -> Self {
+        ReadError(e)
+    }
+}
+
+impl From<BinaryWriteError> for DatError {
+    fn from(e: BinaryWriteError) -> Self {
+        WriteError(e)
+    }
+}
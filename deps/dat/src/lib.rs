@@ -2,18 +2,27 @@
 mod tests;
 
 pub mod tree;
-use tree::{ FileTree, Node, FileEntry, FileState, };
-mod error;
+use tree::{ FileTree, FileEntry, FileState, };
+pub mod error;
 use error::{ DatError, DatError::* };
+pub mod records;
+use records::{ Dat1Directory, Dat1Entry, Dat2Entry };
+pub mod writer;
+pub use writer::Dat2Writer;
 
 use std::{
-    path::Path, 
-    fs::File, 
-    io::{Read, Seek, Cursor, SeekFrom, stdout, Write},
-    error::Error, collections::HashMap,
-    cell::{RefCell, RefMut},
+    io::{Read, Seek, Cursor, SeekFrom, Write},
+    error::Error,
+    cell::RefCell,
+};
+use common::{
+    BinRead,
+    BinaryReader,
+    BinaryReadError,
+    Stream,
+    binary_reader::MAX_PREALLOC,
+    readers::ReadMode,
 };
-use common::{ Stream, read_num };
 
 #[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
 pub enum Version {
@@ -24,13 +33,6 @@ pub enum Version {
     Dat2,
 }
 
-macro_rules! read_dat {
-    ($file:expr, $t: ty) => {{
-        read_num!($file, $t, be).ok_or(DatError::ReadError)
-    }};
-}
-
-
 pub struct DatFile {
     file: RefCell<Box<dyn Stream>>,
     version: Version,
@@ -44,29 +46,17 @@ impl DatFile {
 
 
     pub fn open(mut stream: impl Stream + 'static) -> Result<DatFile, Box<dyn Error>> {
-        let mut buf = [0u8; 4];
-
-        let mut read_be_int = || -> Result<i32, Box<dyn Error>> {
-            let r = stream.read(&mut buf)?;
-            if r < 4 {
-                return Err("too few bytes read".into());
-            }
-
-            Ok(i32::from_be_bytes(buf))
-        };
+        let dir_count = stream.read_i32_be()?;
+        let id = stream.read_i32_be()?;
+        let zero = stream.read_i32_be()?;
 
-        let dir_count = read_be_int()?;
-        let id = read_be_int()?;
-        let zero = read_be_int()?;
-
-        let version;
-        if dir_count > 0 &&
+        let version = if dir_count > 0 &&
             (id == 0x0A || id == 0x5E) &&
                 zero == 0 {
-            version = Version::Dat1;
+            Version::Dat1
         } else {
-            version = Version::Dat2;
-        }
+            Version::Dat2
+        };
 
         let mut dat = Self{
             file: RefCell::new(Box::new(stream)),
@@ -83,53 +73,30 @@ impl DatFile {
     }
 
     fn read_dat1(&mut self, dir_count: i32) -> Result<(), Box<dyn Error>> {
-        let _sum = self.read_int32_be()?;
+        let file = &mut **self.file.get_mut();
+        let _sum = file.read_i32_be()?;
 
         //directory names
-        let mut dir_names = Vec::with_capacity(dir_count as usize);
+        let mut dir_names = Vec::new();
         for _ in 0..dir_count {
-            let len = self.read_byte_be()?;
-            let mut dir_name_bytes = vec![0u8; len as usize];
-            self.file.get_mut().read(&mut dir_name_bytes)?;
-
-            let name = String::from_utf8(dir_name_bytes)?;
+            let len = file.read_u8()?;
+            let name = file.read_fixed_string(len as usize)?;
             dir_names.push(name);
         }
 
         //directory content
         for i in 0..dir_count {
-            let file_count = self.read_int32_be()?;
-            let _unknown1 = self.read_int32_be()?;
-            let _unknown2 = self.read_int32_be()?;
-            let _unknown3 = self.read_int32_be()?;
-
-            for _ in 0..file_count {
-                let file_name_len = self.read_byte_be()?;
-                let mut file_name_bytes = vec![0u8; file_name_len as usize];
-                self.file.get_mut().read(&mut file_name_bytes)?;
-
-                let file_name = String::from_utf8(file_name_bytes)?;
-                let file_attributes = self.read_int32_be()?;
-                let file_offset = self.read_int32_be()?;
-                let file_size = self.read_int32_be()?;
-                let file_size_compressed = self.read_int32_be()?;
-
-                let state = (file_size_compressed == 0 || file_attributes == 0x20)
-                    .then_some(FileState::Uncompressed)
-                    .unwrap_or(FileState::Compressed { size: file_size_compressed as usize });
-
-                let entry = FileEntry{
-                    offset: file_offset as usize,
-                    size: file_size as usize,
-                    state,
-                };
+            let directory = Dat1Directory::bin_read(file, ReadMode::BE)?;
 
-                let full_name;
-                if dir_names[i as usize] == "." {
-                    full_name = file_name;
+            for _ in 0..directory.file_count {
+                let record = Dat1Entry::bin_read(file, ReadMode::BE)?;
+                let entry = FileEntry::from(&record);
+
+                let full_name = if dir_names[i as usize] == "." {
+                    record.name
                 } else {
-                    full_name = format!("{}\\{}", dir_names[i as usize], file_name);
-                }
+                    format!("{}\\{}", dir_names[i as usize], record.name)
+                };
 
                 let name = full_name.to_ascii_lowercase();
                 self.registry.insert_unsorted(&name, entry)?;
@@ -144,20 +111,20 @@ impl DatFile {
     }
 
     fn read_dat2(&mut self) -> Result<(), Box<dyn Error>> {
-        let file = self.file.get_mut();
+        let file = &mut **self.file.get_mut();
         file.seek(SeekFrom::End(-8))?;
         
-        let tree_size = read_num!(file, u32, le).ok_or("failed to read tree size")?;
-        let data_size = read_num!(file, u32, le).ok_or("failed to read data size")?;
+        let tree_size = file.read_u32_le()?;
+        let data_size = file.read_u32_le()?;
 
-        let dir_tree_start = data_size - tree_size - 4;
-        file.seek(SeekFrom::Start(dir_tree_start as u64 - 4))?;
+        //the tree and the file count before it have to fit inside the file
+        let dir_tree_start = (data_size as u64).checked_sub(tree_size as u64 + 4).ok_or(InvalidHeader)?;
+        file.seek(SeekFrom::Start(dir_tree_start.checked_sub(4).ok_or(InvalidHeader)?))?;
 
-        let file_count = read_num!(file, u32, le).ok_or("failed to read file count")?;
+        let file_count = file.read_u32_le()?;
 
         //load the entire tree into a buffer :)
-        let mut dir_tree_buffer = Cursor::new(vec![0u8; tree_size as usize]);
-        file.read(dir_tree_buffer.get_mut())?;
+        let mut dir_tree_buffer = Cursor::new(file.read_vec(tree_size as usize)?);
 
         let mut entries_read = 0;
         dir_tree_buffer.seek(SeekFrom::Start(0))?;
@@ -165,29 +132,10 @@ impl DatFile {
             if dir_tree_buffer.position() >= dir_tree_buffer.get_ref().len() as u64 {
                 break;
             }
-            let name_size = read_num!(dir_tree_buffer, u32, le).ok_or("failed to read from file")?;
-            
-            let mut str_buf = vec![0u8; name_size as usize];
-            dir_tree_buffer.read(&mut str_buf)?;
-
-            let file_type = read_num!(dir_tree_buffer, u8, le).ok_or("failed to read from file")?;
-            let real_size = read_num!(dir_tree_buffer, u32, le).ok_or("failed to read from file")?;
-            let packed_size = read_num!(dir_tree_buffer, u32, le).ok_or("failed to read from file")?;
-            let offset = read_num!(dir_tree_buffer, u32, le).ok_or("failed to read from file")?;
-
-            let state = match file_type {
-                0 => FileState::Uncompressed,
-                _ => FileState::Compressed { size: packed_size as usize }
-            };
-
-            let entry = FileEntry {
-                offset: offset as usize,
-                size: real_size as usize,
-                state,
-            };
-
-            let name = String::from_utf8(str_buf)?;
-            let name = name.to_ascii_lowercase();
+            let record = Dat2Entry::bin_read(&mut dir_tree_buffer, ReadMode::LE)?;
+            let entry = FileEntry::from(&record);
+
+            let name = record.name.to_ascii_lowercase();
 
             self.registry.insert_unsorted(&name, entry)?;
             //self.registry.entry(name.to_ascii_lowercase()).or_insert(entry);
@@ -200,11 +148,11 @@ impl DatFile {
         Ok(())
     }
 
-    fn read_int32_be(&mut self) -> Result<i32, Box<dyn Error>> { read_int32_be(&mut self.file.get_mut()) }
-    fn read_int32_le(&mut self) -> Result<i32, Box<dyn Error>> { read_int32_le(&mut self.file.get_mut()) }
-    fn read_byte_be(&mut self) -> Result<i8, Box<dyn Error>> { read_byte_be(&mut self.file.get_mut()) }
-    fn read_byte_le(&mut self) -> Result<i8, Box<dyn Error>> { read_byte_le(&mut self.file.get_mut()) }
-
+    /// Reads and unpacks a file's contents
+    ///
+    /// A compressed file that unpacks to fewer bytes than the file table says
+    /// is an error. Older versions padded it with zeros, which let a corrupt
+    /// table entry allocate as much memory as it claimed.
     pub fn unpack_file(&self, entry: &FileEntry) -> Result<Vec<u8>, Box<dyn Error>> {
         self.file.borrow_mut().seek(SeekFrom::Start(entry.offset as u64))?;
 
@@ -223,10 +171,7 @@ impl DatFile {
     fn unpack_dat1(&self, entry: &FileEntry) -> Result<Vec<u8>, Box<dyn Error>> {
         let output = match entry.state {
             FileState::Uncompressed => {
-                let mut buffer = vec![0u8; entry.size];
-                self.file.borrow_mut().read(&mut buffer)?;
-
-                buffer
+                self.file.borrow_mut().read_vec(entry.size)?
             },
             FileState::Compressed { size: _ } => {
                 self.decompress_lzss(entry)?
@@ -243,10 +188,7 @@ impl DatFile {
     fn unpack_dat2(&self, entry: &FileEntry) -> Result<Vec<u8>, Box<dyn Error>> {
         let output = match entry.state {
             FileState::Uncompressed => {
-                let mut buffer = vec![0u8; entry.size];
-                self.file.borrow_mut().read(&mut buffer)?;
-
-                buffer
+                self.file.borrow_mut().read_vec(entry.size)?
             },
             FileState::Compressed { size } => {
                 self.decompress_zip(entry, size).ok_or("zip decompression error")?
@@ -260,24 +202,14 @@ impl DatFile {
         self.file
             .borrow_mut()
             .seek(SeekFrom::Start(entry.offset as u64))
-            .map_err(|_| ReadError)?;
-        let mut buf;
+            .map_err(BinaryReadError::from)?;
 
-        match entry.state {
-            FileState::Uncompressed => {
-                buf = vec![0u8; entry.size];
-            },
-            FileState::Compressed { size } => {
-                buf = vec![0u8; size];
-            }
+        let size = match entry.state {
+            FileState::Uncompressed => entry.size,
+            FileState::Compressed { size } => size,
         };
 
-        self.file
-            .borrow_mut()
-            .read(&mut buf)
-            .map_err(|_| ReadError)?;
-
-        Ok(buf)
+        Ok(self.file.borrow_mut().read_vec(size)?)
     }
 
     fn decompress_lzss(&self, entry: &FileEntry) -> Result<Vec<u8>, DatError> {
@@ -288,7 +220,8 @@ impl DatFile {
     }
 
     fn decompress_lzss_inner(mut input: Cursor<Vec<u8>>, output_size: usize) -> Result<Vec<u8>, DatError> {
-        let mut output = Cursor::new( vec![0u8; output_size] );
+        //grown as it's unpacked, the size comes from the file table
+        let mut output = Cursor::new( Vec::with_capacity(output_size.min(MAX_PREALLOC)) );
 
         let mut dictionary = [0u8; 4096];
         let mut dict_offset;
@@ -300,26 +233,25 @@ impl DatFile {
         let input_end = input.get_ref().len() as u64;
 
         while input.position() < input_end {
-            n = read_dat!(input, i16)?;
+            n = input.read_i16_be()?;
 
             if n == 0 {
                 return Err(LZSSError)
             } else if n < 0 {
-                let mut buf = vec![0u8; (-n) as usize];
-                input.read(&mut buf).map_err(|_| LZSSError)?;
+                let buf = input.read_vec(n.unsigned_abs() as usize).map_err(|_| LZSSError)?;
                 output.write_all(&buf).map_err(|_| LZSSError)?;
             } else {
                 dict_offset = dictionary.len() - 18;
-                dictionary.fill(' ' as u8);
+                dictionary.fill(b' ');
 
                 let block_end = input.position() + n as u64;
                 while input.position() < block_end {
-                    f = read_dat!(input, u8)?;
+                    f = input.read_u8()?;
                     let mut i = 0;
                     while i < 8 && input.position() < block_end {
 
                         if (f & 1) != 0 {
-                            let byte = read_dat!(input, u8)?;
+                            let byte = input.read_u8()?;
                             output.write(&[byte]).map_err(|_| LZSSError)?;
                             dictionary[dict_offset] = byte;
                             dict_offset += 1;
@@ -327,8 +259,8 @@ impl DatFile {
                                 dict_offset = 0;
                             }
                         } else {
-                            dict_index = read_dat!(input, u8)? as usize;
-                            l = read_dat!(input, u8)?;
+                            dict_index = input.read_u8()? as usize;
+                            l = input.read_u8()?;
                             let l_high = ((l & 0xF0) as usize) << 4;
                             dict_index |= l_high;
                             l &= 0x0F;
@@ -358,8 +290,13 @@ impl DatFile {
 
         }
 
-        Ok(output.into_inner())
+        let mut output = output.into_inner();
+        if output.len() < output_size {
+            return Err(LZSSError);
+        }
+        output.truncate(output_size);
 
+        Ok(output)
     }
 
     fn decompress_zip(&self, entry: &FileEntry, compressed_size: usize) -> Option<Vec<u8>> {
@@ -367,36 +304,12 @@ impl DatFile {
 
         //let _sig = read_num!(file, u16, le)?;
 
-        let mut input_buffer = vec![0u8; compressed_size - 0];
-        let mut output_buffer = vec![0u8; entry.size];
-
-        file.read(&mut input_buffer).ok()?;
+        let input_buffer = file.read_vec(compressed_size).ok()?;
+        let mut output_buffer = Vec::with_capacity(entry.size.min(MAX_PREALLOC));
 
-        let mut decoder = flate2::bufread::ZlibDecoder::new(input_buffer.as_slice());
-        decoder.read(&mut output_buffer).ok()?;
+        let decoder = flate2::bufread::ZlibDecoder::new(input_buffer.as_slice());
+        decoder.take(entry.size as u64).read_to_end(&mut output_buffer).ok()?;
 
-        Some(output_buffer)
+        (output_buffer.len() == entry.size).then_some(output_buffer)
     }
 }
-
-    
-
-fn read_int32(file: &mut Box<dyn Stream>, read_func: fn([u8; 4]) -> i32) -> Result<i32, Box<dyn Error>> {
-    let mut buf = [0u8; 4];
-    let sz = file.read(&mut buf)?;
-    if sz < 4 { return Err("not enough bytes read".into()); }
-
-    return Ok(read_func(buf));
-}
-fn read_int32_be(file: &mut Box<dyn Stream>) -> Result<i32, Box<dyn Error>> { read_int32(file, i32::from_be_bytes) }
-fn read_int32_le(file: &mut Box<dyn Stream>) -> Result<i32, Box<dyn Error>> { read_int32(file, i32::from_le_bytes) }
-
-fn read_byte(file: &mut Box<dyn Stream>, read_func: fn([u8; 1]) -> i8) -> Result<i8, Box<dyn Error>> {
-    let mut buf = [0u8; 1];
-    let sz = file.read(&mut buf)?;
-    if sz < 1 { return Err("not enough bytes read".into()); }
-
-    return Ok(read_func(buf));
-}
-fn read_byte_be(file: &mut Box<dyn Stream>) -> Result<i8, Box<dyn Error>> { read_byte(file, i8::from_be_bytes) }
-fn read_byte_le(file: &mut Box<dyn Stream>) -> Result<i8, Box<dyn Error>> { read_byte(file, i8::from_le_bytes) }
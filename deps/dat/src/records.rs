@@ -0,0 +1,72 @@
+use crate::tree::{ FileEntry, FileState };
+use common::{ BinRead, BinWrite };
+
+//attribute value of files stored uncompressed in a DAT1 archive
+const DAT1_UNCOMPRESSED: i32 = 0x20;
+
+/// Start of each directory's file list in a DAT1 archive
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(big)]
+pub struct Dat1Directory {
+    pub file_count: i32,
+    pub unknown1: i32,
+    pub unknown2: i32,
+    pub unknown3: i32,
+}
+
+/// File record in a DAT1 directory
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(big)]
+pub struct Dat1Entry {
+    pub name_size: u8,
+    #[bin(count = name_size)]
+    pub name: String,
+    pub attributes: i32,
+    pub offset: i32,
+    pub size: i32,
+    pub packed_size: i32,
+}
+
+impl From<&Dat1Entry> for FileEntry {
+    fn from(entry: &Dat1Entry) -> Self {
+        let state = if entry.packed_size == 0 || entry.attributes == DAT1_UNCOMPRESSED {
+            FileState::Uncompressed
+        } else {
+            FileState::Compressed { size: entry.packed_size as usize }
+        };
+
+        Self {
+            offset: entry.offset as usize,
+            size: entry.size as usize,
+            state,
+        }
+    }
+}
+
+/// File record in the directory tree at the end of a DAT2 archive
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(little)]
+pub struct Dat2Entry {
+    pub name_size: u32,
+    #[bin(count = name_size)]
+    pub name: String,
+    pub compressed: u8,
+    pub real_size: u32,
+    pub packed_size: u32,
+    pub offset: u32,
+}
+
+impl From<&Dat2Entry> for FileEntry {
+    fn from(entry: &Dat2Entry) -> Self {
+        let state = match entry.compressed {
+            0 => FileState::Uncompressed,
+            _ => FileState::Compressed { size: entry.packed_size as usize }
+        };
+
+        Self {
+            offset: entry.offset as usize,
+            size: entry.real_size as usize,
+            state,
+        }
+    }
+}
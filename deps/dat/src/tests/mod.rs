@@ -1,24 +1,16 @@
 use crate::{
     DatFile,
+    Dat2Writer,
+    Version,
+    error::DatError,
+    records::{ Dat1Directory, Dat1Entry, Dat2Entry },
     tree,
     tree::Node,
     tree::NodeType,
 };
 
 use std::io::Cursor;
-use std::fs::File;
-
-fn load_dat_f1() -> DatFile {
-    let path = "../../../../reference/f1/MASTER.DAT";
-    let f = File::open(path).unwrap();
-    DatFile::open(f).unwrap()
-}
-
-fn load_dat_f2() -> DatFile {
-    let path = "../../../../reference/f2/master.dat";
-    let f = File::open(path).unwrap();
-    DatFile::open(f).unwrap()
-}
+use common::{ BinRead, BinWrite, BinaryWriter, readers::ReadMode };
 
 #[test]
 fn lzss_test() {
@@ -31,6 +23,105 @@ fn lzss_test() {
     assert_eq!(unpacked.as_slice(), UNPACKED_FILE);
 }
 
+#[test]
+fn lzss_truncated_test() {
+    //a literal run of 4 bytes with only 2 of them present
+    let input = Cursor::new(vec![0xFF, 0xFC, b'a', b'b']);
+    let result = DatFile::decompress_lzss_inner(input, 4);
+    assert!(matches!(result, Err(DatError::LZSSError)));
+
+    //complete blocks that unpack to less than the file table says aren't padded
+    let input = Cursor::new(vec![0xFF, 0xFE, b'a', b'b']);
+    assert_eq!(DatFile::decompress_lzss_inner(input.clone(), 2).unwrap(), b"ab");
+    assert!(matches!(DatFile::decompress_lzss_inner(input, 4), Err(DatError::LZSSError)));
+
+    //a dictionary block cut off inside its flags
+    let input = Cursor::new(vec![0x00, 0x04, 0x01]);
+    let result = DatFile::decompress_lzss_inner(input, 4);
+    assert!(matches!(result, Err(DatError::ReadError(_))));
+}
+
+#[test]
+fn dat2_bad_footer_test() {
+    //tree size larger than the data size it sits in
+    let mut data = vec![0u8; 16];
+    data[8..12].copy_from_slice(&0x100u32.to_le_bytes());
+    data[12..16].copy_from_slice(&0x10u32.to_le_bytes());
+
+    let error = DatFile::open(Cursor::new(data)).err().unwrap();
+    assert!(matches!(error.downcast_ref(), Some(DatError::InvalidHeader)));
+
+    //a tree that would run past the end of the file
+    let mut data = vec![0u8; 24];
+    data[16..20].copy_from_slice(&u32::MAX.wrapping_sub(24).to_le_bytes());
+    data[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(DatFile::open(Cursor::new(data)).is_err());
+}
+
+#[test]
+fn dat2_entry_test() {
+    let data = [
+        9, 0, 0, 0,
+        b'c', b'o', b'l', b'o', b'r', b'.', b'p', b'a', b'l',
+        1,
+        0x00, 0x83, 0, 0,
+        0x10, 0, 0, 0,
+        0x20, 0, 0, 0,
+    ];
+
+    let entry = Dat2Entry::bin_read(&mut Cursor::new(&data[..]), ReadMode::BE).unwrap();
+    assert_eq!(entry.name, "color.pal");
+    assert_eq!(entry.real_size, 0x8300);
+    assert_eq!(entry.offset, 0x20);
+
+    let file = tree::FileEntry::from(&entry);
+    assert_eq!(file.state, tree::FileState::Compressed { size: 0x10 });
+
+    let mut out = Cursor::new(Vec::new());
+    entry.bin_write(&mut out, ReadMode::BE).unwrap();
+    assert_eq!(out.into_inner(), data);
+}
+
+#[test]
+fn dat1_open_test() {
+    let contents = b"hello";
+
+    let mut out = Cursor::new(Vec::new());
+    for header in [1, 0x5E, 0, 0] {
+        out.write_i32_be(header).unwrap();
+    }
+    out.write_u8(3).unwrap();
+    out.write_bytes(b"ART").unwrap();
+
+    Dat1Directory { file_count: 1, ..Default::default() }
+        .bin_write(&mut out, ReadMode::BE)
+        .unwrap();
+
+    let mut entry = Dat1Entry {
+        name_size: 8,
+        name: "TEST.TXT".into(),
+        attributes: 0x20,
+        offset: 0,
+        size: contents.len() as i32,
+        packed_size: 0,
+    };
+    //position of the offset field, after the name and attributes
+    let offset = out.position() + 1 + 8 + 4;
+    entry.bin_write(&mut out, ReadMode::BE).unwrap();
+
+    entry.offset = out.position() as i32;
+    out.patch_u32_be(offset, entry.offset as u32).unwrap();
+    out.write_bytes(contents).unwrap();
+
+    let dat = DatFile::open(Cursor::new(out.into_inner())).unwrap();
+    assert_eq!(*dat.get_version(), Version::Dat1);
+
+    let node = dat.registry.get("art\\test.txt").unwrap();
+    let file = node.read().unwrap().get_file_entry().unwrap().clone();
+    assert_eq!(file.offset, entry.offset as usize);
+    assert_eq!(dat.unpack_file(&file).unwrap(), contents);
+}
+
 fn mock_tree() -> tree::FileTree {
     let entry = tree::FileEntry::default();
     let nodes = vec![
@@ -41,7 +132,7 @@ fn mock_tree() -> tree::FileTree {
     let mut t = tree::FileTree::create(nodes).unwrap();
     t.insert("art/file1", entry.clone()).unwrap();
 
-    return t;
+    t
 }
 
 #[test]
@@ -80,3 +171,35 @@ fn tree_test() {
         assert!(n.is_none());
     }
 }
+
+#[test]
+fn dat2_writer_test() {
+    let mut writer = Dat2Writer::new();
+    writer.add("color.pal", b"palette").unwrap()
+        .add("art\\items\\gun.frm", b"").unwrap()
+        .add("ART\\CRITTERS\\HMJMPSAA.FRM", &[1, 2, 3]).unwrap();
+    let data = writer.finish().unwrap();
+
+    //file count, tree size and archive size at the end
+    let footer = |at: usize| u32::from_le_bytes(data[at .. at + 4].try_into().unwrap());
+    assert_eq!(footer(10), 3);
+    assert_eq!(footer(data.len() - 4) as usize, data.len());
+    assert_eq!(footer(data.len() - 8) as usize, data.len() - 14 - 4);
+
+    let dat = DatFile::open(Cursor::new(data)).unwrap();
+    assert_eq!(dat.get_version(), &Version::Dat2);
+
+    let read = |name: &str| {
+        let node = dat.registry.get(name).unwrap();
+        let entry = node.read().unwrap().get_file_entry().unwrap().clone();
+        dat.unpack_file(&entry).unwrap()
+    };
+    assert_eq!(read("color.pal"), b"palette");
+    assert_eq!(read("art\\items\\gun.frm"), b"");
+    assert_eq!(read("art\\critters\\hmjmpsaa.frm"), [1, 2, 3]);
+
+    let dat = Dat2Writer::build(&[("a.txt", b"one".to_vec()), ("b\\c.txt", b"two".to_vec())]).unwrap();
+    let node = dat.registry.get("b\\c.txt").unwrap();
+    let entry = node.read().unwrap().get_file_entry().unwrap().clone();
+    assert_eq!(dat.unpack_file(&entry).unwrap(), b"two");
+}
@@ -1,9 +1,6 @@
 use crate::error::DatError;
 
 use std::collections::VecDeque;
-use std::mem::take;
-use std::ops::Deref;
-use std::rc::Rc;
 use std::sync::{
     Arc,
     RwLock
@@ -70,7 +67,7 @@ impl Node {
 
     pub fn new_dir(name: &str, path: &str, children: Vec<Node>) -> Node {
         let mut children: Vec<_> = children.into_iter()
-            .map(|n| Node::new_ptr(n))
+            .map(Node::new_ptr)
             .collect();
         children.sort_by(|a, b| {
             let a_lock = a.read().unwrap();
@@ -83,20 +80,16 @@ impl Node {
         });
 
         let node_type = NodeType::Directory { children };
-        let node = Node::new(name, path, node_type);
-
-        node
+        Node::new(name, path, node_type)
     }
 
     pub fn new_dir_unsorted(name: &str, path: &str, children: Vec<Node>) -> Node {
         let children: Vec<_> = children.into_iter()
-            .map(|n| Node::new_ptr(n))
+            .map(Node::new_ptr)
             .collect();
 
         let node_type = NodeType::Directory { children };
-        let node = Node::new(name, path, node_type);
-
-        node
+        Node::new(name, path, node_type)
     }
 
     pub fn get_name(&self) -> &str {
@@ -109,19 +102,19 @@ impl Node {
 
     pub fn get_file_entry(&self) -> Option<&FileEntry> {
         match &self.node_type {
-            NodeType::File { entry } => Some(&entry),
+            NodeType::File { entry } => Some(entry),
             _ => None,
         }
     }
 
     pub fn get_dir_children(&self) -> Option<&Vec<NodePtrType>> {
         match &self.node_type {
-            NodeType::Directory { children } => Some(&children),
+            NodeType::Directory { children } => Some(children),
             _ => None,
         }
     }
 
-    fn sort_children(children: &mut Vec<NodePtrType>) {
+    fn sort_children(children: &mut [NodePtrType]) {
         children.sort_by(|a, b| {
             let a_lock = a.read().unwrap();
             let b_lock = b.read().unwrap();
@@ -227,6 +220,12 @@ pub struct FileTree {
     root: NodePtrType
 }
 
+impl Default for FileTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FileTree {
     pub fn new() -> Self {
         let root = Node::new_ptr(
@@ -254,7 +253,7 @@ impl FileTree {
             .collect();
 
 
-        if parts.len() > 0 && parts[0] == "." {
+        if !parts.is_empty() && parts[0] == "." {
             parts.pop_front();
         }
 
@@ -364,20 +363,17 @@ impl FileTree {
 fn sort_nodes(node: NodePtrType) -> Result<(), DatError> {
     let mut lock = node.try_write().map_err(|_| DatError::TreeError)?;
     
-    match &mut lock.node_type {
-        NodeType::Directory { children } => {
-            Node::sort_children(children);
-            for n in children {
-                sort_nodes(n.clone())?;
-            }
-        },
-        _ => {  }
+    if let NodeType::Directory { children } = &mut lock.node_type {
+        Node::sort_children(children);
+        for n in children {
+            sort_nodes(n.clone())?;
+        }
     };
 
     Ok(())
 }
 
-impl<'a> IntoIterator for &'a FileTree {
+impl IntoIterator for &FileTree {
     type Item = Node;
     type IntoIter = FileTreeIterator;
 
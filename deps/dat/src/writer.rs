@@ -0,0 +1,82 @@
+use crate::{
+    DatFile,
+    error::DatError,
+    records::Dat2Entry,
+};
+
+use common::{
+    BinWrite,
+    BinaryWriter,
+    readers::ReadMode,
+};
+
+use std::{
+    error::Error,
+    io::Cursor,
+};
+
+/// Builds a Fallout 2 style archive with every file stored uncompressed
+///
+/// The files are written first, then the directory tree, the file count
+/// before it and the tree and archive sizes at the very end.
+#[derive(Debug, Default)]
+pub struct Dat2Writer {
+    data: Vec<u8>,
+    entries: Vec<Dat2Entry>,
+}
+
+impl Dat2Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, `name` is its full path like `art\items\gun.frm`
+    pub fn add(&mut self, name: &str, contents: &[u8]) -> Result<&mut Self, DatError> {
+        let size = u32::try_from(contents.len()).map_err(|_| DatError::TooLarge)?;
+        let offset = u32::try_from(self.data.len()).map_err(|_| DatError::TooLarge)?;
+
+        self.entries.push(Dat2Entry {
+            name_size: name.len() as u32,
+            name: name.to_string(),
+            compressed: 0,
+            real_size: size,
+            packed_size: size,
+            offset,
+        });
+        self.data.extend_from_slice(contents);
+
+        Ok(self)
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, DatError> {
+        let file_count = self.entries.len() as u32;
+        let mut output = Cursor::new(self.data);
+        output.set_position(output.get_ref().len() as u64);
+
+        output.write_u32_le(file_count)?;
+        let tree_start = output.position();
+        Dat2Entry::bin_write_slice(&self.entries, &mut output, ReadMode::LE)?;
+
+        //the tree size counts the tree size field, the archive size both fields
+        let tree_size = output.position() - tree_start + 4;
+        let data_size = output.position() + 8;
+        output.write_u32_le(u32::try_from(tree_size).map_err(|_| DatError::TooLarge)?)?;
+        output.write_u32_le(u32::try_from(data_size).map_err(|_| DatError::TooLarge)?)?;
+
+        Ok(output.into_inner())
+    }
+
+    /// Opens the finished archive from memory
+    pub fn into_dat(self) -> Result<DatFile, Box<dyn Error>> {
+        DatFile::open(Cursor::new(self.finish()?))
+    }
+
+    /// Builds and opens an archive holding `files`, each a name and its contents
+    pub fn build(files: &[(&str, impl AsRef<[u8]>)]) -> Result<DatFile, Box<dyn Error>> {
+        let mut writer = Self::new();
+        for (name, contents) in files {
+            writer.add(name, contents.as_ref())?;
+        }
+        writer.into_dat()
+    }
+}
@@ -0,0 +1,48 @@
+//! Fallout 2 `.aaf` fonts
+//!
+//! A big endian header of `AAFF`, the tallest glyph's height, letter spacing,
+//! space width and line spacing. Then 256 glyphs of width, height and an
+//! offset into the data after the glyph table, where each pixel is a byte of
+//! intensity from 0 to 9.
+
+use crate::{ Font, FontError };
+use common::{ BinaryReader, BinaryReadError, Stream, Vec2d };
+
+const MAGIC: &[u8; 4] = b"AAFF";
+const GLYPH_COUNT: usize = 256;
+
+impl Font {
+    pub fn open_aaf(file: &mut dyn Stream) -> Result<Self, FontError> {
+        let magic: [u8; 4] = BinaryReader::read_array(file)?;
+        if &magic != MAGIC {
+            return Err(FontError::BadMagic);
+        }
+
+        let height = file.read_u16_be()? as u32;
+        let letter_spacing = file.read_u16_be()? as u32;
+        let space_width = file.read_u16_be()? as u32;
+        let line_spacing = file.read_u16_be()? as u32;
+
+        let mut entries = Vec::with_capacity(GLYPH_COUNT);
+        for _ in 0 .. GLYPH_COUNT {
+            let width = file.read_u16_be()? as usize;
+            let height = file.read_u16_be()? as usize;
+            let offset = file.read_u32_be()? as usize;
+            entries.push((width, height, offset));
+        }
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(BinaryReadError::from)?;
+
+        let glyphs = entries.into_iter()
+            .enumerate()
+            .map(|(index, (width, height, offset))| {
+                data.get(offset .. offset + width * height)
+                    .map(|pixels| Vec2d::from_slice(width, height, pixels))
+                    .ok_or(FontError::GlyphOutOfRange(index))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { height, letter_spacing, line_spacing, space_width, glyphs })
+    }
+}
@@ -0,0 +1,36 @@
+use common::BinaryReadError;
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+};
+
+#[derive(Debug)]
+pub enum FontError {
+    ReadError(BinaryReadError),
+    /// An aaf file that doesn't start with `AAFF`
+    BadMagic,
+    /// A fon file with a negative count, or more glyphs than characters
+    GlyphCount(i32),
+    /// The glyph's pixels run past the end of the file
+    GlyphOutOfRange(usize),
+}
+
+impl Display for FontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use FontError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading font: {}", e),
+            BadMagic => write!(f, "Invalid aaf file signature"),
+            GlyphCount(count) => write!(f, "Invalid glyph count {}", count),
+            GlyphOutOfRange(index) => write!(f, "Glyph {} is outside the font data", index),
+        }
+    }
+}
+
+impl Error for FontError {}
+
+impl From<BinaryReadError> for FontError {
+    fn from(e: BinaryReadError) -> Self {
+        FontError::ReadError(e)
+    }
+}
@@ -0,0 +1,62 @@
+//! Fallout 1 `.fon` fonts
+//!
+//! A little endian header of glyph count, height and letter spacing, then two
+//! pointers only used at runtime. Each glyph has a width and an offset into
+//! the data after the glyph table. Glyph rows are padded to whole bytes, with
+//! the leftmost pixel in the top bit.
+
+use crate::{ Font, FontError, MAX_INTENSITY };
+use common::{ BinaryReader, BinaryReadError, Stream, Vec2d };
+
+const MAX_GLYPHS: i32 = 256;
+
+impl Font {
+    pub fn open_fon(file: &mut dyn Stream) -> Result<Self, FontError> {
+        let count = file.read_i32_le()?;
+        let height = file.read_i32_le()?.max(0) as usize;
+        let letter_spacing = file.read_i32_le()?.max(0) as u32;
+        let _glyph_pointer = file.read_i32_le()?;
+        let _data_pointer = file.read_i32_le()?;
+
+        if !(0 ..= MAX_GLYPHS).contains(&count) {
+            return Err(FontError::GlyphCount(count));
+        }
+
+        let mut entries = Vec::new();
+        for _ in 0 .. count {
+            let width = file.read_i32_le()?.max(0) as usize;
+            let offset = file.read_i32_le()?.max(0) as usize;
+            entries.push((width, offset));
+        }
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(BinaryReadError::from)?;
+
+        let mut glyphs = Vec::new();
+        for (index, (width, offset)) in entries.into_iter().enumerate() {
+            let row_size = width.div_ceil(8);
+            let bits = data.get(offset .. offset + row_size * height)
+                .ok_or(FontError::GlyphOutOfRange(index))?;
+
+            let mut glyph = Vec2d::new(width, height);
+            for (y, row) in bits.chunks_exact(row_size.max(1)).take(height).enumerate() {
+                for x in 0 .. width {
+                    if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                        glyph.insert(x, y, MAX_INTENSITY);
+                    }
+                }
+            }
+            glyphs.push(glyph);
+        }
+
+        let space_width = glyphs.get(b' ' as usize).map_or(0, |g| g.width() as u32);
+
+        Ok(Self {
+            height: height as u32,
+            letter_spacing,
+            line_spacing: 0,
+            space_width,
+            glyphs,
+        })
+    }
+}
@@ -0,0 +1,216 @@
+//! Bitmap fonts for interface text
+//!
+//! Fallout 1 uses `.fon` fonts, one bit per pixel. Fallout 2 adds `.aaf`
+//! fonts, where each pixel is an intensity so edges can be smoothed. Both are
+//! loaded into the same `Font`, with `.fon` pixels fully on or off.
+
+#[cfg(test)]
+mod tests;
+
+pub mod aaf;
+pub mod error;
+pub mod fon;
+
+pub use error::FontError;
+
+use common::Vec2d;
+use frm::Bitmap;
+use pal::Color;
+
+use std::collections::VecDeque;
+
+/// Intensity of a fully drawn pixel, 0 is not drawn at all
+pub const MAX_INTENSITY: u8 = 9;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A line of laid out text
+///
+/// * `x` - offset from the left of the box, for the alignment
+/// * `y` - offset from the top of the box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'t> {
+    pub text: &'t str,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+}
+
+/// * `height` - height of the tallest glyph, shorter glyphs sit on its bottom
+/// * `letter_spacing` - gap after every character
+/// * `line_spacing` - gap between lines
+/// * `space_width` - width of the space character
+/// * `glyphs` - intensities from 0 to `MAX_INTENSITY`, indexed by character code
+///
+/// Characters are looked up by their code point, so text should be decoded
+/// from the game's code page as Latin-1. Characters the font has no glyph
+/// for take up no space.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Font {
+    pub height: u32,
+    pub letter_spacing: u32,
+    pub line_spacing: u32,
+    pub space_width: u32,
+
+    pub glyphs: Vec<Vec2d<u8>>,
+}
+
+impl Font {
+    pub fn glyph(&self, c: char) -> Option<&Vec2d<u8>> {
+        self.glyphs.get(c as usize)
+    }
+
+    /// Width of a character, without the letter spacing
+    pub fn char_width(&self, c: char) -> u32 {
+        match c {
+            ' ' => self.space_width,
+            c => self.glyph(c).map_or(0, |g| g.width() as u32),
+        }
+    }
+
+    /// Width of a single line, including the spacing after the last character like the game does
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars().map(|c| self.char_width(c) + self.letter_spacing).sum()
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.height + self.line_spacing
+    }
+
+    /// Splits `text` into lines no wider than `max_width`
+    ///
+    /// Lines break at spaces and newlines. A word too long for a line by
+    /// itself is broken between characters, every line gets at least one.
+    pub fn wrap<'t>(&self, text: &'t str, max_width: u32) -> Vec<&'t str> {
+        let mut lines = Vec::new();
+
+        for paragraph in text.split('\n') {
+            let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+
+            let mut words = VecDeque::new();
+            let mut offset = 0;
+            for word in paragraph.split(' ') {
+                if !word.is_empty() {
+                    words.push_back((offset, offset + word.len()));
+                }
+                offset += word.len() + 1;
+            }
+
+            //start and end of the words on the current line
+            let mut line: Option<(usize, usize)> = None;
+            while let Some((start, end)) = words.pop_front() {
+                match line {
+                    Some((line_start, _)) if self.text_width(&paragraph[line_start .. end]) <= max_width => {
+                        line = Some((line_start, end));
+                    },
+                    Some((line_start, line_end)) => {
+                        lines.push(&paragraph[line_start .. line_end]);
+                        line = None;
+                        words.push_front((start, end));
+                    },
+                    None => {
+                        let cut = start + self.fit(&paragraph[start .. end], max_width);
+                        if cut < end {
+                            lines.push(&paragraph[start .. cut]);
+                            words.push_front((cut, end));
+                        } else {
+                            line = Some((start, end));
+                        }
+                    },
+                }
+            }
+
+            lines.push(line.map_or("", |(start, end)| &paragraph[start .. end]));
+        }
+
+        lines
+    }
+
+    //bytes of the longest start of `text` that fits, never less than one character
+    fn fit(&self, text: &str, max_width: u32) -> usize {
+        let mut width = 0;
+        for (i, c) in text.char_indices() {
+            width += self.char_width(c) + self.letter_spacing;
+            if width > max_width {
+                return if i == 0 { c.len_utf8() } else { i };
+            }
+        }
+        text.len()
+    }
+
+    /// Wraps `text` to `max_width` and places each line in a box that wide
+    pub fn layout<'t>(&self, text: &'t str, max_width: u32, align: Align) -> Vec<Line<'t>> {
+        self.wrap(text, max_width).into_iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let width = self.text_width(text);
+                let x = match align {
+                    Align::Left => 0,
+                    Align::Center => max_width.saturating_sub(width) / 2,
+                    Align::Right => max_width.saturating_sub(width),
+                };
+                Line { text, x, y: i as u32 * self.line_height(), width }
+            })
+            .collect()
+    }
+
+    /// Calls `plot` with the position and intensity of every drawn pixel of
+    /// a single line, with its top left corner at `x`, `y`
+    pub fn draw_with(&self, text: &str, x: isize, y: isize, mut plot: impl FnMut(isize, isize, u8)) {
+        let mut pen = x;
+
+        for c in text.chars() {
+            if let Some(glyph) = self.glyph(c).filter(|_| c != ' ') {
+                let top = y + self.height.saturating_sub(glyph.height() as u32) as isize;
+
+                for (row, pixels) in glyph.rows().enumerate() {
+                    for (column, &intensity) in pixels.iter().enumerate() {
+                        if intensity > 0 {
+                            plot(pen + column as isize, top + row as isize, intensity.min(MAX_INTENSITY));
+                        }
+                    }
+                }
+            }
+
+            pen += (self.char_width(c) + self.letter_spacing) as isize;
+        }
+    }
+
+    /// Draws a line in palette index `color`, clipped to `target`
+    ///
+    /// Indexed images can't blend, so every pixel with any intensity is drawn.
+    /// Use `draw_with` and the palette's blend tables for smooth `.aaf` text.
+    pub fn draw(&self, target: &mut Vec2d<u8>, text: &str, x: isize, y: isize, color: u8) {
+        self.draw_with(text, x, y, |x, y, _| {
+            if x >= 0 && y >= 0 {
+                target.insert(x as usize, y as usize, color);
+            }
+        });
+    }
+
+    /// Draws a line in `color`, blended over `target` by each pixel's intensity
+    pub fn draw_bitmap(&self, target: &mut Bitmap, text: &str, x: isize, y: isize, color: Color) {
+        let (width, height) = (target.width as isize, target.height as isize);
+
+        self.draw_with(text, x, y, |x, y, intensity| {
+            if x < 0 || y < 0 || x >= width || y >= height {
+                return;
+            }
+
+            let pixel = &mut target.pixels[(y * width + x) as usize];
+            let alpha = intensity as u32 * 255 / MAX_INTENSITY as u32;
+            let blend = |src: u8, dst: u8| ((src as u32 * alpha + dst as u32 * (255 - alpha)) / 255) as u8;
+
+            pixel.red = blend(color.red, pixel.red);
+            pixel.green = blend(color.green, pixel.green);
+            pixel.blue = blend(color.blue, pixel.blue);
+            pixel.alpha = (alpha + pixel.alpha as u32 * (255 - alpha) / 255) as u8;
+        });
+    }
+}
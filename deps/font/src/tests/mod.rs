@@ -0,0 +1,137 @@
+use std::io::Cursor;
+use common::Vec2d;
+use frm::{ Bitmap, Color as Rgba };
+use pal::Color;
+use crate::*;
+
+//two rows high, space is 2 wide, `A` is 3 wide and `B` 9 so its rows take two bytes
+fn make_fon(count: i32) -> Vec<u8> {
+    let mut glyphs = vec![(0i32, 0); count as usize];
+    let mut data = Vec::new();
+
+    glyphs[b' ' as usize] = (2, 0);
+    glyphs[b'A' as usize] = (3, data.len());
+    data.extend_from_slice(&[0b0100_0000, 0b1110_0000]);
+    glyphs[b'B' as usize] = (9, data.len());
+    data.extend_from_slice(&[0xFF, 0x80, 0x80, 0x80]);
+
+    let mut file = Vec::new();
+    for v in [count, 2, 1, 0, 0] {
+        file.extend_from_slice(&v.to_le_bytes());
+    }
+    for (width, offset) in glyphs {
+        file.extend_from_slice(&width.to_le_bytes());
+        file.extend_from_slice(&(offset as i32).to_le_bytes());
+    }
+    file.extend(data);
+    file
+}
+
+//`a` is 2x1 and `b` 1x2, space comes from the header
+fn make_aaf() -> Vec<u8> {
+    let mut file = b"AAFF".to_vec();
+    for v in [2u16, 1, 3, 2] {
+        file.extend_from_slice(&v.to_be_bytes());
+    }
+    for c in 0 .. 256 {
+        let (width, height, offset) = match c as u8 {
+            b'a' => (2u16, 1u16, 0u32),
+            b'b' => (1, 2, 2),
+            _ => (0, 0, 0),
+        };
+        file.extend_from_slice(&width.to_be_bytes());
+        file.extend_from_slice(&height.to_be_bytes());
+        file.extend_from_slice(&offset.to_be_bytes());
+    }
+    file.extend_from_slice(&[9, 4, 5, 9]);
+    file
+}
+
+fn fon() -> Font {
+    Font::open_fon(&mut Cursor::new(make_fon(128))).unwrap()
+}
+
+#[test]
+fn fon_test() {
+    let font = fon();
+    assert_eq!(font.glyphs.len(), 128);
+    assert_eq!((font.height, font.letter_spacing, font.line_spacing, font.space_width), (2, 1, 0, 2));
+    assert_eq!(font.glyph('A'), Some(&Vec2d::from_slice(3, 2, &[0, 9, 0, 9, 9, 9])));
+    assert_eq!(font.glyph('B').unwrap().row(1), Some(&[9, 0, 0, 0, 0, 0, 0, 0, 9][..]));
+
+    assert_eq!(font.char_width('B'), 9);
+    assert_eq!(font.char_width('é'), 0);
+    assert_eq!(font.text_width("AB A"), 21);
+    assert_eq!(font.line_height(), 2);
+
+    let data = make_fon(300);
+    assert!(matches!(Font::open_fon(&mut Cursor::new(data)), Err(FontError::GlyphCount(300))));
+
+    let data = make_fon(128);
+    let result = Font::open_fon(&mut Cursor::new(data[.. data.len() - 1].to_vec()));
+    assert!(matches!(result, Err(FontError::GlyphOutOfRange(66))));
+
+    let result = Font::open_fon(&mut Cursor::new(data[.. 10].to_vec()));
+    assert!(matches!(result, Err(FontError::ReadError(e)) if e.is_eof()));
+}
+
+#[test]
+fn aaf_test() {
+    let font = Font::open_aaf(&mut Cursor::new(make_aaf())).unwrap();
+    assert_eq!((font.height, font.letter_spacing, font.line_spacing, font.space_width), (2, 1, 2, 3));
+    assert_eq!(font.glyph('b'), Some(&Vec2d::from_slice(1, 2, &[5, 9])));
+    assert_eq!(font.text_width("a b"), 3 + 4 + 2);
+    assert_eq!(font.line_height(), 4);
+
+    let mut data = make_aaf();
+    data[3] = b'X';
+    assert!(matches!(Font::open_aaf(&mut Cursor::new(data)), Err(FontError::BadMagic)));
+
+    let data = make_aaf();
+    let result = Font::open_aaf(&mut Cursor::new(data[.. data.len() - 1].to_vec()));
+    assert!(matches!(result, Err(FontError::GlyphOutOfRange(98))));
+}
+
+#[test]
+fn wrap_test() {
+    //`A` takes 4 with its spacing and space 3
+    let font = fon();
+    assert_eq!(font.wrap("AA AA", 19), ["AA AA"]);
+    assert_eq!(font.wrap("AA   AA", 12), ["AA", "AA"]);
+    assert_eq!(font.wrap("AA AAAAAAA\r\n\n A", 12), ["AA", "AAA", "AAA", "A", "", "A"]);
+    assert_eq!(font.wrap("B", 1), ["B"]);
+    assert_eq!(font.wrap("", 10), [""]);
+
+    let lines = font.layout("A AA\nAAA", 20, Align::Center);
+    assert_eq!(lines, [
+        Line { text: "A AA", x: 2, y: 0, width: 15 },
+        Line { text: "AAA", x: 4, y: 2, width: 12 },
+    ]);
+    assert_eq!(font.layout("A", 20, Align::Right)[0].x, 16);
+    assert_eq!(font.layout("AAA", 4, Align::Left).len(), 3);
+}
+
+#[test]
+fn draw_test() {
+    let font = fon();
+    let mut grid = Vec2d::new(8, 3);
+    font.draw(&mut grid, "A A", -1, 1, 7);
+    assert_eq!(grid.row(0), Some(&[0; 8][..]));
+    assert_eq!(grid.row(1), Some(&[7, 0, 0, 0, 0, 0, 0, 7][..]));
+    assert_eq!(grid.row(2), Some(&[7, 7, 0, 0, 0, 0, 7, 7][..]));
+
+    let aaf = Font::open_aaf(&mut Cursor::new(make_aaf())).unwrap();
+    let mut bitmap = Bitmap { width: 5, height: 2, pixels: vec![Rgba::default(); 10] };
+    aaf.draw_bitmap(&mut bitmap, "ab", 0, 0, Color::new(200, 100, 0));
+
+    //`a` is shorter so it sits on the bottom row
+    assert_eq!(bitmap.pixels[0], Rgba::default());
+    assert_eq!(bitmap.pixels[5], Rgba::new(200, 100, 0, 255));
+    assert_eq!(bitmap.pixels[6], Rgba::new(88, 44, 0, 113));
+    assert_eq!(bitmap.pixels[3], Rgba::new(110, 55, 0, 141));
+    assert_eq!(bitmap.pixels[8], Rgba::new(200, 100, 0, 255));
+
+    let mut plotted = Vec::new();
+    aaf.draw_with("b", 10, 20, |x, y, intensity| plotted.push((x, y, intensity)));
+    assert_eq!(plotted, [(10, 20, 5), (10, 21, 9)]);
+}
@@ -1,13 +1,14 @@
+use common::BinaryReadError;
 use std::error::Error;
 use std::fmt;
 use FrmError::*;
 
 pub type Result<T> = std::result::Result<T, FrmError>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum FrmError {
     InvalidSig,
-    ReadError,
+    ReadError(BinaryReadError),
     SizeMismatch
 }
 
@@ -15,10 +16,16 @@ impl fmt::Display for FrmError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             InvalidSig => write!(f, "Invalid Signature"),
-            ReadError => write!(f, "Error reading file"),
+            ReadError(e) => write!(f, "Error reading file: {}", e),
             SizeMismatch => write!(f, "Frame size does not match width x height"),
         }
     }
 }
 
 impl Error for FrmError {}
+
+impl From<BinaryReadError> for FrmError {
+    fn from(e: BinaryReadError) -> Self {
+        ReadError(e)
+    }
+}
@@ -8,17 +8,31 @@ pub mod error;
 use error::FrmError;
 use FrmError::*;
 
-use common::{ Stream, read_num };
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReader,
+    BinaryReadError,
+    BinaryWriter,
+    BinaryWriteError,
+    Stream,
+    readers::ReadMode,
+};
 use pal::PalFile;
 
-use std::rc::Rc;
+use std::io::{ Seek, Write };
 
-#[derive(Debug, Default, Clone, Copy)]
+const VERSION: u32 = 0x04;
+//width, height, size and shift before each frame's pixels
+const FRAME_HEADER_SIZE: u32 = 12;
+
+#[derive(Debug, Default, Clone, Copy, BinRead, BinWrite)]
 pub struct PixelShift {
     pub x: i16,
     pub y: i16,
 }
 
+/// * `size` - pixel count, always `width * height` for frames read from a file
 #[derive(Debug, Default, Clone)]
 pub struct Frame {
     pub width: u16,
@@ -29,6 +43,28 @@ pub struct Frame {
     pub color_index: Vec<u8>,
 }
 
+//comes before each frame's pixels, checked before any of them are read
+#[derive(BinRead, BinWrite)]
+struct FrameHeader {
+    width: u16,
+    height: u16,
+    size: u32,
+    shift: PixelShift,
+}
+
+//follows the version, shifts are stored as all 6 x values, then all 6 y values
+#[derive(BinRead, BinWrite)]
+#[bin(big)]
+struct FrmHeader {
+    fps: u16,
+    action_frame: u16,
+    frames_per_direction: u16,
+    shift_x: [i16; 6],
+    shift_y: [i16; 6],
+    frame_offsets: [u32; 6],
+    data_size: u32,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct FrmFile {
     pub fps: u16,
@@ -40,63 +76,75 @@ pub struct FrmFile {
     pub frames: Vec<Frame>,
 }
 
-macro_rules! read_frm {
-    ($file:expr, $t: ty) => {{
-        read_num!($file, $t, be).ok_or(ReadError)
-    }};
-}
-
 impl FrmFile {
     pub fn open(file: &mut dyn Stream) -> Result<Self, FrmError> {
-        let mut this = Self::default();
-
-        let ver = read_frm!(file, u32)?;
-        if ver != 0x04 {
+        if u32::bin_read(file, ReadMode::BE)? != VERSION {
             return Err(InvalidSig);
         }
-
-        this.fps = read_frm!(file, u16)?;
-        this.action_frame = read_frm!(file, u16)?;
-        this.frames_per_direction = read_frm!(file, u16)?;
-        
-        for i in 0..6 {
-            this.shifts[i].x = read_frm!(file, i16)?;
-        }
-        for i in 0..6 {
-            this.shifts[i].y = read_frm!(file, i16)?;
-        }
-        for i in 0..6 {
-            this.frame_offsets[i] = read_frm!(file, u32)?;
-        }
-
-        let data_size = read_frm!(file, u32)?;
-
-        let frame_start = file.stream_position().map_err(|_| ReadError)?;
-        let frame_end = frame_start + data_size as u64;
-
-        while file.stream_position().map_err(|_| ReadError)? < frame_end {
-            let mut frame = Frame::default();
-
-            frame.width = read_frm!(file, u16)?;
-            frame.height = read_frm!(file, u16)?;
-
-            frame.size = read_frm!(file, u32)?;
-
-            if frame.width as u32 * frame.height as u32 != frame.size {
+        let header = FrmHeader::bin_read(file, ReadMode::BE)?;
+
+        let mut this = Self {
+            fps: header.fps,
+            action_frame: header.action_frame,
+            frames_per_direction: header.frames_per_direction,
+            shifts: std::array::from_fn(|i| PixelShift { x: header.shift_x[i], y: header.shift_y[i] }),
+            frame_offsets: header.frame_offsets,
+            frames: Vec::new(),
+        };
+
+        let frame_start = file.stream_position().map_err(BinaryReadError::from)?;
+        let frame_end = frame_start + header.data_size as u64;
+
+        while file.stream_position().map_err(BinaryReadError::from)? < frame_end {
+            let header = FrameHeader::bin_read(file, ReadMode::BE)?;
+            if header.width as u32 * header.height as u32 != header.size {
                 return Err(SizeMismatch);
             }
 
-            frame.shift.x = read_frm!(file, i16)?;
-            frame.shift.y = read_frm!(file, i16)?;
+            this.frames.push(Frame {
+                width: header.width,
+                height: header.height,
+                size: header.size,
+                shift: header.shift,
+                color_index: file.read_vec(header.size as usize)?,
+            });
+        }
 
-            let mut buf = vec![0u8; frame.size as usize];
-            file.read(&mut buf).map_err(|_| ReadError)?;
-            frame.color_index = buf;
+        Ok(this)
+    }
 
-            this.frames.push(frame);
+    /// Writes the frames back out, each frame's `size` and the frame data size
+    /// are taken from the pixels in `color_index`
+    pub fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let data_size = self.frames.iter()
+            .map(|f| FRAME_HEADER_SIZE + f.color_index.len() as u32)
+            .sum();
+
+        let header = FrmHeader {
+            fps: self.fps,
+            action_frame: self.action_frame,
+            frames_per_direction: self.frames_per_direction,
+            shift_x: self.shifts.map(|s| s.x),
+            shift_y: self.shifts.map(|s| s.y),
+            frame_offsets: self.frame_offsets,
+            data_size,
+        };
+
+        VERSION.bin_write(output, ReadMode::BE)?;
+        header.bin_write(output, ReadMode::BE)?;
+
+        for frame in &self.frames {
+            let header = FrameHeader {
+                width: frame.width,
+                height: frame.height,
+                size: frame.color_index.len() as u32,
+                shift: frame.shift,
+            };
+            header.bin_write(output, ReadMode::BE)?;
+            output.write_bytes(&frame.color_index)?;
         }
 
-        Ok(this)
+        Ok(())
     }
 
     //returns none if out of bounds
@@ -148,7 +196,7 @@ impl FrmFile {
             for i in 0..b.pixels.len() {
                 let palette_index = f.color_index[i];
 
-                if let Some(index) = Self::apply_pixel_shift(i, &f) {
+                if let Some(index) = Self::apply_pixel_shift(i, f) {
                     if palette_index > 0 {
                         let rgb = palette.colors[palette_index as usize];
                         let c = Color::new(rgb.red, rgb.green, rgb.blue, 255);
@@ -1,9 +1,12 @@
 use crate::{ 
     Frame,
     FrmFile,
-    PixelShift 
+    PixelShift,
+    error::FrmError,
 };
 
+use std::io::Cursor;
+
 #[test]
 fn pixel_shift_test() {
     fn shift(index: usize, frame: &Frame) -> Option<usize> {
@@ -28,3 +31,81 @@ fn pixel_shift_test() {
     frame.shift = PixelShift { x: -1000, y: 2 };
     assert_eq!(shift(500, &frame), None);
 }
+
+#[test]
+fn truncated_test() {
+    //version, fps, action frame, frames per direction and one x shift, then
+    //the header stops
+    let data = vec![0, 0, 0, 4, 0, 10, 0, 0, 0, 1, 0, 0];
+
+    let err = FrmFile::open(&mut Cursor::new(data)).unwrap_err();
+    match err {
+        FrmError::ReadError(e) => {
+            assert!(e.is_eof());
+            assert_eq!(e.position, Some(12));
+        },
+        e => panic!("expected a read error, got {:?}", e),
+    }
+
+    let data = vec![0, 0, 0, 3];
+    assert!(matches!(FrmFile::open(&mut Cursor::new(data)), Err(FrmError::InvalidSig)));
+}
+
+#[test]
+fn write_test() {
+    let frm = FrmFile {
+        fps: 10,
+        action_frame: 1,
+        frames_per_direction: 2,
+        shifts: [PixelShift { x: -1, y: 2 }; 6],
+        frame_offsets: [0, 0, 0, 0, 0, 0],
+        frames: vec![
+            Frame { width: 2, height: 1, size: 2, shift: PixelShift { x: 0, y: 0 }, color_index: vec![1, 2] },
+            Frame { width: 1, height: 3, size: 3, shift: PixelShift { x: 1, y: -1 }, color_index: vec![3, 4, 5] },
+        ],
+    };
+
+    let mut data = Cursor::new(Vec::new());
+    frm.write(&mut data).unwrap();
+    assert_eq!(data.get_ref().len(), 62 + 12 * 2 + 5);
+    //x shifts come before y shifts
+    assert_eq!(&data.get_ref()[10 .. 14], [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(&data.get_ref()[22 .. 24], [0, 2]);
+
+    data.set_position(0);
+    let read = FrmFile::open(&mut data).unwrap();
+    assert_eq!(read.fps, 10);
+    assert_eq!(read.frames_per_direction, 2);
+    assert_eq!(read.shifts[5].y, 2);
+    assert_eq!(read.frames.len(), 2);
+    assert_eq!(read.frames[1].color_index, [3, 4, 5]);
+    assert_eq!(read.frames[1].shift.y, -1);
+
+    //a stale size is written as the pixel count
+    let mut stale = frm.clone();
+    stale.frames[0].size = 7;
+    let mut data = Cursor::new(Vec::new());
+    stale.write(&mut data).unwrap();
+    data.set_position(0);
+    assert_eq!(FrmFile::open(&mut data).unwrap().frames[0].size, 2);
+}
+
+#[test]
+fn frame_size_test() {
+    let frm = FrmFile {
+        frames: vec![Frame { width: 2, height: 2, size: 4, color_index: vec![1, 2, 3, 4], ..Default::default() }],
+        ..Default::default()
+    };
+    let mut data = Cursor::new(Vec::new());
+    frm.write(&mut data).unwrap();
+
+    //the size doesn't match width x height, and claims more pixels than the file has
+    let mut data = data.into_inner();
+    data[62 + 4 .. 62 + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(matches!(FrmFile::open(&mut Cursor::new(data.clone())), Err(FrmError::SizeMismatch)));
+
+    //the header is fine but the pixels are cut off
+    data[62 + 4 .. 62 + 8].copy_from_slice(&4u32.to_be_bytes());
+    data.truncate(data.len() - 1);
+    assert!(matches!(FrmFile::open(&mut Cursor::new(data)), Err(FrmError::ReadError(e)) if e.is_eof()));
+}
@@ -0,0 +1,26 @@
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+    io,
+};
+
+#[derive(Debug)]
+pub enum LipError {
+    ReadError(io::Error),
+    UnknownVersion(u32),
+    /// The sound name isn't followed by `VOC`
+    BadMagic,
+}
+
+impl Display for LipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use LipError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading lip file: {}", e),
+            UnknownVersion(v) => write!(f, "Unknown lip file version {}", v),
+            BadMagic => write!(f, "Invalid lip file signature"),
+        }
+    }
+}
+
+impl Error for LipError {}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests;
+
+pub mod error;
+pub use error::LipError;
+
+use acm::AcmDecoder;
+use common::{
+    Stream,
+    readers::read_bytes,
+};
+use std::io::Read;
+
+const LIP_VERSION: u32 = 2;
+
+/// Talking head frame for each phoneme
+const HEAD_PHONEME_LOOKUP: [u8; 42] = [
+    0, 3, 1, 1, 3, 1, 1, 1, 7, 8,
+    7, 3, 1, 8, 1, 7, 7, 6, 6, 2,
+    2, 2, 2, 4, 4, 4, 4, 5, 5, 2,
+    2, 2, 2, 2, 6, 2, 2, 5, 8, 2,
+    2, 2,
+];
+
+/// Point in the speech where the next phoneme starts
+///
+/// * `position` - byte offset into the decoded 16 bit audio
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub kind: u32,
+    pub position: u32,
+}
+
+/// Lip sync data for a line of speech, stored next to its `.acm` in `sound/speech`
+///
+/// * `sound_name` - name of the speech file, without extension
+/// * `sound_size` - length of the speech in bytes of decoded audio
+///
+/// The head starts on the first phoneme, and moves to the next one each
+/// time playback passes a marker.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LipFile {
+    pub sound_name: String,
+    pub sound_size: u32,
+
+    pub phonemes: Vec<u8>,
+    pub markers: Vec<Marker>,
+}
+
+fn read_u32(stream: &mut impl Read) -> Result<u32, LipError> {
+    read_bytes(stream)
+        .map(u32::from_be_bytes)
+        .map_err(LipError::ReadError)
+}
+
+impl LipFile {
+    pub fn open(stream: &mut impl Read) -> Result<Self, LipError> {
+        let version = read_u32(stream)?;
+        if version != LIP_VERSION {
+            return Err(LipError::UnknownVersion(version));
+        }
+
+        let _unknown = read_u32(stream)?;
+        let _flags = read_u32(stream)?;
+        let _unknown = read_u32(stream)?;
+        let sound_size = read_u32(stream)?;
+        let _unknown = read_u32(stream)?;
+        let phoneme_count = read_u32(stream)?;
+        let _unknown = read_u32(stream)?;
+        let marker_count = read_u32(stream)?;
+
+        let name: [u8; 8] = read_bytes(stream).map_err(LipError::ReadError)?;
+        let magic: [u8; 4] = read_bytes(stream).map_err(LipError::ReadError)?;
+        if &magic[..3] != b"VOC" {
+            return Err(LipError::BadMagic);
+        }
+
+        let name_len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        let sound_name = String::from_utf8_lossy(&name[..name_len]).into_owned();
+
+        let mut phonemes = Vec::new();
+        stream.take(phoneme_count as u64)
+            .read_to_end(&mut phonemes)
+            .map_err(LipError::ReadError)?;
+        if phonemes.len() < phoneme_count as usize {
+            return Err(LipError::ReadError(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        let markers = (0 .. marker_count)
+            .map(|_| Ok(Marker {
+                kind: read_u32(stream)?,
+                position: read_u32(stream)?,
+            }))
+            .collect::<Result<_, LipError>>()?;
+
+        Ok(Self { sound_name, sound_size, phonemes, markers })
+    }
+
+    /// Path of the speech that goes with the lip file at `lip_path`
+    ///
+    /// e.g. `sound\speech\myron\myrn001.lip` gives `sound\speech\myron\myrn001.acm`
+    pub fn acm_path(lip_path: &str) -> String {
+        let stem = lip_path
+            .rsplit_once('.')
+            .filter(|(_, ext)| !ext.contains(['/', '\\']))
+            .map_or(lip_path, |(stem, _)| stem);
+
+        format!("{}.acm", stem)
+    }
+
+    /// Phoneme being spoken `byte_position` bytes into the decoded speech
+    pub fn phoneme_at(&self, byte_position: u64) -> Option<u8> {
+        let passed = self.markers.partition_point(|m| m.position as u64 <= byte_position);
+        let last = self.phonemes.len().checked_sub(1)?;
+
+        Some(self.phonemes[passed.min(last)])
+    }
+
+    /// Talking head frame `byte_position` bytes into the decoded speech
+    pub fn mouth_frame(&self, byte_position: u64) -> usize {
+        self.phoneme_at(byte_position)
+            .map_or(0, head_frame)
+    }
+
+    /// Talking head frame for the playback position of `decoder`
+    pub fn mouth_frame_for<S: Stream>(&self, decoder: &AcmDecoder<S>) -> usize {
+        let bytes = decoder.sample_position() * size_of::<i16>() as u64;
+        self.mouth_frame(bytes)
+    }
+}
+
+/// Talking head frame that shows `phoneme`, unknown phonemes close the mouth
+pub fn head_frame(phoneme: u8) -> usize {
+    HEAD_PHONEME_LOOKUP.get(phoneme as usize)
+        .map_or(0, |f| *f as usize)
+}
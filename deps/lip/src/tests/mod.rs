@@ -0,0 +1,88 @@
+use std::io::Cursor;
+use acm::{ Acm, AcmDecoder };
+use crate::*;
+
+fn make_lip(version: u32, phonemes: &[u8], markers: &[(u32, u32)]) -> Vec<u8> {
+    let header = [
+        version, 0x5800, 0, 0, 8000, 0,
+        phonemes.len() as u32, 0, markers.len() as u32,
+    ];
+
+    let mut data = Vec::new();
+    for v in header {
+        data.extend_from_slice(&v.to_be_bytes());
+    }
+    data.extend_from_slice(b"MYRN001\0");
+    data.extend_from_slice(b"VOC\0");
+    data.extend_from_slice(phonemes);
+    for (kind, position) in markers {
+        data.extend_from_slice(&kind.to_be_bytes());
+        data.extend_from_slice(&position.to_be_bytes());
+    }
+
+    data
+}
+
+fn lip() -> LipFile {
+    let data = make_lip(2, &[0, 8, 13, 23], &[(1, 100), (0, 400), (1, 1000)]);
+    LipFile::open(&mut Cursor::new(data)).unwrap()
+}
+
+#[test]
+fn read_test() {
+    let lip = lip();
+    assert_eq!(lip.sound_name, "MYRN001");
+    assert_eq!(lip.sound_size, 8000);
+    assert_eq!(lip.phonemes, [0, 8, 13, 23]);
+    assert_eq!(lip.markers[1], Marker { kind: 0, position: 400 });
+
+    let data = make_lip(1, &[], &[]);
+    assert!(matches!(LipFile::open(&mut Cursor::new(data)), Err(LipError::UnknownVersion(1))));
+
+    let mut data = make_lip(2, &[], &[]);
+    data[44] = b'X';
+    assert!(matches!(LipFile::open(&mut Cursor::new(data)), Err(LipError::BadMagic)));
+
+    let data = make_lip(2, &[1, 2], &[(0, 10)]);
+    assert!(matches!(LipFile::open(&mut Cursor::new(&data[.. data.len() - 1])), Err(LipError::ReadError(_))));
+}
+
+#[test]
+fn phoneme_test() {
+    let lip = lip();
+    assert_eq!(lip.phoneme_at(0), Some(0));
+    assert_eq!(lip.phoneme_at(99), Some(0));
+    assert_eq!(lip.phoneme_at(100), Some(8));
+    assert_eq!(lip.phoneme_at(999), Some(13));
+    assert_eq!(lip.phoneme_at(5000), Some(23));
+
+    assert_eq!(lip.mouth_frame(0), 0);
+    assert_eq!(lip.mouth_frame(100), 7);
+    assert_eq!(lip.mouth_frame(400), 8);
+    assert_eq!(lip.mouth_frame(1000), 4);
+
+    assert_eq!(LipFile::default().mouth_frame(100), 0);
+    assert_eq!(head_frame(200), 0);
+}
+
+#[test]
+fn decoder_position_test() {
+    let acm = Acm { channels: 1, sample_rate: 22050, samples: vec![0; 1000] };
+    let data = acm.write_to_acm().unwrap();
+    let mut decoder = AcmDecoder::new(Cursor::new(data), None).unwrap();
+
+    let lip = lip();
+    assert_eq!(lip.mouth_frame_for(&decoder), 0);
+
+    //markers count bytes, two per sample
+    decoder.seek_to_sample(50).unwrap();
+    assert_eq!(lip.mouth_frame_for(&decoder), 7);
+    decoder.seek_to_sample(499).unwrap();
+    assert_eq!(lip.mouth_frame_for(&decoder), 8);
+}
+
+#[test]
+fn path_test() {
+    assert_eq!(LipFile::acm_path("sound\\speech\\myron\\myrn001.lip"), "sound\\speech\\myron\\myrn001.acm");
+    assert_eq!(LipFile::acm_path("sound/speech.d/myrn001"), "sound/speech.d/myrn001.acm");
+}
@@ -0,0 +1,43 @@
+use common::BinaryReadError;
+use pro::{ Pid, ProError };
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+};
+
+#[derive(Debug)]
+pub enum MapError {
+    ReadError(BinaryReadError),
+    /// The type byte of an object's PID isn't one of the six prototype types
+    UnknownType(Pid),
+    /// The proto of an item or scenery object couldn't be loaded
+    ProError(ProError),
+    /// Inventories nested deeper than any real map has them
+    InventoryTooDeep,
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use MapError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading map: {}", e),
+            UnknownType(pid) => write!(f, "Object has unknown type, pid {:#010x}", pid.0),
+            ProError(e) => write!(f, "Error reading object proto: {}", e),
+            InventoryTooDeep => write!(f, "Inventories are nested too deep"),
+        }
+    }
+}
+
+impl Error for MapError {}
+
+impl From<BinaryReadError> for MapError {
+    fn from(e: BinaryReadError) -> Self {
+        MapError::ReadError(e)
+    }
+}
+
+impl From<ProError> for MapError {
+    fn from(e: ProError) -> Self {
+        MapError::ProError(e)
+    }
+}
@@ -0,0 +1,239 @@
+#[cfg(test)]
+mod tests;
+
+pub mod error;
+pub mod object;
+pub mod script;
+
+pub use error::MapError;
+pub use object::{ MapObject, ObjectData, ObjectKind };
+pub use script::{ MapScript, ScriptList, ScriptType };
+
+use object::ObjectReader;
+
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReadError,
+    BinaryReader,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+    vec_2d::Vec2d,
+};
+use pro::{ Pid, ProError, ProtoData, ProtoLibrary };
+
+use std::{
+    collections::HashMap,
+    io::{ Seek, Write },
+};
+
+pub const VERSION_FALLOUT_1: u32 = 19;
+pub const VERSION_FALLOUT_2: u32 = 20;
+
+/// Maps are 100 by 100 tiles on each of the 3 elevations
+pub const ELEVATIONS: usize = 3;
+pub const TILES_WIDTH: usize = 100;
+pub const TILES_HEIGHT: usize = 100;
+
+/// * `name` - file name the map was saved as, null padded
+/// * `entrance_*` - where the player starts when entering without a destination
+/// * `script_id` - line of `scripts.lst` with the map script, -1 for none
+/// * `flags` - bit 0 for saved games, bits 1 to 3 for elevations the map doesn't have
+/// * `map_id` - index in `maps.txt`
+/// * `last_visit` - game time the map was left at
+/// * `unused` - always 0 in the original maps
+#[derive(Debug, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(big)]
+pub struct MapHeader {
+    pub version: u32,
+    pub name: [u8; 16],
+    pub entrance_tile: i32,
+    pub entrance_elevation: i32,
+    pub entrance_rotation: i32,
+    pub local_var_count: i32,
+    pub script_id: i32,
+    pub flags: u32,
+    pub darkness: i32,
+    pub global_var_count: i32,
+    pub map_id: i32,
+    pub last_visit: u32,
+    pub unused: [i32; 44],
+}
+
+impl MapHeader {
+    pub fn name(&self) -> String {
+        let name = self.name.split(|b| *b == 0).next().unwrap_or_default();
+        String::from_utf8_lossy(name).into_owned()
+    }
+
+    pub fn has_elevation(&self, elevation: usize) -> bool {
+        elevation < ELEVATIONS && self.flags & (2 << elevation) == 0
+    }
+}
+
+/// A floor and roof tile, the line of `art\tiles\tiles.lst` in the low 12 bits
+/// and flags in the high 4
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub floor: u16,
+    pub roof: u16,
+}
+
+/// A `.map` file, as stored in `maps/<name>.map`
+///
+/// Every value is big endian. After the header come the map's global and
+/// local script variables, the tiles of each elevation the map has, the
+/// scripts by type, then the objects of each elevation.
+///
+/// * `tiles` - indexed by elevation, none for elevations the map doesn't have
+/// * `scripts` - indexed by `ScriptType`
+/// * `objects` - indexed by elevation, each object followed by its inventory
+/// * `object_count` - total the map stores before the objects. The engine
+///   doesn't trust it, and saved maps don't always agree with the lists
+/// * `extra` - bytes after the objects, kept so nothing is lost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapFile {
+    pub header: MapHeader,
+    pub global_vars: Vec<i32>,
+    pub local_vars: Vec<i32>,
+    pub tiles: [Option<Vec2d<Tile>>; ELEVATIONS],
+    pub scripts: [ScriptList; 5],
+    pub objects: [Vec<MapObject>; ELEVATIONS],
+    pub object_count: i32,
+
+    pub extra: Vec<u8>,
+}
+
+impl MapFile {
+    /// Reads a map, taking the subtypes of item and scenery objects from `protos`
+    pub fn open(stream: &mut dyn Stream, protos: &ProtoLibrary) -> Result<Self, MapError> {
+        let mut subtypes = HashMap::new();
+        Self::open_with(stream, &mut |pid| {
+            if let Some(subtype) = subtypes.get(&pid) {
+                return Ok(*subtype);
+            }
+
+            let subtype = match protos.get(pid)?.data {
+                ProtoData::Item(item) => item.kind.subtype(),
+                ProtoData::Scenery(scenery) => scenery.kind.subtype(),
+                _ => return Err(ProError::UnknownType(pid.0 >> 24).into()),
+            };
+            subtypes.insert(pid, subtype);
+            Ok(subtype)
+        })
+    }
+
+    /// Reads a map, `subtype` gives the item or scenery subtype of a PID
+    pub fn open_with(stream: &mut dyn Stream, subtype: &mut dyn FnMut(Pid) -> Result<u32, MapError>) -> Result<Self, MapError> {
+        let header = MapHeader::bin_read(stream, ReadMode::BE)?;
+        let global_vars = read_vars(stream, header.global_var_count)?;
+        let local_vars = read_vars(stream, header.local_var_count)?;
+
+        let mut tiles: [Option<Vec2d<Tile>>; ELEVATIONS] = Default::default();
+        for (elevation, grid) in tiles.iter_mut().enumerate() {
+            if header.has_elevation(elevation) {
+                *grid = Some(read_tiles(stream)?);
+            }
+        }
+
+        let mut scripts: [ScriptList; 5] = Default::default();
+        for list in scripts.iter_mut() {
+            *list = ScriptList::read(stream)?;
+        }
+
+        let mut reader = ObjectReader { version: header.version, subtype };
+        let object_count = stream.read_i32_be()?;
+        let mut objects: [Vec<MapObject>; ELEVATIONS] = Default::default();
+        for list in objects.iter_mut() {
+            let count = stream.read_i32_be()?;
+            for _ in 0 .. count.max(0) {
+                list.push(reader.read(stream, 0)?);
+            }
+        }
+
+        let mut extra = Vec::new();
+        stream.read_to_end(&mut extra)
+            .map_err(BinaryReadError::from)?;
+
+        Ok(Self { header, global_vars, local_vars, tiles, scripts, objects, object_count, extra })
+    }
+
+    /// Writes every section back out
+    ///
+    /// Variable counts, elevations and the object count of each elevation are
+    /// taken from the data, so they can be added or removed. The total
+    /// `object_count` and the script counts are written as stored, keep them
+    /// in step by hand when editing. Script lists whose count doesn't match
+    /// their blocks, or scripts whose data doesn't match their type, are an
+    /// error rather than a map that can't be read back. A map that was read
+    /// and not changed is written back byte for byte.
+    pub fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let mut header = self.header.clone();
+        header.global_var_count = self.global_vars.len() as i32;
+        header.local_var_count = self.local_vars.len() as i32;
+        for (elevation, grid) in self.tiles.iter().enumerate() {
+            match grid {
+                Some(_) => header.flags &= !(2 << elevation),
+                None => header.flags |= 2 << elevation,
+            }
+        }
+        header.bin_write(output, ReadMode::BE)?;
+
+        self.global_vars.bin_write(output, ReadMode::BE)?;
+        self.local_vars.bin_write(output, ReadMode::BE)?;
+
+        for grid in self.tiles.iter().flatten() {
+            for tile in grid.iter() {
+                output.write_u32_be(tile.floor as u32 | (tile.roof as u32) << 16)?;
+            }
+        }
+
+        for list in &self.scripts {
+            list.write(output)?;
+        }
+
+        output.write_i32_be(self.object_count)?;
+        for list in &self.objects {
+            output.write_i32_be(list.len() as i32)?;
+            for object in list {
+                object.write(output)?;
+            }
+        }
+
+        output.write_bytes(&self.extra)
+    }
+
+    pub fn scripts(&self, script_type: ScriptType) -> &ScriptList {
+        &self.scripts[script_type as usize]
+    }
+
+    /// Objects on every elevation, not counting inventories
+    pub fn all_objects(&self) -> impl Iterator<Item = &MapObject> {
+        self.objects.iter().flatten()
+    }
+
+    pub fn all_objects_mut(&mut self) -> impl Iterator<Item = &mut MapObject> {
+        self.objects.iter_mut().flatten()
+    }
+}
+
+fn read_vars(stream: &mut dyn Stream, count: i32) -> Result<Vec<i32>, BinaryReadError> {
+    (0 .. count.max(0))
+        .map(|_| stream.read_i32_be())
+        .collect()
+}
+
+fn read_tiles(stream: &mut dyn Stream) -> Result<Vec2d<Tile>, BinaryReadError> {
+    let mut grid = Vec2d::new(TILES_WIDTH, TILES_HEIGHT);
+    for tile in grid.iter_mut() {
+        let value = stream.read_u32_be()?;
+        *tile = Tile {
+            floor: value as u16,
+            roof: (value >> 16) as u16,
+        };
+    }
+
+    Ok(grid)
+}
@@ -0,0 +1,245 @@
+use crate::error::MapError;
+
+use art::Fid;
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReader,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+};
+use pro::{ Pid, ProtoType };
+use std::io::{ Seek, Write };
+
+//the engine gives up on anything deeper, real maps only nest bags once or twice
+const MAX_INVENTORY_DEPTH: usize = 16;
+
+//exit grids are the misc protos with these ids
+const EXIT_GRIDS: std::ops::RangeInclusive<u32> = 0x0500_0010 ..= 0x0500_0017;
+
+/// Fields every map object starts with
+///
+/// * `tile` - hex the object stands on, -1 for objects in an inventory
+/// * `x` / `y` - pixel offset from the hex
+/// * `sx` / `sy` - screen position, recalculated by the engine
+/// * `rotation` - 0 to 5, clockwise from north east
+/// * `flags` - object flags, see the header of the proto
+/// * `cid` - combat id
+/// * `outline` - outline colour and flags
+/// * `script_id` - -1 for objects without a script
+/// * `script_index` - line of `scripts.lst`, -1 to use the proto's script
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(big)]
+pub struct ObjectHeader {
+    pub id: i32,
+    pub tile: i32,
+    pub x: i32,
+    pub y: i32,
+    pub sx: i32,
+    pub sy: i32,
+    pub frame: i32,
+    pub rotation: i32,
+    pub fid: Fid,
+    pub flags: u32,
+    pub elevation: i32,
+    pub pid: Pid,
+    pub cid: i32,
+    pub light_radius: i32,
+    pub light_intensity: i32,
+    pub outline: u32,
+    pub script_id: i32,
+    pub script_index: i32,
+}
+
+/// State of a critter on the map
+///
+/// * `reaction` - reaction to the player
+/// * `results` - combat results such as knocked out or crippled limbs
+/// * `who_hit_me` - combat id of the last attacker
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(big)]
+pub struct CritterData {
+    pub reaction: i32,
+    pub damage_last_turn: i32,
+    pub maneuver: i32,
+    pub ap: i32,
+    pub results: u32,
+    pub ai_packet: i32,
+    pub team: i32,
+    pub who_hit_me: i32,
+    pub hp: i32,
+    pub radiation: i32,
+    pub poison: i32,
+}
+
+/// Fields that depend on the proto, for objects other than critters
+///
+/// * `destination` - tile and elevation packed together, `tile | elevation << 29`
+/// * `destination_map` - not stored for Fallout 1 ladders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectKind {
+    Weapon { ammo_quantity: i32, ammo_pid: i32 },
+    Ammo { quantity: i32 },
+    Misc { charges: i32 },
+    Key { key_code: i32 },
+    Door { open_flags: u32 },
+    Stairs { destination: u32, destination_map: i32 },
+    Elevator { elevator_type: i32, level: i32 },
+    Ladder { destination_map: Option<i32>, destination: u32 },
+    ExitGrid { map: i32, tile: i32, elevation: i32, rotation: i32 },
+    /// Nothing past the flags
+    Plain,
+}
+
+impl ObjectKind {
+    fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let values = match *self {
+            Self::Weapon { ammo_quantity, ammo_pid } => vec![ammo_quantity, ammo_pid],
+            Self::Ammo { quantity } => vec![quantity],
+            Self::Misc { charges } => vec![charges],
+            Self::Key { key_code } => vec![key_code],
+            Self::Door { open_flags } => vec![open_flags as i32],
+            Self::Stairs { destination, destination_map } => vec![destination as i32, destination_map],
+            Self::Elevator { elevator_type, level } => vec![elevator_type, level],
+            Self::Ladder { destination_map: Some(map), destination } => vec![map, destination as i32],
+            Self::Ladder { destination_map: None, destination } => vec![destination as i32],
+            Self::ExitGrid { map, tile, elevation, rotation } => vec![map, tile, elevation, rotation],
+            Self::Plain => vec![],
+        };
+
+        values.iter().try_for_each(|v| output.write_i32_be(*v))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectData {
+    Critter(Box<CritterData>),
+    /// Everything else stores flags, 0xCCCCCCCC in some maps, then the fields of its kind
+    Other { flags: u32, kind: ObjectKind },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryItem {
+    pub quantity: i32,
+    pub object: MapObject,
+}
+
+/// An object placed on the map, or held in another object's inventory
+///
+/// * `inventory_capacity` / `inventory_ptr` - engine leftovers, kept so the
+///   map is written back as it was
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapObject {
+    pub header: ObjectHeader,
+    pub inventory_capacity: i32,
+    pub inventory_ptr: u32,
+    pub data: ObjectData,
+    pub inventory: Vec<InventoryItem>,
+}
+
+impl MapObject {
+    /// Writes the object followed by its inventory, the inventory length is
+    /// taken from `inventory`
+    pub fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        self.header.bin_write(output, ReadMode::BE)?;
+        output.write_i32_be(self.inventory.len() as i32)?;
+        output.write_i32_be(self.inventory_capacity)?;
+        output.write_u32_be(self.inventory_ptr)?;
+
+        match &self.data {
+            ObjectData::Critter(critter) => critter.bin_write(output, ReadMode::BE)?,
+            ObjectData::Other { flags, kind } => {
+                output.write_u32_be(*flags)?;
+                kind.write(output)?;
+            },
+        }
+
+        for item in &self.inventory {
+            output.write_i32_be(item.quantity)?;
+            item.object.write(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads objects, looking up the subtype of item and scenery protos
+pub(crate) struct ObjectReader<'a> {
+    pub version: u32,
+    pub subtype: &'a mut dyn FnMut(Pid) -> Result<u32, MapError>,
+}
+
+impl ObjectReader<'_> {
+    pub fn read(&mut self, stream: &mut dyn Stream, depth: usize) -> Result<MapObject, MapError> {
+        if depth > MAX_INVENTORY_DEPTH {
+            return Err(MapError::InventoryTooDeep);
+        }
+
+        let header = ObjectHeader::bin_read(stream, ReadMode::BE)?;
+        let inventory_length = stream.read_i32_be()?;
+        let inventory_capacity = stream.read_i32_be()?;
+        let inventory_ptr = stream.read_u32_be()?;
+        let data = self.read_data(stream, header.pid)?;
+
+        let mut inventory = Vec::new();
+        for _ in 0 .. inventory_length.max(0) {
+            let quantity = stream.read_i32_be()?;
+            let object = self.read(stream, depth + 1)?;
+            inventory.push(InventoryItem { quantity, object });
+        }
+
+        Ok(MapObject { header, inventory_capacity, inventory_ptr, data, inventory })
+    }
+
+    fn read_data(&mut self, stream: &mut dyn Stream, pid: Pid) -> Result<ObjectData, MapError> {
+        let proto_type = pid.proto_type().ok_or(MapError::UnknownType(pid))?;
+        if proto_type == ProtoType::Critter {
+            let critter = CritterData::bin_read(stream, ReadMode::BE)?;
+            return Ok(ObjectData::Critter(Box::new(critter)));
+        }
+
+        let flags = stream.read_u32_be()?;
+        let kind = match proto_type {
+            ProtoType::Item => match (self.subtype)(pid)? {
+                3 => ObjectKind::Weapon {
+                    ammo_quantity: stream.read_i32_be()?,
+                    ammo_pid: stream.read_i32_be()?,
+                },
+                4 => ObjectKind::Ammo { quantity: stream.read_i32_be()? },
+                5 => ObjectKind::Misc { charges: stream.read_i32_be()? },
+                6 => ObjectKind::Key { key_code: stream.read_i32_be()? },
+                _ => ObjectKind::Plain,
+            },
+            ProtoType::Scenery => match (self.subtype)(pid)? {
+                0 => ObjectKind::Door { open_flags: stream.read_u32_be()? },
+                1 => ObjectKind::Stairs {
+                    destination: stream.read_u32_be()?,
+                    destination_map: stream.read_i32_be()?,
+                },
+                2 => ObjectKind::Elevator {
+                    elevator_type: stream.read_i32_be()?,
+                    level: stream.read_i32_be()?,
+                },
+                3 | 4 => ObjectKind::Ladder {
+                    destination_map: match self.version {
+                        crate::VERSION_FALLOUT_1 => None,
+                        _ => Some(stream.read_i32_be()?),
+                    },
+                    destination: stream.read_u32_be()?,
+                },
+                _ => ObjectKind::Plain,
+            },
+            ProtoType::Misc if EXIT_GRIDS.contains(&pid.0) => ObjectKind::ExitGrid {
+                map: stream.read_i32_be()?,
+                tile: stream.read_i32_be()?,
+                elevation: stream.read_i32_be()?,
+                rotation: stream.read_i32_be()?,
+            },
+            _ => ObjectKind::Plain,
+        };
+
+        Ok(ObjectData::Other { flags, kind })
+    }
+}
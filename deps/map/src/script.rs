@@ -0,0 +1,244 @@
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReadError,
+    BinaryReader,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+};
+use std::io::{ self, Seek, Write };
+
+/// Scripts are stored in blocks of this many, unused slots included
+pub const EXTENT_SIZE: usize = 16;
+
+/// Script types, numbered like the type byte of a script id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ScriptType {
+    System = 0,
+    Spatial = 1,
+    Timed = 2,
+    Item = 3,
+    Critter = 4,
+}
+
+impl ScriptType {
+    pub const ALL: [ScriptType; 5] = [
+        Self::System,
+        Self::Spatial,
+        Self::Timed,
+        Self::Item,
+        Self::Critter,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+}
+
+/// Fields only some script types have
+///
+/// * `tile` - tile and elevation packed together, `tile | elevation << 29`
+/// * `time` - game time the script runs at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptData {
+    Spatial { tile: u32, radius: i32 },
+    Timed { time: i32 },
+    None,
+}
+
+/// What the engine saves of a running script
+///
+/// * `index` - line of `scripts.lst` holding the program
+/// * `program` - leftover pointer, kept so the map is written back as it was
+/// * `owner_id` - id of the object the script is attached to
+/// * `local_vars_offset` - first of the script's variables in the map's local variables
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite)]
+#[bin(big)]
+pub struct ScriptState {
+    pub flags: u32,
+    pub index: i32,
+    pub program: u32,
+    pub owner_id: i32,
+    pub local_vars_offset: i32,
+    pub local_var_count: i32,
+    pub return_value: i32,
+    pub action: i32,
+    pub fixed_param: i32,
+    pub action_being_used: i32,
+    pub script_overrides: i32,
+    pub unknown: i32,
+    pub how_much: i32,
+    pub run_flags: u32,
+}
+
+/// * `sid` - script id, the `ScriptType` in the high byte
+/// * `next` - leftover link to the next script
+/// * `data` - written as is, so it has to match the type in `sid`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapScript {
+    pub sid: i32,
+    pub next: i32,
+    pub data: ScriptData,
+    pub state: ScriptState,
+}
+
+impl MapScript {
+    pub fn script_type(&self) -> Option<ScriptType> {
+        ScriptType::from_u8((self.sid >> 24) as u8)
+    }
+
+    fn read(stream: &mut dyn Stream) -> Result<Self, BinaryReadError> {
+        let sid = stream.read_i32_be()?;
+        let next = stream.read_i32_be()?;
+
+        let data = match ScriptType::from_u8((sid >> 24) as u8) {
+            Some(ScriptType::Spatial) => ScriptData::Spatial {
+                tile: stream.read_u32_be()?,
+                radius: stream.read_i32_be()?,
+            },
+            Some(ScriptType::Timed) => ScriptData::Timed { time: stream.read_i32_be()? },
+            _ => ScriptData::None,
+        };
+
+        Ok(Self {
+            sid,
+            next,
+            data,
+            state: ScriptState::bin_read(stream, ReadMode::BE)?,
+        })
+    }
+
+    fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let matches = match self.script_type() {
+            Some(ScriptType::Spatial) => matches!(self.data, ScriptData::Spatial { .. }),
+            Some(ScriptType::Timed) => matches!(self.data, ScriptData::Timed { .. }),
+            _ => self.data == ScriptData::None,
+        };
+        if !matches {
+            return Err(invalid_input(output, format!("data of script {:#x} doesn't match its type", self.sid)));
+        }
+
+        output.write_i32_be(self.sid)?;
+        output.write_i32_be(self.next)?;
+
+        match self.data {
+            ScriptData::Spatial { tile, radius } => {
+                output.write_u32_be(tile)?;
+                output.write_i32_be(radius)?;
+            },
+            ScriptData::Timed { time } => output.write_i32_be(time)?,
+            ScriptData::None => { },
+        }
+
+        self.state.bin_write(output, ReadMode::BE)
+    }
+}
+
+impl Default for MapScript {
+    /// An empty slot, as the engine leaves them
+    fn default() -> Self {
+        Self {
+            sid: 0,
+            next: 0,
+            data: ScriptData::None,
+            state: ScriptState::default(),
+        }
+    }
+}
+
+/// A block of `EXTENT_SIZE` scripts, the first `length` of which are used
+///
+/// Blocks with fewer scripts are written padded with empty slots.
+///
+/// * `next` - leftover link to the next block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptExtent {
+    pub scripts: Vec<MapScript>,
+    pub length: i32,
+    pub next: i32,
+}
+
+impl ScriptExtent {
+    /// The scripts in use
+    pub fn used(&self) -> &[MapScript] {
+        let length = (self.length.max(0) as usize).min(self.scripts.len());
+        &self.scripts[.. length.min(EXTENT_SIZE)]
+    }
+
+    fn read(stream: &mut dyn Stream) -> Result<Self, BinaryReadError> {
+        let scripts = (0 .. EXTENT_SIZE)
+            .map(|_| MapScript::read(stream))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            scripts,
+            length: stream.read_i32_be()?,
+            next: stream.read_i32_be()?,
+        })
+    }
+
+    fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let empty = MapScript::default();
+        let padding = EXTENT_SIZE.saturating_sub(self.scripts.len());
+        for script in self.scripts.iter().take(EXTENT_SIZE).chain(std::iter::repeat_n(&empty, padding)) {
+            script.write(output)?;
+        }
+
+        output.write_i32_be(self.length)?;
+        output.write_i32_be(self.next)
+    }
+}
+
+/// Scripts of one type, the map stores the count then enough blocks to hold them
+///
+/// * `count` - the stored count, it decides how many blocks are read. The
+///   engine counts the used slots again when loading, so it's written as it
+///   was rather than recounted
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScriptList {
+    pub extents: Vec<ScriptExtent>,
+    pub count: i32,
+}
+
+impl ScriptList {
+    pub fn iter(&self) -> impl Iterator<Item = &MapScript> {
+        self.extents.iter().flat_map(ScriptExtent::used)
+    }
+
+    pub fn len(&self) -> usize {
+        self.extents.iter().map(|e| e.used().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn read(stream: &mut dyn Stream) -> Result<Self, BinaryReadError> {
+        let count = stream.read_i32_be()?;
+        let extents = (0 .. (count.max(0) as usize).div_ceil(EXTENT_SIZE))
+            .map(|_| ScriptExtent::read(stream))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { extents, count })
+    }
+
+    //fails if `count` doesn't need exactly the blocks there are, the map couldn't be read back
+    pub(crate) fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        if (self.count.max(0) as usize).div_ceil(EXTENT_SIZE) != self.extents.len() {
+            return Err(invalid_input(output, format!("{} script blocks don't hold a count of {}", self.extents.len(), self.count)));
+        }
+
+        output.write_i32_be(self.count)?;
+        self.extents.iter().try_for_each(|extent| extent.write(output))
+    }
+}
+
+fn invalid_input(output: &mut impl Seek, message: String) -> BinaryWriteError {
+    BinaryWriteError {
+        position: output.stream_position().ok(),
+        error: io::Error::new(io::ErrorKind::InvalidInput, message),
+    }
+}
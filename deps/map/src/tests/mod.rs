@@ -0,0 +1,403 @@
+use std::io::Cursor;
+use common::BinaryWriter;
+use dat::Dat2Writer;
+use pro::ProtoType;
+use crate::{
+    *,
+    object::*,
+    script::*,
+};
+
+fn write_ints(out: &mut Cursor<Vec<u8>>, values: &[i32]) {
+    for v in values {
+        out.write_i32_be(*v).unwrap();
+    }
+}
+
+//header and variables, elevations 0 and 2, no scripts or objects yet
+fn make_map(version: u32) -> Cursor<Vec<u8>> {
+    let mut out = Cursor::new(Vec::new());
+    out.write_u32_be(version).unwrap();
+    out.write_fixed_string("ARTEMPLE.MAP", 16).unwrap();
+    //entrance tile, elevation, rotation, local vars, script, flags, darkness, global vars, id, time
+    write_ints(&mut out, &[20100, 0, 2, 3, 5, 0x4, 1, 2, 7, 1000]);
+    write_ints(&mut out, &[0; 44]);
+
+    write_ints(&mut out, &[10, 11]);
+    write_ints(&mut out, &[20, 21, 22]);
+
+    for elevation in [0, 2] {
+        for i in 0 .. TILES_WIDTH * TILES_HEIGHT {
+            let floor = (i % 0x1000) as u32;
+            out.write_u32_be(floor | ((elevation + 1) << 16)).unwrap();
+        }
+    }
+
+    out
+}
+
+fn write_script(out: &mut Cursor<Vec<u8>>, sid: i32) {
+    write_ints(out, &[sid, -1]);
+    match sid >> 24 {
+        1 => write_ints(out, &[0x2000_0064, 3]),
+        2 => write_ints(out, &[600]),
+        _ => (),
+    }
+    write_ints(out, &[0, sid & 0xFF, 0, 42, 0, 1, 0, 0, 0, -1, 0, 0, 0, 0]);
+}
+
+fn write_no_scripts(out: &mut Cursor<Vec<u8>>) {
+    write_ints(out, &[0; 5]);
+}
+
+//common fields, then the inventory length, capacity and pointer
+fn write_object(out: &mut Cursor<Vec<u8>>, pid: u32, inventory: i32) {
+    write_ints(out, &[1, 20100, 0, 0, 0, 0, 0, 3, 0x0100_0001, 0, 0, pid as i32, -1, 0, 0, 0, -1, -1]);
+    write_ints(out, &[inventory, inventory, 0x00DE_AD00]);
+}
+
+//the map written back has to be the same bytes
+fn round_trip(data: &Cursor<Vec<u8>>, map: &MapFile) {
+    let mut out = Cursor::new(Vec::new());
+    map.write(&mut out).unwrap();
+    assert_eq!(out.get_ref(), data.get_ref());
+}
+
+fn subtypes(pid: Pid) -> Result<u32, MapError> {
+    Ok(match pid.0 {
+        0x0000_0001 => 3,
+        0x0000_0002 => 1,
+        0x0000_0003 => 6,
+        0x0200_0001 => 0,
+        0x0200_0002 => 4,
+        _ => 5,
+    })
+}
+
+#[test]
+fn header_test() {
+    let mut out = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut out);
+    //a total that doesn't match the lists is kept as it is
+    write_ints(&mut out, &[7, 0, 0, 0]);
+    out.write_bytes(&[1, 2]).unwrap();
+    out.set_position(0);
+
+    let map = MapFile::open_with(&mut out, &mut subtypes).unwrap();
+    assert_eq!(map.header.name(), "ARTEMPLE.MAP");
+    assert_eq!(map.header.entrance_tile, 20100);
+    assert_eq!(map.header.entrance_rotation, 2);
+    assert_eq!(map.header.script_id, 5);
+    assert_eq!(map.header.map_id, 7);
+    assert_eq!(map.global_vars, [10, 11]);
+    assert_eq!(map.local_vars, [20, 21, 22]);
+
+    assert!(map.header.has_elevation(0));
+    assert!(!map.header.has_elevation(1));
+    assert!(!map.header.has_elevation(3));
+    assert!(map.tiles[1].is_none());
+
+    let tiles = map.tiles[2].as_ref().unwrap();
+    assert_eq!(tiles.width(), 100);
+    assert_eq!(tiles.get(5, 1), Some(&Tile { floor: 105, roof: 3 }));
+    assert_eq!(map.tiles[0].as_ref().unwrap().get(99, 99).unwrap().roof, 1);
+
+    assert!(map.scripts(ScriptType::Spatial).is_empty());
+    assert_eq!(map.all_objects().count(), 0);
+    assert_eq!(map.object_count, 7);
+    assert_eq!(map.extra, [1, 2]);
+    round_trip(&out, &map);
+}
+
+#[test]
+fn scripts_test() {
+    let mut out = make_map(VERSION_FALLOUT_2);
+
+    //17 spatial scripts take two blocks, unused slots are still stored
+    write_ints(&mut out, &[0, 17]);
+    for i in 0 .. 32 {
+        write_script(&mut out, 0x0100_0000 | i);
+        if i == 15 {
+            write_ints(&mut out, &[16, 0]);
+        }
+    }
+    write_ints(&mut out, &[1, 0]);
+
+    //stored count higher than the used slots
+    write_ints(&mut out, &[2]);
+    write_script(&mut out, 0x0200_0000);
+    for _ in 1 .. 16 {
+        write_script(&mut out, 0);
+    }
+    write_ints(&mut out, &[1, 0]);
+
+    write_ints(&mut out, &[0, 0]);
+    write_ints(&mut out, &[0, 0, 0, 0]);
+    out.set_position(0);
+
+    let map = MapFile::open_with(&mut out, &mut subtypes).unwrap();
+    assert!(map.scripts(ScriptType::System).is_empty());
+
+    let spatial = map.scripts(ScriptType::Spatial);
+    assert_eq!(spatial.extents.len(), 2);
+    assert_eq!(spatial.extents[1].scripts.len(), EXTENT_SIZE);
+    assert_eq!(spatial.len(), 17);
+    assert_eq!(spatial.count, 17);
+
+    let last = spatial.iter().last().unwrap();
+    assert_eq!(last.sid, 0x0100_0010);
+    assert_eq!(last.script_type(), Some(ScriptType::Spatial));
+    assert_eq!(last.data, ScriptData::Spatial { tile: 0x2000_0064, radius: 3 });
+    assert_eq!(last.state.index, 0x10);
+    assert_eq!(last.state.owner_id, 42);
+
+    let timed = map.scripts(ScriptType::Timed);
+    assert_eq!(timed.len(), 1);
+    assert_eq!(timed.count, 2);
+    assert_eq!(timed.iter().next().unwrap().data, ScriptData::Timed { time: 600 });
+    assert_eq!(timed.extents[0].scripts[1].data, ScriptData::None);
+    round_trip(&out, &map);
+}
+
+#[test]
+fn objects_test() {
+    let mut out = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut out);
+    write_ints(&mut out, &[4, 3]);
+
+    //critter with a loaded gun and a bag holding a key
+    write_object(&mut out, 0x0100_0001, 2);
+    write_ints(&mut out, &[1, 0, 0, 8, 0, 12, 1, -1, 30, 0, 0]);
+    write_ints(&mut out, &[1]);
+    write_object(&mut out, 0x0000_0001, 0);
+    write_ints(&mut out, &[0, 12, 0x0000_0029]);
+    write_ints(&mut out, &[1]);
+    write_object(&mut out, 0x0000_0002, 1);
+    write_ints(&mut out, &[0]);
+    write_ints(&mut out, &[1]);
+    write_object(&mut out, 0x0000_0003, 0);
+    write_ints(&mut out, &[0, 77]);
+
+    write_object(&mut out, 0x0200_0001, 0);
+    write_ints(&mut out, &[-0x3333_3334, 1]);
+    write_object(&mut out, 0x0500_0010, 0);
+    write_ints(&mut out, &[0, 12, 20100, 0, 2]);
+
+    write_ints(&mut out, &[0, 1]);
+    write_object(&mut out, 0x0200_0002, 0);
+    write_ints(&mut out, &[0, 3, 0x2000_0064]);
+    out.set_position(0);
+
+    let map = MapFile::open_with(&mut out, &mut subtypes).unwrap();
+    assert_eq!(map.objects.each_ref().map(Vec::len), [3, 0, 1]);
+
+    let critter = &map.objects[0][0];
+    assert_eq!(critter.header.pid.proto_type(), Some(ProtoType::Critter));
+    assert_eq!(critter.header.rotation, 3);
+    assert_eq!(critter.inventory_ptr, 0x00DE_AD00);
+    let ObjectData::Critter(data) = &critter.data else { panic!("not a critter") };
+    assert_eq!(data.ap, 8);
+    assert_eq!(data.team, 1);
+    assert_eq!(data.hp, 30);
+
+    assert_eq!(critter.inventory.len(), 2);
+    let gun = &critter.inventory[0];
+    assert_eq!(gun.quantity, 1);
+    assert_eq!(gun.object.data, ObjectData::Other { flags: 0, kind: ObjectKind::Weapon { ammo_quantity: 12, ammo_pid: 0x29 } });
+    let key = &critter.inventory[1].object.inventory[0].object;
+    assert_eq!(key.data, ObjectData::Other { flags: 0, kind: ObjectKind::Key { key_code: 77 } });
+
+    assert_eq!(map.objects[0][1].data, ObjectData::Other { flags: 0xCCCC_CCCC, kind: ObjectKind::Door { open_flags: 1 } });
+    assert_eq!(map.objects[0][2].data, ObjectData::Other {
+        flags: 0,
+        kind: ObjectKind::ExitGrid { map: 12, tile: 20100, elevation: 0, rotation: 2 },
+    });
+    assert_eq!(map.objects[2][0].data, ObjectData::Other {
+        flags: 0,
+        kind: ObjectKind::Ladder { destination_map: Some(3), destination: 0x2000_0064 },
+    });
+    round_trip(&out, &map);
+}
+
+#[test]
+fn fallout_1_test() {
+    //fallout 1 ladders only store the destination tile
+    let mut out = make_map(VERSION_FALLOUT_1);
+    write_no_scripts(&mut out);
+    write_ints(&mut out, &[1, 1]);
+    write_object(&mut out, 0x0200_0002, 0);
+    write_ints(&mut out, &[0, 0x2000_0064]);
+    write_ints(&mut out, &[0, 0]);
+    out.set_position(0);
+
+    let map = MapFile::open_with(&mut out, &mut subtypes).unwrap();
+    assert_eq!(map.objects[0][0].data, ObjectData::Other {
+        flags: 0,
+        kind: ObjectKind::Ladder { destination_map: None, destination: 0x2000_0064 },
+    });
+    assert!(map.extra.is_empty());
+    round_trip(&out, &map);
+}
+
+#[test]
+fn edit_test() {
+    let mut out = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut out);
+    write_ints(&mut out, &[2, 2]);
+    write_object(&mut out, 0x0000_0003, 0);
+    write_ints(&mut out, &[0, 5]);
+    write_object(&mut out, 0x0100_0001, 0);
+    write_ints(&mut out, &[0; 11]);
+    write_ints(&mut out, &[0, 0]);
+    out.set_position(0);
+    let mut map = MapFile::open_with(&mut out, &mut subtypes).unwrap();
+
+    //move every object, put the key in the critter's inventory
+    for object in map.all_objects_mut() {
+        object.header.tile += 200;
+    }
+    let key = map.objects[0].remove(0);
+    map.objects[0][0].inventory.push(object::InventoryItem { quantity: 1, object: key });
+    map.object_count -= 1;
+
+    //add an elevation, a variable and a timed script in a block of its own
+    map.tiles[1] = Some(Vec2d::new_with(TILES_WIDTH, TILES_HEIGHT, Tile { floor: 1, roof: 2 }));
+    map.global_vars.push(12);
+    map.scripts[ScriptType::Timed as usize].extents.push(ScriptExtent {
+        scripts: vec![MapScript { sid: 0x0200_0000, data: ScriptData::Timed { time: 30 }, ..Default::default() }],
+        length: 1,
+        next: 0,
+    });
+    map.scripts[ScriptType::Timed as usize].count += 1;
+
+    let mut written = Cursor::new(Vec::new());
+    map.write(&mut written).unwrap();
+    written.set_position(0);
+    let edited = MapFile::open_with(&mut written, &mut subtypes).unwrap();
+
+    assert_eq!(edited.header.global_var_count, 3);
+    assert!(edited.header.has_elevation(1));
+    assert_eq!(edited.tiles[1].as_ref().unwrap().get(3, 3), Some(&Tile { floor: 1, roof: 2 }));
+    assert_eq!(edited.objects[0].len(), 1);
+    assert_eq!(edited.object_count, 1);
+    assert_eq!(edited.objects[0][0].header.tile, 20300);
+    assert_eq!(edited.objects[0][0].inventory[0].object.header.tile, 20300);
+
+    let timed = edited.scripts(ScriptType::Timed);
+    assert_eq!(timed.extents[0].scripts.len(), EXTENT_SIZE);
+    assert_eq!(timed.iter().next().unwrap().data, ScriptData::Timed { time: 30 });
+
+    //apart from the padded script block it's the same map
+    let mut expected = map.clone();
+    expected.header = edited.header.clone();
+    expected.scripts = edited.scripts.clone();
+    assert_eq!(edited, expected);
+}
+
+#[test]
+fn script_edit_test() {
+    let mut out = make_map(VERSION_FALLOUT_2);
+    write_ints(&mut out, &[0, 1]);
+    write_script(&mut out, 0x0100_0000);
+    for _ in 1 .. 16 {
+        write_script(&mut out, 0);
+    }
+    write_ints(&mut out, &[1, 0]);
+    write_ints(&mut out, &[0, 0, 0]);
+    write_ints(&mut out, &[0, 0, 0, 0]);
+    out.set_position(0);
+    let mut map = MapFile::open_with(&mut out, &mut subtypes).unwrap();
+
+    //move the spatial script and add a timed one next to it in the same block
+    let spatial = &mut map.scripts[ScriptType::Spatial as usize];
+    spatial.extents[0].scripts[0].data = ScriptData::Spatial { tile: 0x2000_00C8, radius: 5 };
+    spatial.extents[0].scripts[1] = MapScript { sid: 0x0100_0001, data: ScriptData::Spatial { tile: 7, radius: 1 }, ..Default::default() };
+    spatial.extents[0].length = 2;
+    spatial.count = 2;
+
+    let mut written = Cursor::new(Vec::new());
+    map.write(&mut written).unwrap();
+    written.set_position(0);
+    let edited = MapFile::open_with(&mut written, &mut subtypes).unwrap();
+    assert_eq!(edited.scripts(ScriptType::Spatial).len(), 2);
+    assert_eq!(edited, map);
+
+    //a block the count doesn't cover couldn't be read back
+    let mut extra_block = map.clone();
+    extra_block.scripts[ScriptType::Spatial as usize].extents.push(map.scripts[1].extents[0].clone());
+    assert!(extra_block.write(&mut Cursor::new(Vec::new())).is_err());
+
+    //neither could data of another script type
+    let mut wrong_data = map.clone();
+    wrong_data.scripts[ScriptType::Spatial as usize].extents[0].scripts[1].sid = 0x0200_0001;
+    assert!(wrong_data.write(&mut Cursor::new(Vec::new())).is_err());
+}
+
+#[test]
+fn error_test() {
+    let mut bad_type = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut bad_type);
+    write_ints(&mut bad_type, &[1, 1]);
+    write_object(&mut bad_type, 0x0900_0001, 0);
+    bad_type.set_position(0);
+    assert!(matches!(MapFile::open_with(&mut bad_type, &mut subtypes), Err(MapError::UnknownType(Pid(0x0900_0001)))));
+
+    //every bag holds another bag
+    let mut deep = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut deep);
+    write_ints(&mut deep, &[1, 1]);
+    for _ in 0 .. 20 {
+        write_object(&mut deep, 0x0000_0002, 1);
+        write_ints(&mut deep, &[0, 1]);
+    }
+    deep.set_position(0);
+    assert!(matches!(MapFile::open_with(&mut deep, &mut subtypes), Err(MapError::InventoryTooDeep)));
+
+    let mut truncated = make_map(VERSION_FALLOUT_2);
+    write_ints(&mut truncated, &[0, 0]);
+    truncated.set_position(0);
+    assert!(matches!(MapFile::open_with(&mut truncated, &mut subtypes), Err(MapError::ReadError(_))));
+}
+
+#[test]
+fn proto_library_test() {
+    //a key, subtype 6
+    let mut key = Cursor::new(Vec::new());
+    write_ints(&mut key, &[0x0000_0001, 100, 0x0000_0001, 0, 0, 0]);
+    write_ints(&mut key, &[0, -1, 6, 0, 1, 0, 0, -1]);
+    key.write_u8(0).unwrap();
+    write_ints(&mut key, &[1]);
+
+    let mut files = vec![
+        ("proto\\items\\items.lst".to_string(), b"00000001.pro\n".to_vec()),
+        ("proto\\items\\00000001.pro".to_string(), key.into_inner()),
+    ];
+    for proto_type in &ProtoType::ALL[1 ..] {
+        files.push((proto_type.lst_path(), Vec::new()));
+    }
+    let files: Vec<_> = files.iter().map(|(name, data)| (name.as_str(), data.clone())).collect();
+    let dat = Dat2Writer::build(&files).unwrap();
+    let protos = ProtoLibrary::new(&dat, "english").unwrap();
+
+    let mut out = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut out);
+    write_ints(&mut out, &[2, 2]);
+    for code in [5, 6] {
+        write_object(&mut out, 0x0000_0001, 0);
+        write_ints(&mut out, &[0, code]);
+    }
+    write_ints(&mut out, &[0, 0]);
+    out.set_position(0);
+
+    let map = MapFile::open(&mut out, &protos).unwrap();
+    assert_eq!(map.objects[0][1].data, ObjectData::Other { flags: 0, kind: ObjectKind::Key { key_code: 6 } });
+
+    //items without a proto can't be read
+    let mut missing = make_map(VERSION_FALLOUT_2);
+    write_no_scripts(&mut missing);
+    write_ints(&mut missing, &[1, 1]);
+    write_object(&mut missing, 0x0000_0002, 0);
+    write_ints(&mut missing, &[0]);
+    missing.set_position(0);
+    assert!(matches!(MapFile::open(&mut missing, &protos), Err(MapError::ProError(_))));
+}
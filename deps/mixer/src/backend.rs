@@ -0,0 +1,116 @@
+use crate::{
+    Mixer,
+    MixerError,
+};
+
+use common::Wav;
+use std::io::Write;
+
+/// Audio output, pulls rendered stereo frames from a `Mixer`
+pub trait Backend {
+    fn sample_rate(&self) -> u32;
+
+    /// Renders the next `frames` frames and outputs them, the mixer has to
+    /// render at the backend's sample rate
+    fn pull(&mut self, mixer: &mut Mixer, frames: usize) -> Result<(), MixerError>;
+}
+
+fn check_rate(backend: &impl Backend, mixer: &Mixer) -> Result<(), MixerError> {
+    if mixer.sample_rate() != backend.sample_rate() {
+        return Err(MixerError::SampleRateMismatch {
+            mixer: mixer.sample_rate(),
+            backend: backend.sample_rate(),
+        });
+    }
+    Ok(())
+}
+
+/// Renders and throws the output away, for running without an audio device
+#[derive(Debug)]
+pub struct NullBackend {
+    sample_rate: u32,
+    buffer: Vec<i16>,
+    pub frames_pulled: u64,
+}
+
+impl NullBackend {
+    /// Backend for `mixer`'s output rate
+    pub fn for_mixer(mixer: &Mixer) -> Self {
+        Self::new(mixer.sample_rate())
+    }
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            buffer: Vec::new(),
+            frames_pulled: 0,
+        }
+    }
+}
+
+impl Backend for NullBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn pull(&mut self, mixer: &mut Mixer, frames: usize) -> Result<(), MixerError> {
+        check_rate(self, mixer)?;
+
+        self.buffer.resize(frames * Mixer::CHANNELS, 0);
+        mixer.render(&mut self.buffer);
+        self.frames_pulled += frames as u64;
+
+        Ok(())
+    }
+}
+
+/// Records the output, which can then be written out as a wav file
+#[derive(Debug)]
+pub struct WavBackend {
+    sample_rate: u32,
+    samples: Vec<i16>,
+}
+
+impl WavBackend {
+    /// Backend for `mixer`'s output rate
+    pub fn for_mixer(mixer: &Mixer) -> Self {
+        Self::new(mixer.sample_rate())
+    }
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    pub fn into_wav(self) -> Wav {
+        Wav::new(Mixer::CHANNELS as u16, self.sample_rate, self.samples)
+    }
+
+    pub fn finish(self, output: &mut impl Write) -> Result<(), MixerError> {
+        self.into_wav()
+            .write(output)
+            .map_err(MixerError::WriteError)
+    }
+}
+
+impl Backend for WavBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn pull(&mut self, mixer: &mut Mixer, frames: usize) -> Result<(), MixerError> {
+        check_rate(self, mixer)?;
+
+        let start = self.samples.len();
+        self.samples.resize(start + frames * Mixer::CHANNELS, 0);
+        mixer.render(&mut self.samples[start..]);
+
+        Ok(())
+    }
+}
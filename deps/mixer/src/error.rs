@@ -0,0 +1,28 @@
+use common::wav::WavError;
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+};
+
+#[derive(Debug)]
+pub enum MixerError {
+    WriteError(WavError),
+    /// The output can't run at 0 Hz
+    ZeroSampleRate,
+    /// A backend pulled from a mixer rendering at another rate
+    SampleRateMismatch { mixer: u32, backend: u32 },
+}
+
+impl Display for MixerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use MixerError::*;
+        match self {
+            WriteError(e) => write!(f, "Failed to write audio: {}", e),
+            ZeroSampleRate => write!(f, "Sample rate must be above 0"),
+            SampleRateMismatch { mixer, backend } =>
+                write!(f, "Mixer renders at {} Hz but the backend plays at {} Hz", mixer, backend),
+        }
+    }
+}
+
+impl Error for MixerError {}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests;
+
+pub mod error;
+pub mod mixer;
+pub mod backend;
+
+pub use self::{
+    error::MixerError,
+    mixer::{ Mixer, PlayParams, VoiceId },
+    backend::{ Backend, NullBackend, WavBackend },
+};
+
+use acm::Acm;
+use std::sync::Arc;
+
+/// Volume groups, each with its own volume setting
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    #[default]
+    Sfx,
+    Speech,
+    Music,
+}
+
+impl Bus {
+    pub const COUNT: usize = 3;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Decoded audio that voices play from, cheap to clone
+///
+/// Mono sounds play on both output channels, sounds with more than two
+/// channels only play their first two.
+#[derive(Debug, Clone)]
+pub struct Sound {
+    pub channels: u16,
+    pub sample_rate: u32,
+
+    samples: Arc<[i16]>,
+}
+
+impl Sound {
+    pub fn new(channels: u16, sample_rate: u32, samples: Vec<i16>) -> Self {
+        Self {
+            channels: channels.max(1),
+            sample_rate,
+            samples: samples.into(),
+        }
+    }
+
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Length in sample frames, one sample for every channel
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    //left and right sample of a frame
+    fn frame(&self, index: usize) -> (f32, f32) {
+        let start = index * self.channels as usize;
+        let left = self.samples[start] as f32;
+        let right = match self.channels {
+            1 => left,
+            _ => self.samples[start + 1] as f32,
+        };
+
+        (left, right)
+    }
+}
+
+impl From<&Acm> for Sound {
+    fn from(acm: &Acm) -> Self {
+        Self::new(acm.channels as u16, acm.sample_rate, acm.samples.clone())
+    }
+}
+
+impl From<Acm> for Sound {
+    fn from(acm: Acm) -> Self {
+        Self::new(acm.channels as u16, acm.sample_rate, acm.samples)
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    Bus,
+    MixerError,
+    Sound,
+};
+
+/// How a sound is played
+///
+/// * `volume` - 0.0 to 1.0, scaled by the bus and master volume
+/// * `pan` - -1.0 is fully left, 1.0 fully right
+/// * `priority` - when every voice is busy, a sound only replaces one with the
+///   same or a lower priority
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayParams {
+    pub bus: Bus,
+    pub volume: f32,
+    pub pan: f32,
+    pub looping: bool,
+    pub priority: u8,
+}
+
+impl Default for PlayParams {
+    fn default() -> Self {
+        Self {
+            bus: Bus::Sfx,
+            volume: 1.0,
+            pan: 0.0,
+            looping: false,
+            priority: 0,
+        }
+    }
+}
+
+/// Handle to a playing sound, stays unique after the voice is reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+struct Voice {
+    id: VoiceId,
+    sound: Sound,
+    params: PlayParams,
+
+    //position in source frames, and how far it moves per output frame
+    position: f64,
+    step: f64,
+    finished: bool,
+}
+
+impl Voice {
+    fn gains(&self) -> (f32, f32) {
+        let pan = self.params.pan.clamp(-1.0, 1.0);
+        let volume = self.params.volume.max(0.0);
+
+        (volume * (1.0 - pan).min(1.0), volume * (1.0 + pan).min(1.0))
+    }
+
+    //linear interpolation between the two nearest source frames
+    fn sample(&self) -> (f32, f32) {
+        let frames = self.sound.frames();
+        let index = self.position as usize;
+        let fraction = (self.position - index as f64) as f32;
+
+        let (l0, r0) = self.sound.frame(index);
+        let next = match index + 1 {
+            i if i < frames => Some(i),
+            _ if self.params.looping => Some(0),
+            _ => None,
+        };
+        let (l1, r1) = next.map_or((0.0, 0.0), |i| self.sound.frame(i));
+
+        (l0 + (l1 - l0) * fraction, r0 + (r1 - r0) * fraction)
+    }
+
+    //returns false once a one shot sound has ended
+    fn advance(&mut self) -> bool {
+        let frames = self.sound.frames() as f64;
+        self.position += self.step;
+
+        if self.position >= frames {
+            if !self.params.looping {
+                return false;
+            }
+            self.position %= frames;
+        }
+
+        true
+    }
+}
+
+/// Mixes playing sounds into interleaved stereo 16 bit output
+///
+/// Sounds are resampled to the output rate as they play. At most
+/// `max_voices` sounds play at once, past that new sounds steal the voice
+/// with the lowest priority, or the oldest one when priorities are equal.
+pub struct Mixer {
+    sample_rate: u32,
+    max_voices: usize,
+
+    voices: Vec<Voice>,
+    next_id: u64,
+
+    master_volume: f32,
+    bus_volumes: [f32; Bus::COUNT],
+
+    mix_buffer: Vec<f32>,
+}
+
+impl Mixer {
+    pub const CHANNELS: usize = 2;
+
+    pub fn new(sample_rate: u32, max_voices: usize) -> Result<Self, MixerError> {
+        if sample_rate == 0 {
+            return Err(MixerError::ZeroSampleRate);
+        }
+
+        Ok(Self {
+            sample_rate,
+            max_voices,
+
+            voices: Vec::with_capacity(max_voices),
+            next_id: 0,
+
+            master_volume: 1.0,
+            bus_volumes: [1.0; Bus::COUNT],
+
+            mix_buffer: Vec::new(),
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Starts playing `sound`, returns `None` if no voice could be freed for it
+    /// or the sound is empty or has no sample rate
+    pub fn play(&mut self, sound: &Sound, params: PlayParams) -> Option<VoiceId> {
+        if sound.frames() == 0 || sound.sample_rate == 0 || self.max_voices == 0 {
+            return None;
+        }
+
+        if self.voices.len() >= self.max_voices {
+            //voices are kept oldest first, so min_by_key finds the oldest of the lowest
+            let (index, lowest) = self.voices.iter()
+                .enumerate()
+                .min_by_key(|(_, v)| v.params.priority)?;
+
+            if lowest.params.priority > params.priority {
+                return None;
+            }
+            self.voices.remove(index);
+        }
+
+        let id = VoiceId(self.next_id);
+        self.next_id += 1;
+
+        self.voices.push(Voice {
+            id,
+            sound: sound.clone(),
+            params,
+            position: 0.0,
+            step: sound.sample_rate as f64 / self.sample_rate as f64,
+            finished: false,
+        });
+
+        Some(id)
+    }
+
+    pub fn stop(&mut self, id: VoiceId) {
+        self.voices.retain(|v| v.id != id);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voice(id).is_some()
+    }
+
+    pub fn playing_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn set_volume(&mut self, id: VoiceId, volume: f32) {
+        if let Some(voice) = self.voice_mut(id) {
+            voice.params.volume = volume;
+        }
+    }
+
+    pub fn set_pan(&mut self, id: VoiceId, pan: f32) {
+        if let Some(voice) = self.voice_mut(id) {
+            voice.params.pan = pan;
+        }
+    }
+
+    /// Stops a looping sound once it reaches its end
+    pub fn set_looping(&mut self, id: VoiceId, looping: bool) {
+        if let Some(voice) = self.voice_mut(id) {
+            voice.params.looping = looping;
+        }
+    }
+
+    pub fn bus_volume(&self, bus: Bus) -> f32 {
+        self.bus_volumes[bus.index()]
+    }
+
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: f32) {
+        self.bus_volumes[bus.index()] = volume.max(0.0);
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.max(0.0);
+    }
+
+    /// Fills `output` with interleaved stereo frames
+    pub fn render(&mut self, output: &mut [i16]) {
+        self.mix_buffer.clear();
+        self.mix_buffer.resize(output.len(), 0.0);
+
+        let frames = output.len() / Self::CHANNELS;
+        for voice in self.voices.iter_mut() {
+            let (left, right) = voice.gains();
+            let bus = self.bus_volumes[voice.params.bus.index()] * self.master_volume;
+            let (left, right) = (left * bus, right * bus);
+
+            for frame in self.mix_buffer.chunks_exact_mut(Self::CHANNELS).take(frames) {
+                let (l, r) = voice.sample();
+                frame[0] += l * left;
+                frame[1] += r * right;
+
+                if !voice.advance() {
+                    voice.finished = true;
+                    break;
+                }
+            }
+        }
+        self.voices.retain(|v| !v.finished);
+
+        for (out, mixed) in output.iter_mut().zip(&self.mix_buffer) {
+            *out = mixed.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+    }
+
+    fn voice(&self, id: VoiceId) -> Option<&Voice> {
+        self.voices.iter().find(|v| v.id == id)
+    }
+
+    fn voice_mut(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices.iter_mut().find(|v| v.id == id)
+    }
+}
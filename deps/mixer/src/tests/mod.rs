@@ -0,0 +1,149 @@
+use std::io::Cursor;
+use common::Wav;
+use crate::*;
+
+fn render(mixer: &mut Mixer, frames: usize) -> Vec<i16> {
+    let mut backend = WavBackend::for_mixer(mixer);
+    backend.pull(mixer, frames).unwrap();
+    backend.samples().to_vec()
+}
+
+fn constant(value: i16, frames: usize) -> Sound {
+    Sound::new(1, 22050, vec![value; frames])
+}
+
+#[test]
+fn mono_test() {
+    let mut mixer = Mixer::new(22050, 4).unwrap();
+    let id = mixer.play(&Sound::new(1, 22050, vec![100, 200, -300]), PlayParams::default()).unwrap();
+    assert!(mixer.is_playing(id));
+
+    let out = render(&mut mixer, 4);
+    assert_eq!(out, [100, 100, 200, 200, -300, -300, 0, 0]);
+    assert!(!mixer.is_playing(id));
+}
+
+#[test]
+fn volume_test() {
+    let mut mixer = Mixer::new(22050, 4).unwrap();
+    let sound = Sound::new(2, 22050, vec![1000, 2000]);
+
+    mixer.play(&sound, PlayParams { pan: -1.0, ..Default::default() });
+    assert_eq!(render(&mut mixer, 1), [1000, 0]);
+
+    mixer.play(&sound, PlayParams { volume: 0.5, pan: 0.5, ..Default::default() });
+    assert_eq!(render(&mut mixer, 1), [250, 1000]);
+
+    mixer.set_bus_volume(Bus::Music, 0.5);
+    mixer.set_master_volume(0.5);
+    mixer.play(&sound, PlayParams { bus: Bus::Music, ..Default::default() });
+    mixer.play(&sound, PlayParams { bus: Bus::Speech, ..Default::default() });
+    assert_eq!(render(&mut mixer, 1), [250 + 500, 500 + 1000]);
+
+    //mixing clips instead of wrapping
+    mixer.set_master_volume(1.0);
+    for _ in 0 .. 4 {
+        mixer.play(&constant(i16::MAX, 2), PlayParams::default());
+    }
+    assert_eq!(render(&mut mixer, 1), [i16::MAX, i16::MAX]);
+}
+
+#[test]
+fn looping_test() {
+    let mut mixer = Mixer::new(22050, 4).unwrap();
+    let id = mixer.play(&Sound::new(1, 22050, vec![1, 2, 3]), PlayParams {
+        looping: true,
+        ..Default::default()
+    }).unwrap();
+
+    let out = render(&mut mixer, 7);
+    assert_eq!(out.iter().step_by(2).copied().collect::<Vec<_>>(), [1, 2, 3, 1, 2, 3, 1]);
+    assert!(mixer.is_playing(id));
+
+    mixer.set_looping(id, false);
+    let out = render(&mut mixer, 3);
+    assert_eq!(out.iter().step_by(2).copied().collect::<Vec<_>>(), [2, 3, 0]);
+    assert!(!mixer.is_playing(id));
+}
+
+#[test]
+fn resample_test() {
+    //11025 Hz sounds play twice as long at 22050 Hz, the new frames are interpolated
+    let mut mixer = Mixer::new(22050, 4).unwrap();
+    mixer.play(&Sound::new(1, 11025, vec![0, 100, 200]), PlayParams::default());
+
+    let out = render(&mut mixer, 7);
+    assert_eq!(out.iter().step_by(2).copied().collect::<Vec<_>>(), [0, 50, 100, 150, 200, 100, 0]);
+    assert_eq!(mixer.playing_count(), 0);
+
+    //44100 Hz sounds skip every other frame
+    mixer.play(&Sound::new(1, 44100, vec![0, 1, 2, 3, 4, 5]), PlayParams::default());
+    let out = render(&mut mixer, 4);
+    assert_eq!(out.iter().step_by(2).copied().collect::<Vec<_>>(), [0, 2, 4, 0]);
+}
+
+#[test]
+fn stealing_test() {
+    let mut mixer = Mixer::new(22050, 2).unwrap();
+    let low = mixer.play(&constant(1, 10), PlayParams { priority: 1, ..Default::default() }).unwrap();
+    let high = mixer.play(&constant(2, 10), PlayParams { priority: 5, ..Default::default() }).unwrap();
+
+    //lower priority than anything playing
+    assert!(mixer.play(&constant(4, 10), PlayParams::default()).is_none());
+
+    let new = mixer.play(&constant(8, 10), PlayParams { priority: 1, ..Default::default() }).unwrap();
+    assert!(!mixer.is_playing(low));
+    assert!(mixer.is_playing(high));
+    assert_ne!(new, low);
+    assert_eq!(render(&mut mixer, 1), [10, 10]);
+
+    //equal priorities replace the oldest voice
+    let newest = mixer.play(&constant(16, 10), PlayParams { priority: 5, ..Default::default() }).unwrap();
+    assert!(!mixer.is_playing(new));
+    assert!(mixer.is_playing(high));
+    assert!(mixer.is_playing(newest));
+
+    mixer.stop(high);
+    assert_eq!(render(&mut mixer, 1), [16, 16]);
+    mixer.stop_all();
+    assert_eq!(mixer.playing_count(), 0);
+}
+
+#[test]
+fn backend_test() {
+    let mut mixer = Mixer::new(22050, 4).unwrap();
+    mixer.play(&constant(7, 100), PlayParams::default());
+
+    let mut null = NullBackend::new(22050);
+    null.pull(&mut mixer, 60).unwrap();
+    null.pull(&mut mixer, 60).unwrap();
+    assert_eq!(null.frames_pulled, 120);
+    assert_eq!(mixer.playing_count(), 0);
+
+    mixer.play(&constant(7, 3), PlayParams::default());
+    let mut backend = WavBackend::new(22050);
+    backend.pull(&mut mixer, 4).unwrap();
+
+    let mut data = Vec::new();
+    backend.finish(&mut data).unwrap();
+    let wav = Wav::open(&mut Cursor::new(data)).unwrap();
+    assert_eq!(wav.channels, 2);
+    assert_eq!(wav.sample_rate, 22050);
+    assert_eq!(wav.samples, [7, 7, 7, 7, 7, 7, 0, 0]);
+
+    let mut other = WavBackend::new(44100);
+    assert!(matches!(
+        other.pull(&mut mixer, 1),
+        Err(MixerError::SampleRateMismatch { mixer: 22050, backend: 44100 })
+    ));
+    assert!(other.samples().is_empty());
+}
+
+#[test]
+fn sample_rate_test() {
+    assert!(matches!(Mixer::new(0, 4), Err(MixerError::ZeroSampleRate)));
+
+    let mut mixer = Mixer::new(22050, 4).unwrap();
+    assert!(mixer.play(&Sound::new(1, 0, vec![1, 2]), PlayParams::default()).is_none());
+    assert_eq!(mixer.playing_count(), 0);
+}
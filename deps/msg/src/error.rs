@@ -0,0 +1,33 @@
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+    io,
+};
+
+#[derive(Debug)]
+pub enum MsgError {
+    ReadError(io::Error),
+    /// No file with this path in the archive
+    NotFound(String),
+    /// The archive entry couldn't be unpacked
+    UnpackError(Box<dyn Error>),
+    /// The id of the message starting on `line` isn't a number
+    InvalidId { line: usize },
+    /// A field opened on `line` is never closed
+    Unterminated { line: usize },
+}
+
+impl Display for MsgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use MsgError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading msg file: {}", e),
+            NotFound(path) => write!(f, "Msg file {} not found", path),
+            UnpackError(e) => write!(f, "Error unpacking msg file: {}", e),
+            InvalidId { line } => write!(f, "Invalid message id on line {}", line),
+            Unterminated { line } => write!(f, "Unclosed brace on line {}", line),
+        }
+    }
+}
+
+impl Error for MsgError {}
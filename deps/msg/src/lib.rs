@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests;
+
+pub mod error;
+pub use error::MsgError;
+
+use dat::DatFile;
+
+use std::{
+    collections::BTreeMap,
+    io::Read,
+};
+
+/// A single `{id}{sound}{text}` record
+///
+/// * `sound` - speech file played with the line, only set in dialogue files
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub id: i32,
+    pub sound: Option<String>,
+    pub text: String,
+}
+
+/// Game text, as stored in `text/<language>/game/*.msg` and `text/<language>/dialog/*.msg`
+///
+/// Messages are `{id}{sound}{text}` records. Like the original engine, anything
+/// between records is skipped, which covers comments and stray `}`, and line
+/// breaks inside a field are dropped so long texts can span several lines.
+/// A message id used twice keeps the last text.
+#[derive(Debug, Default, Clone)]
+pub struct MsgFile {
+    messages: BTreeMap<i32, Message>,
+}
+
+impl MsgFile {
+    pub fn open(stream: &mut impl Read) -> Result<Self, MsgError> {
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).map_err(MsgError::ReadError)?;
+
+        Self::from_bytes(&data)
+    }
+
+    /// Loads the msg file at `path` from a DAT archive
+    pub fn load(dat: &DatFile, path: &str) -> Result<Self, MsgError> {
+        let path = path.to_ascii_lowercase();
+        let entry = dat.registry.get(&path)
+            .and_then(|node| node.read().ok()?.get_file_entry().cloned())
+            .ok_or_else(|| MsgError::NotFound(path.clone()))?;
+
+        let data = dat.unpack_file(&entry).map_err(MsgError::UnpackError)?;
+        Self::from_bytes(&data)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, MsgError> {
+        let mut parser = Parser { data, pos: 0, line: 1 };
+        let mut messages = BTreeMap::new();
+
+        while let Some(line) = parser.find_start() {
+            let id = parser.field(line)?;
+            let id = parse_id(&id).ok_or(MsgError::InvalidId { line })?;
+
+            let sound = parser.next_field(line)?;
+            let text = parser.next_field(line)?;
+
+            let sound = decode_text(sound.trim_ascii());
+            let sound = (!sound.is_empty()).then_some(sound);
+            messages.insert(id, Message { id, sound, text: decode_text(&text) });
+        }
+
+        Ok(Self { messages })
+    }
+
+    /// Path of a msg file inside a DAT archive
+    ///
+    /// e.g. `path("english", "game/pro_item.msg")` gives `text\english\game\pro_item.msg`
+    pub fn path(language: &str, name: &str) -> String {
+        format!("text\\{}\\{}", language, name.replace('/', "\\")).to_ascii_lowercase()
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Message> {
+        self.messages.get(&id)
+    }
+
+    /// Text of message `id`, without its speech file
+    pub fn text(&self, id: i32) -> Option<&str> {
+        self.get(id).map(|m| m.text.as_str())
+    }
+
+    /// Messages in id order
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.messages.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn next_byte(&mut self) -> Option<u8> {
+        let b = *self.data.get(self.pos)?;
+        self.pos += 1;
+        if b == b'\n' {
+            self.line += 1;
+        }
+
+        Some(b)
+    }
+
+    //skips to just past the next `{`, returns the line it's on
+    fn find_start(&mut self) -> Option<usize> {
+        loop {
+            if self.next_byte()? == b'{' {
+                return Some(self.line);
+            }
+        }
+    }
+
+    //reads up to the closing `}`, a `{` inside a field is kept as text
+    fn field(&mut self, line: usize) -> Result<Vec<u8>, MsgError> {
+        let mut field = Vec::new();
+        loop {
+            match self.next_byte() {
+                Some(b'}') => return Ok(field),
+                Some(b'\r' | b'\n') => { },
+                Some(b) => field.push(b),
+                None => return Err(MsgError::Unterminated { line }),
+            }
+        }
+    }
+
+    //`line` is where the message started, for errors
+    fn next_field(&mut self, line: usize) -> Result<Vec<u8>, MsgError> {
+        self.find_start().ok_or(MsgError::Unterminated { line })?;
+        self.field(line)
+    }
+}
+
+//digits with an optional sign, like the engine accepts
+fn parse_id(bytes: &[u8]) -> Option<i32> {
+    std::str::from_utf8(bytes).ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+//localised files are usually in a single byte code page, fall back to latin-1
+fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|b| *b as char).collect(),
+    }
+}
@@ -0,0 +1,67 @@
+use dat::Dat2Writer;
+use crate::*;
+
+const DIALOG: &[u8] = b"\
+# Myron's dialogue
+{100}{myrn001}{What do you want?}
+{101}{}{A line that goes
+ on for a while.}
+
+}stray braces between messages are ignored
+{102}{ }{Says {hi} twice}
+{100}{myrn002}{Replaced.}
+";
+
+#[test]
+fn parse_test() {
+    let msg = MsgFile::from_bytes(DIALOG).unwrap();
+    assert_eq!(msg.len(), 3);
+
+    let first = msg.get(100).unwrap();
+    assert_eq!(first.sound.as_deref(), Some("myrn002"));
+    assert_eq!(first.text, "Replaced.");
+
+    assert_eq!(msg.get(101).unwrap().sound, None);
+    assert_eq!(msg.text(101), Some("A line that goes on for a while."));
+    //the `{` is kept, the first `}` closes the text
+    assert_eq!(msg.text(102), Some("Says {hi"));
+    assert_eq!(msg.get(102).unwrap().sound, None);
+    assert_eq!(msg.text(103), None);
+
+    let ids: Vec<_> = msg.iter().map(|m| m.id).collect();
+    assert_eq!(ids, [100, 101, 102]);
+}
+
+#[test]
+fn encoding_test() {
+    let msg = MsgFile::from_bytes(b"{1}{}{caf\xe9}\r\n{-2}{}{\xc3\xa9t\xc3\xa9}").unwrap();
+    assert_eq!(msg.text(1), Some("café"));
+    assert_eq!(msg.text(-2), Some("été"));
+}
+
+#[test]
+fn error_test() {
+    let result = MsgFile::from_bytes(b"{1}{}{ok}\n{x1}{}{bad id}");
+    assert!(matches!(result, Err(MsgError::InvalidId { line: 2 })));
+
+    let result = MsgFile::from_bytes(b"{1}{}{ok}\n\n{2}{}{never closed");
+    assert!(matches!(result, Err(MsgError::Unterminated { line: 3 })));
+
+    let result = MsgFile::from_bytes(b"{1}{}");
+    assert!(matches!(result, Err(MsgError::Unterminated { line: 1 })));
+
+    assert!(MsgFile::from_bytes(b"# nothing here").unwrap().is_empty());
+}
+
+#[test]
+fn dat_test() {
+    assert_eq!(MsgFile::path("English", "game/pro_item.msg"), "text\\english\\game\\pro_item.msg");
+
+    let dat = Dat2Writer::build(&[("text\\english\\game\\misc.msg", b"{5}{}{Five}")]).unwrap();
+
+    let msg = MsgFile::load(&dat, &MsgFile::path("english", "game\\MISC.MSG")).unwrap();
+    assert_eq!(msg.text(5), Some("Five"));
+
+    let missing = MsgFile::load(&dat, "text\\english\\game\\none.msg");
+    assert!(matches!(missing, Err(MsgError::NotFound(_))));
+}
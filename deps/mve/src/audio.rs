@@ -0,0 +1,172 @@
+use crate::{
+    AudioChannels,
+    AudioChannelWidth,
+    AudioCompression,
+    AudioFrame,
+    Error,
+    InitAudioBuffers,
+    MveFile,
+    Opcode,
+};
+
+use common::{
+    Stream,
+    Wav,
+};
+
+//interplay dpcm step table, indexed by the encoded byte
+const DELTA_CODINGS: [i16; 256] =
+    [
+         0,      1,      2,      3,      4,      5,      6,      7,      8,      9,     10,     11,     12,     13,     14,     15,
+        16,     17,     18,     19,     20,     21,     22,     23,     24,     25,     26,     27,     28,     29,     30,     31,
+        32,     33,     34,     35,     36,     37,     38,     39,     40,     41,     42,     43,     47,     51,     56,     61,
+        66,     72,     79,     86,     94,    102,    112,    122,    133,    145,    158,    173,    189,    206,    225,    245,
+        267,    292,    318,    348,    379,    414,    452,    493,    538,    587,    640,    699,    763,    832,    908,    991,
+        1081,   1180,   1288,   1405,   1534,   1673,   1826,   1993,   2175,   2373,   2590,   2826,   3084,   3365,   3672,   4008,
+        4373,   4772,   5208,   5683,   6202,   6767,   7385,   8059,   8794,   9597,  10472,  11428,  12471,  13609,  14851,  16206,
+        17685,  19298,  21060,  22981,  25078,  27367,  29864,  32589, -29973, -26728, -23186, -19322, -15105, -10503,  -5481,     -1,
+            1,      1,   5481,  10503,  15105,  19322,  23186,  26728,  29973, -32589, -29864, -27367, -25078, -22981, -21060, -19298,
+        -17685, -16206, -14851, -13609, -12471, -11428, -10472,  -9597,  -8794,  -8059,  -7385,  -6767,  -6202,  -5683,  -5208,  -4772,
+        -4373,  -4008,  -3672,  -3365,  -3084,  -2826,  -2590,  -2373,  -2175,  -1993,  -1826,  -1673,  -1534,  -1405,  -1288,  -1180,
+        -1081,   -991,   -908,   -832,   -763,   -699,   -640,   -587,   -538,   -493,   -452,   -414,   -379,   -348,   -318,   -292,
+        -267,   -245,   -225,   -206,   -189,   -173,   -158,   -145,   -133,   -122,   -112,   -102,    -94,    -86,    -79,    -72,
+        -66,    -61,    -56,    -51,    -47,    -43,    -42,    -41,    -40,    -39,    -38,    -37,    -36,    -35,    -34,    -33,
+        -32,    -31,    -30,    -29,    -28,    -27,    -26,    -25,    -24,    -23,    -22,    -21,    -20,    -19,    -18,    -17,
+        -16,    -15,    -14,    -13,    -12,    -11,    -10,     -9,     -8,     -7,     -6,     -5,     -4,     -3,     -2,     -1
+    ];
+
+/// Audio stream layout, from the init audio buffers opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub channels: AudioChannels,
+    pub channel_width: AudioChannelWidth,
+    pub compression: AudioCompression,
+    pub sample_rate: u16,
+}
+
+impl From<&InitAudioBuffers> for AudioFormat {
+    fn from(init: &InitAudioBuffers) -> Self {
+        match *init {
+            InitAudioBuffers::V0 { channels, channel_width, sample_rate, .. } =>
+                Self { channels, channel_width, compression: AudioCompression::Uncompressed, sample_rate },
+            InitAudioBuffers::V1 { channels, channel_width, compression, sample_rate, .. } =>
+                Self { channels, channel_width, compression, sample_rate },
+        }
+    }
+}
+
+impl AudioFormat {
+    pub fn channel_count(&self) -> u16 {
+        match self.channels {
+            AudioChannels::Mono => 1,
+            AudioChannels::Stereo => 2,
+        }
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        match self.channel_width {
+            AudioChannelWidth::Bit8 => 1,
+            AudioChannelWidth::Bit16 => 2,
+        }
+    }
+
+    /// Most samples a single audio frame can hold, one second over all channels
+    ///
+    /// Silence frames are only a length, so this keeps a corrupt one from
+    /// adding more samples than a movie frame could ever play.
+    pub fn max_frame_samples(&self) -> usize {
+        self.sample_rate as usize * self.channel_count() as usize
+    }
+}
+
+impl AudioFrame<'_> {
+    pub fn stream_mask(&self) -> u16 {
+        match self {
+            Self::Data { stream_mask, .. } | Self::Silence { stream_mask, .. } => *stream_mask,
+        }
+    }
+
+    /// Whether the frame belongs to audio track `track`, e.g. 0 for English
+    pub fn has_track(&self, track: u8) -> bool {
+        track < 16 && self.stream_mask() & (1 << track) != 0
+    }
+
+    /// Decodes the frame into interleaved 16 bit samples
+    pub fn samples(&self, format: &AudioFormat) -> Vec<i16> {
+        match *self {
+            Self::Data { data, .. } => match (format.compression, format.channel_width) {
+                (AudioCompression::Compressed, AudioChannelWidth::Bit16) =>
+                    uncompress_audio(format.channel_count() as usize, data),
+                (_, AudioChannelWidth::Bit16) => data
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                    .collect(),
+                (_, AudioChannelWidth::Bit8) => data
+                    .iter()
+                    .map(|b| (*b as i16 - 128) << 8)
+                    .collect(),
+            },
+
+            Self::Silence { stream_len, .. } => {
+                let channels = format.channel_count() as usize;
+                let len = (stream_len as usize / format.bytes_per_sample()).min(format.max_frame_samples());
+                vec![0; len - len % channels]
+            },
+        }
+    }
+}
+
+//starts with one initial sample per channel, then a byte per sample
+//indexing the step table, channels alternate between bytes
+fn uncompress_audio(channels: usize, data: &[u8]) -> Vec<i16> {
+    let header_len = (channels * 2).min(data.len() & !1);
+    let mut predictors: Vec<i32> = data[.. header_len]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as i32)
+        .collect();
+
+    let mut output: Vec<i16> = predictors.iter().map(|p| *p as i16).collect();
+    if predictors.len() < channels {
+        return output;
+    }
+
+    output.reserve(data.len() - header_len);
+    for (i, b) in data[header_len ..].iter().enumerate() {
+        let predictor = &mut predictors[i % channels];
+        *predictor = (*predictor + DELTA_CODINGS[*b as usize] as i32)
+            .clamp(i16::MIN as i32, i16::MAX as i32);
+        output.push(*predictor as i16);
+    }
+
+    output
+}
+
+impl<S: Stream> MveFile<S> {
+    /// Reads the rest of the movie and collects audio track `track`
+    ///
+    /// Returns `None` if the movie has no audio
+    pub fn read_audio(&mut self, track: u8) -> Result<Option<Wav>, Error> {
+        let mut format: Option<AudioFormat> = None;
+        let mut samples = Vec::new();
+
+        while let Some(chunk) = self.next_chunk()? {
+            for op in chunk.opcodes() {
+                match op? {
+                    Opcode::InitAudioBuffers(init) => {
+                        format.get_or_insert(AudioFormat::from(&init));
+                    },
+                    Opcode::AudioFrame(frame) if frame.has_track(track) => {
+                        if let Some(format) = &format {
+                            samples.extend(frame.samples(format));
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        Ok(format.map(|format| {
+            Wav::new(format.channel_count(), format.sample_rate as u32, samples)
+        }))
+    }
+}
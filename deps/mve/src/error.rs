@@ -1,11 +1,19 @@
-use std::fmt::{Debug, Display, Formatter, write};
+use std::fmt::{Debug, Display, Formatter};
 
 use std::io::Error as IoError;
 
 #[derive(Debug)]
 pub enum Error {
-    FileError,
+    /// File does not start with the MVE signature
+    BadMagic,
     ReadError(IoError),
+    /// Stream ended inside the chunk starting at `offset`
+    TruncatedChunk { offset: u64 },
+    /// Opcode header or data runs past the end of its chunk
+    TruncatedOpcode { opcode: Option<u8> },
+    UnknownChunkType(u16),
+    UnknownOpcode(u8),
+    UnknownOpcodeVersion { opcode: u8, version: u8 },
 }
 
 impl Display for Error {
@@ -22,6 +30,6 @@ pub trait FromIoError<T> {
 }
 impl<T> FromIoError<T> for Result<T, IoError> {
     fn to(self) -> Result<T, Error> {
-        self.map_err(|x| Error::ReadError(x))
+        self.map_err(Error::ReadError)
     }
 }
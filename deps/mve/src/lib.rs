@@ -1,6 +1,8 @@
 #![allow(unused)]
 
 pub mod error;
+pub mod subtitles;
+pub mod audio;
 
 #[cfg(test)]
 mod tests;
@@ -9,34 +11,23 @@ pub use error::{
     Error::{ self, * },
     FromIoError
 };
+pub use subtitles::{ Subtitle, Subtitles };
+pub use audio::AudioFormat;
 
 use common::{ 
-    read_num, 
-    read_bytes,
-    readers::{ FromBytes, ReadMode },
+    BinaryReader,
+    Stream,
 };
 
 use std::{
     io::{Cursor, Read}, 
     ops::{ Add, Sub },
     cell::Cell,
+    time::Duration,
 };
 
 use itertools::Itertools;
 
-macro_rules! read_mve {
-    ($data:expr, $t:ty) => {{
-        read_num!($data, $t, le)
-    }};
-}
-
-pub(crate) fn read_type<T, const N: usize>(data: &mut impl Read) -> Result<T, Error> where T: FromBytes<N> {
-    match common::readers::read_type(ReadMode::LE, data) {
-        Ok(o) => Ok(o),
-        Err(e) => Err(ReadError(e)),
-    }
-}
-
 struct DeltaIterator<T, I> where T: Add<Output = T> + Copy, I: Iterator<Item = T> {
     value: Option<T>,
     iter: I,
@@ -81,78 +72,44 @@ impl<T: Add<Output = T> + Copy, I: Iterator<Item = T>> IntoDeltaIterator<T> for
     }
 }
 
-fn uncompress_audio(initial_value: i16, data: &[i8]) -> Option<(i16, Vec<i16>)> {
-    const DELTA_CODINGS: [i16; 256] = 
-        [
-             0,      1,      2,      3,      4,      5,      6,      7,      8,      9,     10,     11,     12,     13,     14,     15,
-            16,     17,     18,     19,     20,     21,     22,     23,     24,     25,     26,     27,     28,     29,     30,     31,
-            32,     33,     34,     35,     36,     37,     38,     39,     40,     41,     42,     43,     47,     51,     56,     61,
-            66,     72,     79,     86,     94,    102,    112,    122,    133,    145,    158,    173,    189,    206,    225,    245,
-            267,    292,    318,    348,    379,    414,    452,    493,    538,    587,    640,    699,    763,    832,    908,    991,
-            1081,   1180,   1288,   1405,   1534,   1673,   1826,   1993,   2175,   2373,   2590,   2826,   3084,   3365,   3672,   4008,
-            4373,   4772,   5208,   5683,   6202,   6767,   7385,   8059,   8794,   9597,  10472,  11428,  12471,  13609,  14851,  16206,
-            17685,  19298,  21060,  22981,  25078,  27367,  29864,  32589, -29973, -26728, -23186, -19322, -15105, -10503,  -5481,     -1,
-                1,      1,   5481,  10503,  15105,  19322,  23186,  26728,  29973, -32589, -29864, -27367, -25078, -22981, -21060, -19298,
-            -17685, -16206, -14851, -13609, -12471, -11428, -10472,  -9597,  -8794,  -8059,  -7385,  -6767,  -6202,  -5683,  -5208,  -4772,
-            -4373,  -4008,  -3672,  -3365,  -3084,  -2826,  -2590,  -2373,  -2175,  -1993,  -1826,  -1673,  -1534,  -1405,  -1288,  -1180,
-            -1081,   -991,   -908,   -832,   -763,   -699,   -640,   -587,   -538,   -493,   -452,   -414,   -379,   -348,   -318,   -292,
-            -267,   -245,   -225,   -206,   -189,   -173,   -158,   -145,   -133,   -122,   -112,   -102,    -94,    -86,    -79,    -72,
-            -66,    -61,    -56,    -51,    -47,    -43,    -42,    -41,    -40,    -39,    -38,    -37,    -36,    -35,    -34,    -33,
-            -32,    -31,    -30,    -29,    -28,    -27,    -26,    -25,    -24,    -23,    -22,    -21,    -20,    -19,    -18,    -17,
-            -16,    -15,    -14,    -13,    -12,    -11,    -10,     -9,     -8,     -7,     -6,     -5,     -4,     -3,     -2,     -1
-        ];
-
-    let (last_delta, deltas) = apply_deltas(initial_value, data);
-    let mut uncompressed_values = Vec::with_capacity(deltas.len());
-
-    for d in deltas {
-        let value = DELTA_CODINGS.get(d as usize)?;
-        uncompressed_values.push(*value);
-    }
-
-    Some((last_delta, uncompressed_values))
-}
+const FILE_TYPE: &[u8; 20] = b"Interplay MVE File\x1a\0";
+const MAGIC_BYTES: [u16; 3] = [ 0x001a, 0x0100, 0x1133 ];
 
-#[derive(Debug)]
-struct Header {
-    file_type: String,
-    magic_bytes: [u16; 3],
+fn get_remaining<'a>(c: &Cursor<&'a [u8]>) -> &'a [u8] {
+    let data = *c.get_ref();
+    let pos = (c.position() as usize).min(data.len());
+    &data[pos..]
 }
 
-fn unwrap_inner<T, E>(r: Result< Result<T, E>, E >) -> Result<T, E> {
-    match r {
-        Ok( Ok(o) ) => Ok(o),
-        Ok(Err(e)) => Err(e),
-        Err(e) => Err(e),
+//like read_exact, but returns how much was read instead of failing at eof
+fn read_up_to(stream: &mut impl Read, buf: &mut [u8]) -> Result<usize, Error> {
+    let mut total = 0;
+    while total < buf.len() {
+        match stream.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(ReadError(e)),
+        }
     }
-}
 
-fn get_remaining<T>(c: &Cursor<T>) -> &[u8] where T: AsRef<[u8]> {
-    let pos = c.position() as usize;
-    &c.get_ref().as_ref()[pos..]
+    Ok(total)
 }
 
-#[derive(Debug)]
-struct Color {
-    red: u8,
-    green:u8,
-    blue: u8,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green:u8,
+    pub blue: u8,
 }
 
 // Opcodes
-#[derive(Debug)] enum AudioChannels { Mono, Stereo }
-#[derive(Debug)] enum AudioChannelWidth { Bit8, Bit16 }
-#[derive(Debug)] enum AudioCompression { Uncompressed, Compressed }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)] pub enum AudioChannels { Mono, Stereo }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)] pub enum AudioChannelWidth { Bit8, Bit16 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)] pub enum AudioCompression { Uncompressed, Compressed }
 
 #[derive(Debug)]
-struct AudioFlags {
-    channels: AudioChannels,
-    channel_width: AudioChannelWidth,
-    compression: AudioCompression,
-}
-
-#[derive(Debug)]
-enum InitAudioBuffers {
+pub enum InitAudioBuffers {
     V0{ 
         channels: AudioChannels,
         channel_width: AudioChannelWidth,
@@ -169,18 +126,43 @@ enum InitAudioBuffers {
 }
 
 #[derive(Debug)]
-enum InitVideoBuffers {
+pub enum InitVideoBuffers {
     V0{ width: u16, height: u16 },
     V1{ width: u16, height: u16, count: u16 },
     V2{ width: u16, height: u16, count: u16, true_color: u16 },
 }
 
 #[derive(Debug)]
-enum SendBufferToDisplay {
+pub enum SendBufferToDisplay {
     V0{ palette_start: u16, palette_count: u16 },
     V1{ palette_start: u16, palette_count: u16, unknown1: u16 },
 }
 
+/// Movie frame clock, set up by the create timer opcode
+///
+/// * `rate` - length of a timer tick in microseconds
+/// * `subdivision` - timer ticks per frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timer {
+    pub rate: u32,
+    pub subdivision: u16,
+}
+impl Timer {
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_micros(self.rate as u64 * self.subdivision as u64)
+    }
+
+    /// Index of the frame on screen after `elapsed` playback time
+    pub fn frame_at(&self, elapsed: Duration) -> u32 {
+        let frame_len = self.frame_duration().as_micros();
+        if frame_len == 0 {
+            return 0;
+        }
+
+        (elapsed.as_micros() / frame_len).try_into().unwrap_or(u32::MAX)
+    }
+}
+
 #[repr(u16)]
 enum LanguageFlags {
     English = 0
@@ -194,151 +176,126 @@ enum LanguageFlags {
 /// * `stream_len` - length of data stream
 /// * `data` - audio data, if Compressed data is delta encoded
 #[derive(Debug)]
-enum AudioFrame {
-    Data{ seq_index: u16, stream_mask: u16, stream_len: u16, data: Vec<i8> },
+pub enum AudioFrame<'a> {
+    Data{ seq_index: u16, stream_mask: u16, stream_len: u16, data: &'a [u8] },
     Silence{ seq_index: u16, stream_mask: u16, stream_len: u16 },
 }
-impl AudioFrame {
-    fn get_samples(&self, flags: &AudioFlags) -> Vec<i16> {
-        match self {
-            Self::Data { seq_index, stream_mask, stream_len, data } => {
-                todo!()
-            }
-            
-            Self::Silence { seq_index, stream_mask, stream_len } => {
-                //stream mask in silence ops are inverted?
-                let stream_mask = !stream_mask;
+impl<'a> AudioFrame<'a> {
+    /// Audio data reinterpreted as signed bytes, used by compressed streams
+    pub fn signed_data(&self) -> impl Iterator<Item = i8> + 'a {
+        let data: &'a [u8] = match self {
+            Self::Data { data, .. } => data,
+            Self::Silence { .. } => &[],
+        };
 
-                todo!()
-            },
-        }
+        data.iter().map(|b| *b as i8)
     }
 }
 
-struct OpcodeIterator<'a> {
-    length: usize,
-    data: Cursor<&'a [u8]>
+pub struct OpcodeIterator<'a> {
+    data: Cursor<&'a [u8]>,
 }
 impl<'a> Iterator for OpcodeIterator<'a> {
-    type Item = Opcode;
+    type Item = Result<Opcode<'a>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if (self.data.position() as usize) < self.length {
-            let data = &mut self.data;
-
-            let len: u16 = read_type(data).ok()?;
-            let type_ = read_type(data).ok()?;
-            let ver = read_type(data).ok()?;
-
-            let mut op_data = vec![0u8; len as usize];
-            self.data.read_exact(&mut op_data).ok()?;
-
-            Opcode::from_data(type_, ver, &op_data)
-        } else {
-            None
+        let remaining = get_remaining(&self.data);
+        if remaining.is_empty() {
+            return None;
         }
-    }
-}
-
-struct ChunkIterator<'a> {
-    length: usize,
-    data: Cursor<&'a [u8]>,
-}
-impl<'a> Iterator for ChunkIterator<'a> {
-    type Item = Chunk;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        
-        if (self.data.position() as usize) < self.length {
-            let len = read_mve!(self.data, u16)?;
-            let chunk_type = ChunkType::from_int(read_mve!(self.data, u16)? as i32)?;
-
-            let mut buf = vec![0u8; len as usize];
-            self.data.read_exact(&mut buf).ok()?;
-
-            let opcode_iter = OpcodeIterator{ length: len as usize, data: Cursor::new(&buf) };
 
-            Some(Chunk{ chunk_type, opcodes: opcode_iter.collect() })
-        } else {
-            None
+        let result = Opcode::read(&mut self.data);
+        if result.is_err() {
+            //stop after the first error, the rest of the chunk can't be trusted
+            self.data.set_position(self.data.get_ref().len() as u64);
         }
+
+        Some(result)
     }
 }
 
+/// Opcodes borrow their data from the chunk they were read from
 #[derive(Debug)]
-enum Opcode {
+pub enum Opcode<'a> {
     EndOfStream,
     EndOfChunk,
-    CreateTimer { rate: u32, subdivision: u16 },
+    CreateTimer(Timer),
     InitAudioBuffers(InitAudioBuffers),
     StartStopAudio,
     InitVideoBuffers(InitVideoBuffers),
     SendBufferToDisplay(SendBufferToDisplay),
-    AudioFrame(AudioFrame),
+    AudioFrame(AudioFrame<'a>),
     InitVideoMode{ width: u16, height: u16, flags: u16 },
     CreateGradient,
     SetPalette{ palette_start: u16, palette_count: u16, data: Vec<Color> },
-    SetPaletteCompressed(Vec<u8>),
-    SetDecodingMap(Vec<u8>),
-    VideoData(Vec<u8>),
+    SetPaletteCompressed(&'a [u8]),
+    SetDecodingMap(&'a [u8]),
+    VideoData(&'a [u8]),
     Unknown(u8), //store type value for ease of debugging
 }
-impl Opcode {
-    fn read_opcodes(data: &[u8]) -> Option<Vec<Opcode>> {
-        let length = data.len();
-        let mut data = Cursor::new(data);
+impl<'a> Opcode<'a> {
+    fn read(data: &mut Cursor<&'a [u8]>) -> Result<Self, Error> {
+        let remaining = get_remaining(data);
+        if remaining.len() < 4 {
+            return Err(TruncatedOpcode { opcode: None });
+        }
 
-        let mut opcodes = vec![];
-        while (data.position() as usize) < length {
-            let len = read_mve!(data, u32)?;
-            let type_ = read_mve!(data, u8)?;
-            let ver = read_mve!(data, u8)?;
+        let len = u16::from_le_bytes([remaining[0], remaining[1]]) as usize;
+        let type_ = remaining[2];
+        let ver = remaining[3];
 
-            let mut op_data = vec![0u8; len as usize];
-            data.read_exact(&mut op_data).ok()?;
+        let op_data = remaining.get(4 .. 4 + len)
+            .ok_or(TruncatedOpcode { opcode: Some(type_) })?;
+        data.set_position(data.position() + 4 + len as u64);
 
-            opcodes.push(Self::from_data(type_, ver, &op_data)?);
-        }
+        Self::from_data(type_, ver, op_data)
+    }
 
-        Some(opcodes)
+    fn from_data(type_: u8, ver: u8, data: &'a [u8]) -> Result<Self, Error> {
+        let truncated = TruncatedOpcode { opcode: Some(type_) };
+        Self::parse_data(type_, ver, data)
+            .unwrap_or(Err(truncated))
     }
 
-    fn from_data(type_: u8, ver: u8, data: &[u8]) -> Option<Self> {
+    //returns None if the opcode data is too short
+    fn parse_data(type_: u8, ver: u8, data: &'a [u8]) -> Option<Result<Self, Error>> {
+        let unknown_version = Err(UnknownOpcodeVersion { opcode: type_, version: ver });
         let mut data = Cursor::new(data);
 
-        match type_ {
+        let opcode = match type_ {
             0x00 => Some(Self::EndOfStream),
             0x01 => Some(Self::EndOfChunk),
-            0x02 => Some(Self::CreateTimer { 
-                rate: read_type(&mut data).ok()?,
-                subdivision: read_type(&mut data).ok()?,
-            }),
+            0x02 => Some(Self::CreateTimer(Timer {
+                rate: data.read_u32_le().ok()?,
+                subdivision: data.read_u16_le().ok()?,
+            })),
 
+            0x03 if ver > 1 => return Some(unknown_version),
             0x03 => Self::read_init_audio_buffers(&mut data, ver),
             0x04 => Some(Self::StartStopAudio),
+            0x05 if ver > 2 => return Some(unknown_version),
             0x05 => Self::read_init_video_buffers(&mut data, ver),
+            0x07 if ver > 1 => return Some(unknown_version),
             0x07 => {
-                let palette_start = read_mve!(data, u16)?;
-                let palette_count = read_mve!(data, u16)?;
+                let palette_start = data.read_u16_le().ok()?;
+                let palette_count = data.read_u16_le().ok()?;
 
                 let version = if ver == 0 {
                     SendBufferToDisplay::V0 { palette_start, palette_count }
                 } else {
-                    SendBufferToDisplay::V1 { palette_start, palette_count, unknown1: read_mve!(data, u16)? }
+                    SendBufferToDisplay::V1 { palette_start, palette_count, unknown1: data.read_u16_le().ok()? }
                 };
 
                 Some(Self::SendBufferToDisplay(version))
             }
 
             t @ 0x08 ..= 0x09 => {
-                let seq_index = read_mve!(data, u16)?;
-                let stream_mask = read_mve!(data, u16)?;
-                let stream_len = read_mve!(data, u16)?;
+                let seq_index = data.read_u16_le().ok()?;
+                let stream_mask = data.read_u16_le().ok()?;
+                let stream_len = data.read_u16_le().ok()?;
                 
                 let version = if t == 0x08 {
-                    let remaining: &[i8] = 
-                        unsafe { std::mem::transmute(get_remaining(&data)) };
-                    let data = Vec::from_iter(remaining.iter().copied());
+                    let data = get_remaining(&data);
                     AudioFrame::Data { seq_index, stream_mask, stream_len, data }
                 } else {
                     AudioFrame::Silence { seq_index, stream_mask, stream_len }
@@ -348,16 +305,16 @@ impl Opcode {
             },
 
             0x0A => Some(Self::InitVideoMode { 
-                width: read_mve!(data, u16)?,
-                height: read_mve!(data, u16)?,
-                flags: read_mve!(data, u16)?,
+                width: data.read_u16_le().ok()?,
+                height: data.read_u16_le().ok()?,
+                flags: data.read_u16_le().ok()?,
             }),
 
             0x0B => Some(Self::CreateGradient),
 
             0x0C => {
-                let palette_start = read_mve!(data, u16)?;
-                let palette_count = read_mve!(data, u16)?;
+                let palette_start = data.read_u16_le().ok()?;
+                let palette_count = data.read_u16_le().ok()?;
                 let data = 
                     get_remaining(&data).iter()
                         .tuples::<(_, _, _)>()
@@ -367,21 +324,23 @@ impl Opcode {
                 Some(Self::SetPalette { palette_start, palette_count, data })
             },
 
-            0x0D => Some(Self::SetPaletteCompressed( Vec::from(data.into_inner()) )),
+            0x0D => Some(Self::SetPaletteCompressed(data.into_inner())),
 
-            0x0F => Some(Self::SetDecodingMap( Vec::from(data.into_inner()) )),
+            0x0F => Some(Self::SetDecodingMap(data.into_inner())),
 
-            0x11 => Some(Self::VideoData( Vec::from(data.into_inner()) )),
+            0x11 => Some(Self::VideoData(data.into_inner())),
 
             t @ (0x06 | 0x0E | 0x010 | 0x12 | 0x14 | 0x15) => Some(Self::Unknown(t)),
-            _ => None,
-        }
+            t => return Some(Err(UnknownOpcode(t))),
+        };
+
+        opcode.map(Ok)
     }
 
-    fn read_init_audio_buffers(data: &mut Cursor<&[u8]>, ver: u8) -> Option<Opcode> {
-        let _unknown = read_mve!(data, u16)?;
-        let flags = read_mve!(data, u16)?;
-        let sample_rate = read_mve!(data, u16)?;
+    fn read_init_audio_buffers(data: &mut Cursor<&[u8]>, ver: u8) -> Option<Self> {
+        let _unknown = data.read_u16_le().ok()?;
+        let flags = data.read_u16_le().ok()?;
+        let sample_rate = data.read_u16_le().ok()?;
 
         let channels = 
             if (flags & 0b1) == 0 { AudioChannels::Mono }
@@ -392,7 +351,7 @@ impl Opcode {
             else { AudioChannelWidth::Bit16 };
 
         let version = if ver == 0 {
-            let min_buf_len = read_mve!(data, u16)?;
+            let min_buf_len = data.read_u16_le().ok()?;
             InitAudioBuffers::V0 { 
                 channels,
                 channel_width,
@@ -404,7 +363,7 @@ impl Opcode {
                 if (flags & 0b100) == 0 { AudioCompression::Uncompressed }
                 else { AudioCompression::Compressed };
 
-            let min_buf_len = read_mve!(data, u32)?;
+            let min_buf_len = data.read_u32_le().ok()?;
             InitAudioBuffers::V1 { 
                 channels,
                 channel_width,
@@ -417,18 +376,18 @@ impl Opcode {
         Some(Self::InitAudioBuffers(version))
     }
 
-    fn read_init_video_buffers(data: &mut Cursor<&[u8]>, ver: u8) -> Option<Opcode> {
-        let width = read_mve!(data, u16)?;
-        let height = read_mve!(data, u16)?;
+    fn read_init_video_buffers(data: &mut Cursor<&[u8]>, ver: u8) -> Option<Self> {
+        let width = data.read_u16_le().ok()?;
+        let height = data.read_u16_le().ok()?;
         let version = if ver == 0 {
             InitVideoBuffers::V0 { width, height }
         } else {
-            let count = read_mve!(data, u16)?;
+            let count = data.read_u16_le().ok()?;
 
             if ver == 1 {
                 InitVideoBuffers::V1 { width, height, count }
             } else {
-                let true_color = read_mve!(data, u16)?;
+                let true_color = data.read_u16_le().ok()?;
                 InitVideoBuffers::V2 { width, height, count, true_color }
             }
         };
@@ -440,8 +399,8 @@ impl Opcode {
 
 // End Opcodes
 
-#[derive(Debug)]
-enum ChunkType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkType {
     InitAudio,
     AudioOnly,
     InitVideo,
@@ -464,80 +423,84 @@ impl ChunkType {
     }
 }
 
+/// A chunk borrowed from the read buffer of an [`MveFile`]
 #[derive(Debug)]
-struct Chunk {
-    chunk_type: ChunkType,
-    opcodes: Vec<Opcode>,
+pub struct Chunk<'a> {
+    pub chunk_type: ChunkType,
+    pub data: &'a [u8],
+}
+impl<'a> Chunk<'a> {
+    pub fn opcodes(&self) -> OpcodeIterator<'a> {
+        OpcodeIterator { data: Cursor::new(self.data) }
+    }
 }
-impl Chunk {
-    fn from_data(data: &[u8]) -> Option<(Self, Vec<u8>)> {
-        if data.len() == 0 { return None; }
 
-        let mut data = Cursor::new(data);
+/// Streaming reader for Interplay MVE movies
+///
+/// Only the chunk currently being looked at is kept in memory, and the buffer
+/// it is read into is reused for the next chunk.
+pub struct MveFile<S: Stream> {
+    stream: S,
+    buffer: Vec<u8>,
+}
 
-        let len = read_mve!(data, u16)?;
-        let chunk_type = ChunkType::from_int(read_mve!(data, u16)? as i32)?;
+impl<S: Stream> MveFile<S> {
+    pub fn open(mut stream: S) -> Result<Self, Error> {
+        let mut file_type = [0u8; 20];
+        let mut magic_bytes = [0u16; 3];
 
-        // if len == 0 { return None; }
+        if read_up_to(&mut stream, &mut file_type)? < file_type.len() {
+            return Err(BadMagic);
+        }
+        for m in magic_bytes.iter_mut() {
+            *m = stream.read_u16_le().map_err(|_| BadMagic)?;
+        }
 
-        let data: &[u8] = get_remaining(&data);
-        // let data = data.into_inner();
-        if len as usize > data.len() { return None }
+        if &file_type != FILE_TYPE || magic_bytes != MAGIC_BYTES {
+            return Err(BadMagic);
+        }
 
-        let (chunk, rest) = data.split_at(len as usize);
+        Ok(Self { stream, buffer: Vec::new() })
+    }
 
-        let iter = OpcodeIterator{ length: chunk.len(), data: Cursor::new(chunk) };
-        let opcodes = iter.collect::<Vec<_>>();
+    /// Reads the next chunk, returns `None` at the end of the stream
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk<'_>>, Error> {
+        let offset = self.stream.stream_position().to()?;
 
-        Some((Self{
-            chunk_type,
-            opcodes,
-        }, Vec::from(rest)))
-    }
-}
+        let mut header = [0u8; 4];
+        match read_up_to(&mut self.stream, &mut header)? {
+            0 => return Ok(None),
+            4 => { },
+            _ => return Err(TruncatedChunk { offset }),
+        }
 
-fn read_chunk_rec(mut acc: Vec<Chunk>, data: &[u8]) -> Vec<Chunk> {
-    match Chunk::from_data(data) {
-        None => acc,
-        Some((c, d)) => read_chunk_rec({acc.push(c); acc}, &d ),
-    }
-}
+        let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+        let chunk_type = u16::from_le_bytes([header[2], header[3]]);
+        let chunk_type = ChunkType::from_int(chunk_type as i32)
+            .ok_or(UnknownChunkType(chunk_type))?;
 
-fn apply_deltas(mut delta: i16, values: &[i8]) -> (i16, Vec<i16>) {
-    let mut output = Vec::with_capacity(values.len());
+        self.buffer.resize(len, 0);
+        if read_up_to(&mut self.stream, &mut self.buffer)? < len {
+            return Err(TruncatedChunk { offset });
+        }
 
-    for v in values {
-        delta += *v as i16;
-        output.push(delta);
+        Ok(Some(Chunk { chunk_type, data: &self.buffer }))
     }
 
-    (delta, output)
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
 }
 
+/// Checks that every chunk and opcode in `data` can be parsed
 pub fn read_mve(data: &[u8]) -> Result<(), Error> {
-    // let mut data = std::io::BufReader::new(data);
-    let mut data = Cursor::new(data);
-
-    let file_type = read_bytes!(data, 20).to()?;
-    let magic_bytes = (0..3)
-        .filter_map(|_| read_bytes!(data, 2).ok())
-        .map(|bytes| u16::from_le_bytes(bytes))
-        .collect::<Vec<_>>();
-
-    if file_type != "Interplay MVE File\x1a\0".as_bytes() ||
-        magic_bytes != [ 0x001a, 0x0100, 0x1133 ] {
-        return Err(FileError);
-    }
-
-    let rest = get_remaining(&data);
-    let chunks = ChunkIterator {
-        length: rest.len(),
-        data: Cursor::new(rest)
-    };
+    let mut mve = MveFile::open(Cursor::new(data))?;
 
-    chunks
-    .map(|x| format!("{:?}", x.chunk_type))
-    .for_each(|x| println!("{}", x));
+    while let Some(chunk) = mve.next_chunk()? {
+        for op in chunk.opcodes() {
+            op?;
+        }
+    }
 
-    todo!()
+    Ok(())
 }
@@ -0,0 +1,121 @@
+use crate::{
+    Error,
+    FromIoError,
+    Timer,
+};
+
+use common::Stream;
+use std::time::Duration;
+
+/// A single line from a `.sve` file
+///
+/// * `frame` - first movie frame the line is shown on
+/// * `text` - line text, shown until the next subtitle's frame is reached
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Subtitle {
+    pub frame: u32,
+    pub text: String,
+}
+
+/// Movie subtitles, as stored in `text/<language>/cuts/<movie>.sve`
+///
+/// Each line of the file is `frame:text`. Lines without a `:` are skipped,
+/// and the frame number is parsed leniently like the original engine does
+/// (leading digits only, anything else reads as 0).
+#[derive(Debug, Default, Clone)]
+pub struct Subtitles {
+    lines: Vec<Subtitle>,
+
+    //frame each line actually appears on, see `from_bytes`
+    starts: Vec<u32>,
+}
+
+impl Subtitles {
+    pub fn open(stream: &mut dyn Stream) -> Result<Self, Error> {
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).to()?;
+
+        Ok(Self::from_bytes(&data))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let lines: Vec<_> = data
+            .split(|b| *b == b'\n')
+            .filter_map(parse_line)
+            .collect();
+
+        //the engine walks the list in file order and moves on by at most one line
+        //per frame once its frame is reached, so a line shows at least one frame
+        //after the one above it
+        let starts = lines.iter()
+            .scan(None, |last: &mut Option<u32>, s| {
+                let start = last.map_or(s.frame, |last| s.frame.max(last.saturating_add(1)));
+                *last = Some(start);
+                Some(start)
+            })
+            .collect();
+
+        Self { lines, starts }
+    }
+
+    /// Path of the subtitle file for `movie` inside a DAT archive
+    ///
+    /// e.g. `path("english", "intro.mve")` gives `text\english\cuts\intro.sve`
+    pub fn path(language: &str, movie: &str) -> String {
+        let name = movie
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(movie);
+        let stem = name
+            .rsplit_once('.')
+            .map(|(stem, _)| stem)
+            .unwrap_or(name);
+
+        format!("text\\{}\\cuts\\{}.sve", language, stem).to_ascii_lowercase()
+    }
+
+    /// Subtitle on screen at `frame`, frames are counted from 0
+    ///
+    /// A line listed after one with a later frame shows for a single frame
+    /// before moving on, like in the original engine.
+    pub fn get(&self, frame: u32) -> Option<&Subtitle> {
+        let index = self.starts.partition_point(|start| *start <= frame);
+        index.checked_sub(1).map(|i| &self.lines[i])
+    }
+
+    /// Subtitle on screen after `elapsed` playback time of a movie using `timer`
+    pub fn at_time(&self, timer: &Timer, elapsed: Duration) -> Option<&Subtitle> {
+        self.get(timer.frame_at(elapsed))
+    }
+
+    pub fn lines(&self) -> &[Subtitle] {
+        &self.lines
+    }
+}
+
+fn parse_line(line: &[u8]) -> Option<Subtitle> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let split = line.iter().position(|b| *b == b':')?;
+    let (frame, text) = (&line[..split], &line[split + 1..]);
+
+    Some(Subtitle {
+        frame: parse_frame(frame),
+        text: decode_text(text),
+    })
+}
+
+//behaves like atoi
+fn parse_frame(bytes: &[u8]) -> u32 {
+    bytes.iter()
+        .skip_while(|b| b.is_ascii_whitespace())
+        .take_while(|b| b.is_ascii_digit())
+        .fold(0u32, |acc, b| acc.saturating_mul(10).saturating_add((b - b'0') as u32))
+}
+
+//localised files are usually in a single byte code page, fall back to latin-1
+fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|b| *b as char).collect(),
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::Cursor;
+use crate::*;
+use super::chunks::{ chunk, header, opcode };
+
+fn audio_frame(type_: u8, stream_mask: u16, stream_len: u16, data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&0u16.to_le_bytes());
+    body.extend_from_slice(&stream_mask.to_le_bytes());
+    body.extend_from_slice(&stream_len.to_le_bytes());
+    body.extend_from_slice(data);
+    opcode(type_, 0, &body)
+}
+
+fn make_mve(flags: u16, frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut init = 0u16.to_le_bytes().to_vec();
+    init.extend_from_slice(&flags.to_le_bytes());
+    init.extend_from_slice(&22050u16.to_le_bytes());
+    init.extend_from_slice(&0u32.to_le_bytes());
+
+    [
+        header(),
+        chunk(0, &[ opcode(0x03, 1, &init), opcode(0x01, 0, &[]) ]),
+        chunk(1, frames),
+        chunk(5, &[ opcode(0x00, 0, &[]) ]),
+    ].concat()
+}
+
+fn read_audio(data: Vec<u8>, track: u8) -> Option<common::Wav> {
+    let mut mve = MveFile::open(Cursor::new(data)).unwrap();
+    mve.read_audio(track).unwrap()
+}
+
+#[test]
+fn compressed_test() {
+    //stereo, 16 bit, compressed
+    let data = make_mve(0b111, &[
+        audio_frame(0x08, 1, 12, &[
+            0x00, 0x70, 0xF0, 0xFF,
+            1, 255, 130, 137,
+        ]),
+        //other languages are skipped
+        audio_frame(0x08, 2, 8, &[0, 0, 0, 0, 5, 5]),
+        audio_frame(0x09, 1, 4, &[]),
+    ]);
+
+    let wav = read_audio(data.clone(), 0).unwrap();
+    assert_eq!(wav.channels, 2);
+    assert_eq!(wav.sample_rate, 22050);
+    assert_eq!(wav.block_align(), 4);
+    //the left channel clamps at the top of the range
+    assert_eq!(wav.samples, [0x7000, -16, 0x7001, -17, 32767, -17 - 32589, 0, 0]);
+
+    let wav = read_audio(data, 1).unwrap();
+    assert_eq!(wav.samples, [0, 0, 5, 5]);
+}
+
+#[test]
+fn uncompressed_test() {
+    //mono, 8 bit
+    let data = make_mve(0b000, &[
+        audio_frame(0x08, 1, 3, &[0x80, 0x00, 0xFF]),
+        audio_frame(0x09, 1, 2, &[]),
+    ]);
+    let wav = read_audio(data, 0).unwrap();
+    assert_eq!(wav.channels, 1);
+    assert_eq!(wav.samples, [0, -32768, 32512, 0, 0]);
+
+    //mono, 16 bit
+    let data = make_mve(0b010, &[
+        audio_frame(0x08, 1, 4, &[0x01, 0x00, 0x00, 0x80]),
+    ]);
+    let wav = read_audio(data, 0).unwrap();
+    assert_eq!(wav.samples, [1, -32768]);
+
+    //silence is capped at a second of whole sample frames
+    let data = make_mve(0b000, &[audio_frame(0x09, 1, u16::MAX, &[])]);
+    assert_eq!(read_audio(data, 0).unwrap().samples.len(), 22050);
+
+    let data = make_mve(0b011, &[audio_frame(0x09, 1, 6, &[])]);
+    assert_eq!(read_audio(data, 0).unwrap().samples, [0, 0]);
+
+    //no audio at all
+    assert!(read_audio(super::chunks::make_mve(), 0).is_none());
+}
@@ -0,0 +1,110 @@
+use std::io::Cursor;
+use crate::*;
+
+pub fn opcode(type_: u8, ver: u8, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    out.push(type_);
+    out.push(ver);
+    out.extend_from_slice(data);
+    out
+}
+
+pub fn chunk(chunk_type: u16, opcodes: &[Vec<u8>]) -> Vec<u8> {
+    let body = opcodes.concat();
+    let mut out = Vec::new();
+    out.extend_from_slice(&(body.len() as u16).to_le_bytes());
+    out.extend_from_slice(&chunk_type.to_le_bytes());
+    out.extend_from_slice(&body);
+    out
+}
+
+pub fn header() -> Vec<u8> {
+    let mut out = b"Interplay MVE File\x1a\0".to_vec();
+    for m in [0x001a_u16, 0x0100, 0x1133] {
+        out.extend_from_slice(&m.to_le_bytes());
+    }
+    out
+}
+
+pub fn make_mve() -> Vec<u8> {
+    let mut timer = 66_666u32.to_le_bytes().to_vec();
+    timer.extend_from_slice(&1u16.to_le_bytes());
+
+    [
+        header(),
+        chunk(2, &[ opcode(0x02, 0, &timer), opcode(0x01, 0, &[]) ]),
+        chunk(1, &[ opcode(0x08, 0, &[0, 0, 1, 0, 4, 0, 0xFF, 0x80, 1, 2]), opcode(0x01, 0, &[]) ]),
+        chunk(5, &[ opcode(0x00, 0, &[]) ]),
+    ].concat()
+}
+
+#[test]
+fn chunk_reader_test() {
+    let mut mve = MveFile::open(Cursor::new(make_mve())).unwrap();
+
+    let c = mve.next_chunk().unwrap().unwrap();
+    assert_eq!(c.chunk_type, ChunkType::InitVideo);
+    let ops: Vec<_> = c.opcodes().collect::<Result<_, _>>().unwrap();
+    assert!(matches!(ops[0], Opcode::CreateTimer(Timer { rate: 66_666, subdivision: 1 })));
+    assert!(matches!(ops[1], Opcode::EndOfChunk));
+
+    let c = mve.next_chunk().unwrap().unwrap();
+    assert_eq!(c.chunk_type, ChunkType::AudioOnly);
+    match c.opcodes().next().unwrap().unwrap() {
+        Opcode::AudioFrame(frame) => {
+            let samples: Vec<_> = frame.signed_data().collect();
+            assert_eq!(samples, [-1, -128, 1, 2]);
+        },
+        op => panic!("expected audio frame, got {:?}", op),
+    }
+
+    let c = mve.next_chunk().unwrap().unwrap();
+    assert_eq!(c.chunk_type, ChunkType::EndChunk);
+
+    assert!(mve.next_chunk().unwrap().is_none());
+    read_mve(&make_mve()).unwrap();
+}
+
+#[test]
+fn bad_magic_test() {
+    let mut data = make_mve();
+    data[0] = b'X';
+    assert!(matches!(MveFile::open(Cursor::new(data)), Err(BadMagic)));
+
+    assert!(matches!(MveFile::open(Cursor::new(vec![0u8; 4])), Err(BadMagic)));
+}
+
+#[test]
+fn truncated_chunk_test() {
+    let mut data = make_mve();
+    data.truncate(data.len() - 2);
+
+    let mut mve = MveFile::open(Cursor::new(data)).unwrap();
+    mve.next_chunk().unwrap();
+    mve.next_chunk().unwrap();
+    assert!(matches!(mve.next_chunk(), Err(TruncatedChunk { .. })));
+
+    //half a chunk header
+    let data = [header(), vec![4, 0]].concat();
+    let mut mve = MveFile::open(Cursor::new(data)).unwrap();
+    assert!(matches!(mve.next_chunk(), Err(TruncatedChunk { offset: 26 })));
+}
+
+#[test]
+fn opcode_errors_test() {
+    let data = [ header(), chunk(2, &[ opcode(0x05, 3, &[0; 8]) ]) ].concat();
+    assert!(matches!(read_mve(&data), Err(UnknownOpcodeVersion { opcode: 0x05, version: 3 })));
+
+    let data = [ header(), chunk(2, &[ opcode(0x42, 0, &[]) ]) ].concat();
+    assert!(matches!(read_mve(&data), Err(UnknownOpcode(0x42))));
+
+    //opcode claims more data than the chunk holds
+    let mut op = opcode(0x11, 0, &[1, 2, 3]);
+    op[0] = 10;
+    let data = [ header(), chunk(3, &[ op ]) ].concat();
+    assert!(matches!(read_mve(&data), Err(TruncatedOpcode { opcode: Some(0x11) })));
+
+    let data = [ header(), chunk(9, &[]) ].concat();
+    assert!(matches!(read_mve(&data), Err(UnknownChunkType(9))));
+}
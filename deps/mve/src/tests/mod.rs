@@ -4,7 +4,11 @@ use crate::*;
 
 use crate::IntoDeltaIterator;
 
-const TEST_DATA: &'static [u8] =
+mod audio;
+mod chunks;
+mod subtitles;
+
+const TEST_DATA: &[u8] =
     include_bytes!("../../../../reference/f2/master/art/cuts/afailed.mve");
 
 #[test]
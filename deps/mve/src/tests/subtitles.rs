@@ -0,0 +1,76 @@
+use std::time::Duration;
+use crate::{
+    Subtitles,
+    Timer,
+};
+
+const SVE_DATA: &[u8] = b"0:\r\n\
+120:War. War never changes.\r\n\
+not a subtitle\r\n\
+250:The end of the world occurred pretty much as we had predicted.\r\n\
+ 400 : Too many humans: not enough room.\r\n";
+
+#[test]
+fn parse_test() {
+    let subs = Subtitles::from_bytes(SVE_DATA);
+    let lines = subs.lines();
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0].frame, 0);
+    assert_eq!(lines[0].text, "");
+    assert_eq!(lines[1].frame, 120);
+    assert_eq!(lines[1].text, "War. War never changes.");
+    assert_eq!(lines[3].frame, 400);
+    assert_eq!(lines[3].text, " Too many humans: not enough room.");
+}
+
+#[test]
+fn frame_lookup_test() {
+    let subs = Subtitles::from_bytes(SVE_DATA);
+    let text = |frame| subs.get(frame).map(|s| s.text.as_str());
+
+    assert_eq!(text(0), Some(""));
+    assert_eq!(text(119), Some(""));
+    assert_eq!(text(120), Some("War. War never changes."));
+    assert_eq!(text(249), Some("War. War never changes."));
+    assert_eq!(text(250), Some("The end of the world occurred pretty much as we had predicted."));
+    assert_eq!(text(100_000), Some(" Too many humans: not enough room."));
+
+    let late_start = Subtitles::from_bytes(b"10:first\n");
+    assert_eq!(late_start.get(9), None);
+}
+
+#[test]
+fn out_of_order_test() {
+    //a line can't show before the lines above it have been shown, each of them for a frame
+    let subs = Subtitles::from_bytes(b"10:a\n5:b\n7:c\n20:d\n20:e\n");
+    let text = |frame| subs.get(frame).map(|s| s.text.as_str());
+
+    assert_eq!(text(5), None);
+    assert_eq!(text(10), Some("a"));
+    assert_eq!(text(11), Some("b"));
+    assert_eq!(text(12), Some("c"));
+    assert_eq!(text(19), Some("c"));
+    assert_eq!(text(20), Some("d"));
+    assert_eq!(text(21), Some("e"));
+}
+
+#[test]
+fn timer_test() {
+    //15 fps
+    let timer = Timer { rate: 66_666, subdivision: 1 };
+    let subs = Subtitles::from_bytes(SVE_DATA);
+
+    assert_eq!(timer.frame_at(Duration::from_secs(8)), 120);
+    assert_eq!(
+        subs.at_time(&timer, Duration::from_secs(9)).map(|s| s.frame),
+        Some(120)
+    );
+    assert_eq!(Timer::default().frame_at(Duration::from_secs(1)), 0);
+}
+
+#[test]
+fn path_test() {
+    assert_eq!(Subtitles::path("english", "art/cuts/INTRO.MVE"), "text\\english\\cuts\\intro.sve");
+    assert_eq!(Subtitles::path("german", "vexpld"), "text\\german\\cuts\\vexpld.sve");
+}
@@ -1,7 +1,7 @@
 pub mod color;
 pub use color::Color;
 
-use common::Stream;
+use common::{ BinaryReader, BinaryReadError, Stream };
 
 use std::{
     mem::size_of
@@ -17,7 +17,7 @@ pub struct PalFile {
 }
 
 impl PalFile {
-    pub fn open(file: &mut dyn Stream) -> Option<Self> {
+    pub fn open(file: &mut dyn Stream) -> Result<Self, BinaryReadError> {
         let mut colors = [Color::default(); COLOR_COUNT];
         let mut conversion_table = [0u8; CONVERSION_TABLE_COUNT];
 
@@ -25,8 +25,7 @@ impl PalFile {
         let conversion_table_size = size_of::<u8>() * CONVERSION_TABLE_COUNT;
         let palette_size = colors_size + conversion_table_size;
 
-        let mut read_buf = vec![0u8; palette_size];
-        file.read(&mut read_buf).ok()?;
+        let read_buf = file.read_vec(palette_size)?;
 
         let mut i = 0;
         let mut color_index = 0;
@@ -34,7 +33,7 @@ impl PalFile {
         while i < palette_size {
             if i < COLOR_COUNT * 3 {
                 let mut color_values = [
-                    read_buf[i+0],
+                    read_buf[i],
                     read_buf[i+1],
                     read_buf[i+2],
                 ];
@@ -56,7 +55,7 @@ impl PalFile {
             i += 1;
         }
 
-        Some(Self{
+        Ok(Self{
             colors,
             conversion_table,
         })
@@ -0,0 +1,98 @@
+use crate::error::ProError;
+
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReader,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+};
+use serde::{ Deserialize, Serialize };
+use std::io::{ Seek, Write };
+
+pub const STAT_COUNT: usize = 35;
+pub const SKILL_COUNT: usize = 18;
+
+/// Critter prototype data following the common header
+///
+/// * `script_id` - -1 for critters without a script
+/// * `head_fid` - talking head, -1 for critters without one
+/// * `base_stats` / `bonus_stats` - indexed by stat, S.P.E.C.I.A.L. first
+/// * `kill_type` - which kill counter the critter's death adds to
+/// * `damage_type` - unarmed damage type, only stored by Fallout 2
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Critter {
+    pub flags_ext: u32,
+    pub script_id: i32,
+    pub head_fid: i32,
+    pub ai_packet: i32,
+    pub team: i32,
+    #[serde(with = "crate::text::critter_flags")]
+    pub critter_flags: u32,
+
+    #[serde(with = "crate::text::array")]
+    pub base_stats: [i32; STAT_COUNT],
+    #[serde(with = "crate::text::array")]
+    pub bonus_stats: [i32; STAT_COUNT],
+    pub skills: [i32; SKILL_COUNT],
+
+    pub body_type: i32,
+    pub experience: i32,
+    pub kill_type: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub damage_type: Option<i32>,
+}
+
+impl Critter {
+    pub(crate) fn read(stream: &mut dyn Stream) -> Result<Self, ProError> {
+        let mode = ReadMode::BE;
+
+        Ok(Self {
+            flags_ext: stream.read_u32_be()?,
+            script_id: stream.read_i32_be()?,
+            head_fid: stream.read_i32_be()?,
+            ai_packet: stream.read_i32_be()?,
+            team: stream.read_i32_be()?,
+            critter_flags: stream.read_u32_be()?,
+
+            base_stats: BinRead::bin_read(stream, mode)?,
+            bonus_stats: BinRead::bin_read(stream, mode)?,
+            skills: BinRead::bin_read(stream, mode)?,
+
+            body_type: stream.read_i32_be()?,
+            experience: stream.read_i32_be()?,
+            kill_type: stream.read_i32_be()?,
+            damage_type: match stream.read_i32_be() {
+                Ok(damage_type) => Some(damage_type),
+                Err(e) if e.is_eof() => None,
+                Err(e) => return Err(e.into()),
+            },
+        })
+    }
+
+    pub(crate) fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let mode = ReadMode::BE;
+
+        output.write_u32_be(self.flags_ext)?;
+        output.write_i32_be(self.script_id)?;
+        output.write_i32_be(self.head_fid)?;
+        output.write_i32_be(self.ai_packet)?;
+        output.write_i32_be(self.team)?;
+        output.write_u32_be(self.critter_flags)?;
+
+        self.base_stats.bin_write(output, mode)?;
+        self.bonus_stats.bin_write(output, mode)?;
+        self.skills.bin_write(output, mode)?;
+
+        output.write_i32_be(self.body_type)?;
+        output.write_i32_be(self.experience)?;
+        output.write_i32_be(self.kill_type)?;
+        if let Some(damage_type) = self.damage_type {
+            output.write_i32_be(damage_type)?;
+        }
+
+        Ok(())
+    }
+}
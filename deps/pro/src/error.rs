@@ -0,0 +1,51 @@
+use common::BinaryReadError;
+use msg::MsgError;
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+};
+
+#[derive(Debug)]
+pub enum ProError {
+    ReadError(BinaryReadError),
+    /// The type byte of the PID isn't one of the six prototype types
+    UnknownType(u32),
+    /// Item or scenery subtype that doesn't exist
+    UnknownSubtype(u32),
+    /// No file with this path in the archive
+    NotFound(String),
+    /// The archive entry couldn't be unpacked
+    UnpackError(Box<dyn Error>),
+    MsgError(MsgError),
+    /// The JSON or TOML form of a proto couldn't be converted
+    TextError(String),
+}
+
+impl Display for ProError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use ProError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading proto: {}", e),
+            UnknownType(t) => write!(f, "Unknown proto type {}", t),
+            UnknownSubtype(t) => write!(f, "Unknown proto subtype {}", t),
+            NotFound(path) => write!(f, "Proto file {} not found", path),
+            UnpackError(e) => write!(f, "Error unpacking proto file: {}", e),
+            MsgError(e) => write!(f, "Error reading proto names: {}", e),
+            TextError(e) => write!(f, "Error converting proto text: {}", e),
+        }
+    }
+}
+
+impl Error for ProError {}
+
+impl From<BinaryReadError> for ProError {
+    fn from(e: BinaryReadError) -> Self {
+        ProError::ReadError(e)
+    }
+}
+
+impl From<MsgError> for ProError {
+    fn from(e: MsgError) -> Self {
+        ProError::MsgError(e)
+    }
+}
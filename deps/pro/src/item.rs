@@ -0,0 +1,218 @@
+use crate::error::ProError;
+
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReader,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+};
+use serde::{ Deserialize, Serialize };
+use std::io::{ Seek, Write };
+
+/// Damage types in the order armor and critter resistances list them
+pub const DAMAGE_TYPES: [&str; 7] = ["normal", "laser", "fire", "plasma", "electrical", "emp", "explosion"];
+
+/// Item prototype data following the common header
+///
+/// * `flags_ext` - action flags, the low byte holds the weapon attack modes
+/// * `script_id` - -1 for items without a script
+/// * `size` - volume it takes up in a container
+/// * `inventory_fid` - art shown in the inventory, -1 to use the ground art
+/// * `sound_id` - letter of the item's sound effects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    #[serde(with = "crate::text::action_flags")]
+    pub flags_ext: u32,
+    pub script_id: i32,
+    pub material: u32,
+    pub size: i32,
+    pub weight: i32,
+    pub cost: i32,
+    pub inventory_fid: i32,
+    #[serde(with = "crate::text::letter")]
+    pub sound_id: u8,
+
+    pub kind: ItemKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Armor(Armor),
+    Container(Container),
+    Drug(Drug),
+    Weapon(Weapon),
+    Ammo(Ammo),
+    Misc(MiscItem),
+    Key(Key),
+}
+
+impl ItemKind {
+    /// Number stored in the proto for this subtype
+    pub fn subtype(&self) -> u32 {
+        match self {
+            Self::Armor(_) => 0,
+            Self::Container(_) => 1,
+            Self::Drug(_) => 2,
+            Self::Weapon(_) => 3,
+            Self::Ammo(_) => 4,
+            Self::Misc(_) => 5,
+            Self::Key(_) => 6,
+        }
+    }
+
+    fn read(stream: &mut dyn Stream, subtype: u32) -> Result<Self, ProError> {
+        let mode = ReadMode::BE;
+        Ok(match subtype {
+            0 => Self::Armor(Armor::bin_read(stream, mode)?),
+            1 => Self::Container(Container::bin_read(stream, mode)?),
+            2 => Self::Drug(Drug::bin_read(stream, mode)?),
+            3 => Self::Weapon(Weapon::bin_read(stream, mode)?),
+            4 => Self::Ammo(Ammo::bin_read(stream, mode)?),
+            5 => Self::Misc(MiscItem::bin_read(stream, mode)?),
+            6 => Self::Key(Key::bin_read(stream, mode)?),
+            _ => return Err(ProError::UnknownSubtype(subtype)),
+        })
+    }
+
+    fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let mode = ReadMode::BE;
+        match self {
+            Self::Armor(armor) => armor.bin_write(output, mode),
+            Self::Container(container) => container.bin_write(output, mode),
+            Self::Drug(drug) => drug.bin_write(output, mode),
+            Self::Weapon(weapon) => weapon.bin_write(output, mode),
+            Self::Ammo(ammo) => ammo.bin_write(output, mode),
+            Self::Misc(misc) => misc.bin_write(output, mode),
+            Self::Key(key) => key.bin_write(output, mode),
+        }
+    }
+}
+
+impl Item {
+    pub(crate) fn read(stream: &mut dyn Stream) -> Result<Self, ProError> {
+        let flags_ext = stream.read_u32_be()?;
+        let script_id = stream.read_i32_be()?;
+        let subtype = stream.read_u32_be()?;
+
+        Ok(Self {
+            flags_ext,
+            script_id,
+            material: stream.read_u32_be()?,
+            size: stream.read_i32_be()?,
+            weight: stream.read_i32_be()?,
+            cost: stream.read_i32_be()?,
+            inventory_fid: stream.read_i32_be()?,
+            sound_id: stream.read_u8()?,
+            kind: ItemKind::read(stream, subtype)?,
+        })
+    }
+
+    pub(crate) fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        output.write_u32_be(self.flags_ext)?;
+        output.write_i32_be(self.script_id)?;
+        output.write_u32_be(self.kind.subtype())?;
+        output.write_u32_be(self.material)?;
+        output.write_i32_be(self.size)?;
+        output.write_i32_be(self.weight)?;
+        output.write_i32_be(self.cost)?;
+        output.write_i32_be(self.inventory_fid)?;
+        output.write_u8(self.sound_id)?;
+        self.kind.write(output)
+    }
+}
+
+/// * `damage_resist` - percentages, by `DAMAGE_TYPES`
+/// * `perk` - perk granted while worn, -1 for none
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Armor {
+    pub armor_class: i32,
+    pub damage_resist: [i32; 7],
+    pub damage_threshold: [i32; 7],
+    pub perk: i32,
+    pub male_fid: i32,
+    pub female_fid: i32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Container {
+    pub max_size: i32,
+    pub flags: u32,
+}
+
+/// Stat changes applied when the drug is taken, then after each delay
+///
+/// * `stats` - the three stats changed, -1 for unused slots
+/// * `amounts` - change to each stat now
+/// * `delayed` - game minutes until, and the changes made then
+/// * `addiction_effect` - perk given to addicts
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Drug {
+    pub stats: [i32; 3],
+    pub amounts: [i32; 3],
+    pub delay_1: i32,
+    pub amounts_1: [i32; 3],
+    pub delay_2: i32,
+    pub amounts_2: [i32; 3],
+    pub addiction_chance: i32,
+    pub addiction_effect: i32,
+    pub addiction_onset: i32,
+}
+
+/// * `anim_code` - critter art used to hold it, see `art::WeaponAnim`
+/// * `range` / `ap_cost` - for the primary and secondary attack
+/// * `projectile_pid` / `ammo_pid` - -1 for none
+/// * `rounds` - rounds fired in a burst
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Weapon {
+    pub anim_code: i32,
+    pub min_damage: i32,
+    pub max_damage: i32,
+    pub damage_type: i32,
+    pub range: [i32; 2],
+    pub projectile_pid: i32,
+    pub min_strength: i32,
+    pub ap_cost: [i32; 2],
+    pub critical_fail: i32,
+    pub perk: i32,
+    pub rounds: i32,
+    pub caliber: i32,
+    pub ammo_pid: i32,
+    pub max_ammo: i32,
+    #[serde(with = "crate::text::letter")]
+    pub sound_id: u8,
+}
+
+/// * `damage_mult` / `damage_div` - damage is scaled by `mult / div`
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Ammo {
+    pub caliber: i32,
+    pub quantity: i32,
+    pub armor_class_modifier: i32,
+    pub damage_resist_modifier: i32,
+    pub damage_mult: i32,
+    pub damage_div: i32,
+}
+
+/// Other items, some of which take charges such as the motion sensor
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct MiscItem {
+    pub power_pid: i32,
+    pub power_type: i32,
+    pub charges: i32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Key {
+    pub key_code: i32,
+}
@@ -0,0 +1,307 @@
+#[cfg(test)]
+mod tests;
+
+pub mod error;
+pub mod item;
+pub mod critter;
+pub mod scenery;
+pub mod text;
+
+pub use error::ProError;
+pub use item::{ Item, ItemKind };
+pub use critter::Critter;
+pub use scenery::{ Scenery, SceneryKind };
+
+use art::{ Fid, LstFile };
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReadError,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+};
+use dat::DatFile;
+use msg::MsgFile;
+use serde::{ Deserialize, Serialize };
+
+use std::io::{ Cursor, Seek, Write };
+
+/// Prototype types, numbered like the type byte of a PID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ProtoType {
+    Item = 0,
+    Critter = 1,
+    Scenery = 2,
+    Wall = 3,
+    Tile = 4,
+    Misc = 5,
+}
+
+impl ProtoType {
+    pub const ALL: [ProtoType; 6] = [
+        Self::Item,
+        Self::Critter,
+        Self::Scenery,
+        Self::Wall,
+        Self::Tile,
+        Self::Misc,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    /// Directory under `proto`, also the name of the type's `.lst`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Item => "items",
+            Self::Critter => "critters",
+            Self::Scenery => "scenery",
+            Self::Wall => "walls",
+            Self::Tile => "tiles",
+            Self::Misc => "misc",
+        }
+    }
+
+    /// e.g. `proto\items\items.lst`
+    pub fn lst_path(self) -> String {
+        format!("proto\\{0}\\{0}.lst", self.name())
+    }
+
+    /// Names and descriptions of this type's protos, e.g. `pro_item.msg`
+    pub fn msg_name(self) -> &'static str {
+        match self {
+            Self::Item => "pro_item.msg",
+            Self::Critter => "pro_crit.msg",
+            Self::Scenery => "pro_scen.msg",
+            Self::Wall => "pro_wall.msg",
+            Self::Tile => "pro_tile.msg",
+            Self::Misc => "pro_misc.msg",
+        }
+    }
+}
+
+/// Prototype id, the type in the high byte and the line of the type's `.lst`
+/// counted from 1 in the rest
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Pid(pub u32);
+
+impl Pid {
+    pub fn new(proto_type: ProtoType, id: u32) -> Self {
+        Self(((proto_type as u32) << 24) | (id & 0xFF_FFFF))
+    }
+
+    pub fn proto_type(self) -> Option<ProtoType> {
+        ProtoType::from_u8((self.0 >> 24) as u8)
+    }
+
+    pub fn id(self) -> u32 {
+        self.0 & 0xFF_FFFF
+    }
+}
+
+impl BinRead for Pid {
+    fn bin_read<S: Stream + ?Sized>(stream: &mut S, mode: ReadMode) -> Result<Self, BinaryReadError> {
+        u32::bin_read(stream, mode).map(Pid)
+    }
+}
+
+impl BinWrite for Pid {
+    fn bin_write<W: Write + Seek + ?Sized>(&self, output: &mut W, mode: ReadMode) -> Result<(), BinaryWriteError> {
+        self.0.bin_write(output, mode)
+    }
+}
+
+/// Fields every prototype starts with
+///
+/// * `text_id` - message of the name in the type's `pro_*.msg`, the description follows it
+/// * `light_intensity` - out of 0x10000
+/// * `flags` - object flags, e.g. flat, no block, multi hex
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Header {
+    pub pid: Pid,
+    pub text_id: i32,
+    #[serde(with = "text::fid")]
+    pub fid: Fid,
+    pub light_radius: i32,
+    pub light_intensity: i32,
+    #[serde(with = "text::object_flags")]
+    pub flags: u32,
+}
+
+/// * `script_id` - -1 for walls without a script
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Wall {
+    pub flags_ext: u32,
+    pub script_id: i32,
+    pub material: u32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Tile {
+    pub material: u32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Misc {
+    pub unknown: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtoData {
+    Item(Item),
+    Critter(Box<Critter>),
+    Scenery(Scenery),
+    Wall(Wall),
+    Tile(Tile),
+    Misc(Misc),
+}
+
+/// A `.pro` file, as stored in `proto/<type>/<number>.pro`
+///
+/// Every value is big endian. The common header is followed by the data of
+/// the PID's type, which for items and scenery depends on a subtype.
+///
+/// * `extra` - bytes after the known layout, kept so nothing is lost
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proto {
+    pub header: Header,
+    pub data: ProtoData,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<u8>,
+}
+
+impl Proto {
+    pub fn open(stream: &mut dyn Stream) -> Result<Self, ProError> {
+        let header = Header::bin_read(stream, ReadMode::BE)?;
+        let proto_type = header.pid.proto_type()
+            .ok_or(ProError::UnknownType(header.pid.0 >> 24))?;
+
+        let data = match proto_type {
+            ProtoType::Item => ProtoData::Item(Item::read(stream)?),
+            ProtoType::Critter => ProtoData::Critter(Box::new(Critter::read(stream)?)),
+            ProtoType::Scenery => ProtoData::Scenery(Scenery::read(stream)?),
+            ProtoType::Wall => ProtoData::Wall(Wall::bin_read(stream, ReadMode::BE)?),
+            ProtoType::Tile => ProtoData::Tile(Tile::bin_read(stream, ReadMode::BE)?),
+            ProtoType::Misc => ProtoData::Misc(Misc::bin_read(stream, ReadMode::BE)?),
+        };
+
+        let mut extra = Vec::new();
+        stream.read_to_end(&mut extra)
+            .map_err(BinaryReadError::from)?;
+
+        Ok(Self { header, data, extra })
+    }
+
+    /// Writes the proto back out, byte for byte the way `open` read it
+    pub fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        self.header.bin_write(output, ReadMode::BE)?;
+
+        match &self.data {
+            ProtoData::Item(item) => item.write(output)?,
+            ProtoData::Critter(critter) => critter.write(output)?,
+            ProtoData::Scenery(scenery) => scenery.write(output)?,
+            ProtoData::Wall(wall) => wall.bin_write(output, ReadMode::BE)?,
+            ProtoData::Tile(tile) => tile.bin_write(output, ReadMode::BE)?,
+            ProtoData::Misc(misc) => misc.bin_write(output, ReadMode::BE)?,
+        }
+
+        output.write_bytes(&self.extra)
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.header.pid
+    }
+
+    /// Name from the type's `pro_*.msg`
+    pub fn name<'a>(&self, names: &'a MsgFile) -> Option<&'a str> {
+        names.text(self.header.text_id)
+    }
+
+    pub fn description<'a>(&self, names: &'a MsgFile) -> Option<&'a str> {
+        names.text(self.header.text_id.checked_add(1)?)
+    }
+}
+
+/// Loads prototypes by PID from a DAT archive, along with their names
+///
+/// Each type's `.lst` lists its `.pro` files, the PID's id is the line counted from 1.
+pub struct ProtoLibrary<'a> {
+    dat: &'a DatFile,
+    lists: [LstFile; 6],
+    names: [MsgFile; 6],
+}
+
+impl<'a> ProtoLibrary<'a> {
+    /// * `language` - folder under `text` to take names from, e.g. `english`
+    pub fn new(dat: &'a DatFile, language: &str) -> Result<Self, ProError> {
+        let mut lists: [LstFile; 6] = Default::default();
+        let mut names: [MsgFile; 6] = Default::default();
+
+        for proto_type in ProtoType::ALL {
+            let lst = read_file(dat, &proto_type.lst_path())?;
+            lists[proto_type as usize] = LstFile::from_bytes(&lst);
+
+            let path = MsgFile::path(language, &format!("game\\{}", proto_type.msg_name()));
+            names[proto_type as usize] = match MsgFile::load(dat, &path) {
+                Ok(msg) => msg,
+                Err(msg::MsgError::NotFound(_)) => MsgFile::default(),
+                Err(e) => return Err(e.into()),
+            };
+        }
+
+        Ok(Self { dat, lists, names })
+    }
+
+    pub fn list(&self, proto_type: ProtoType) -> &LstFile {
+        &self.lists[proto_type as usize]
+    }
+
+    /// Path of the `.pro` for `pid`, e.g. `proto\items\00000004.pro`
+    pub fn path(&self, pid: Pid) -> Option<String> {
+        let proto_type = pid.proto_type()?;
+        let line = (pid.id() as usize).checked_sub(1)?;
+        let name = &self.list(proto_type).get(line)?.name;
+
+        Some(format!("proto\\{}\\{}", proto_type.name(), name).to_ascii_lowercase())
+    }
+
+    pub fn get(&self, pid: Pid) -> Result<Proto, ProError> {
+        let path = self.path(pid)
+            .ok_or_else(|| ProError::NotFound(format!("{:#010x}", pid.0)))?;
+        let data = read_file(self.dat, &path)?;
+
+        Proto::open(&mut Cursor::new(data))
+    }
+
+    /// Names of the type's protos
+    pub fn names(&self, proto_type: ProtoType) -> &MsgFile {
+        &self.names[proto_type as usize]
+    }
+
+    pub fn name(&self, proto: &Proto) -> Option<&str> {
+        proto.name(self.names(proto.pid().proto_type()?))
+    }
+
+    pub fn description(&self, proto: &Proto) -> Option<&str> {
+        proto.description(self.names(proto.pid().proto_type()?))
+    }
+}
+
+fn read_file(dat: &DatFile, path: &str) -> Result<Vec<u8>, ProError> {
+    let entry = dat.registry.get(path)
+        .and_then(|node| node.read().ok()?.get_file_entry().cloned())
+        .ok_or_else(|| ProError::NotFound(path.to_string()))?;
+
+    dat.unpack_file(&entry).map_err(ProError::UnpackError)
+}
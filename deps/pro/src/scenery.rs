@@ -0,0 +1,139 @@
+use crate::error::ProError;
+
+use common::{
+    BinRead,
+    BinWrite,
+    BinaryReader,
+    BinaryWriteError,
+    BinaryWriter,
+    Stream,
+    readers::ReadMode,
+};
+use serde::{ Deserialize, Serialize };
+use std::io::{ Seek, Write };
+
+/// Scenery prototype data following the common header
+///
+/// * `script_id` - -1 for scenery without a script
+/// * `sound_id` - letter of the scenery's sound effects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scenery {
+    #[serde(with = "crate::text::action_flags")]
+    pub flags_ext: u32,
+    pub script_id: i32,
+    pub material: u32,
+    #[serde(with = "crate::text::letter")]
+    pub sound_id: u8,
+
+    pub kind: SceneryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SceneryKind {
+    Door(Door),
+    Stairs(Stairs),
+    Elevator(Elevator),
+    LadderBottom(Ladder),
+    LadderTop(Ladder),
+    Generic(Generic),
+}
+
+impl SceneryKind {
+    /// Number stored in the proto for this subtype
+    pub fn subtype(&self) -> u32 {
+        match self {
+            Self::Door(_) => 0,
+            Self::Stairs(_) => 1,
+            Self::Elevator(_) => 2,
+            Self::LadderBottom(_) => 3,
+            Self::LadderTop(_) => 4,
+            Self::Generic(_) => 5,
+        }
+    }
+
+    fn read(stream: &mut dyn Stream, subtype: u32) -> Result<Self, ProError> {
+        let mode = ReadMode::BE;
+        Ok(match subtype {
+            0 => Self::Door(Door::bin_read(stream, mode)?),
+            1 => Self::Stairs(Stairs::bin_read(stream, mode)?),
+            2 => Self::Elevator(Elevator::bin_read(stream, mode)?),
+            3 => Self::LadderBottom(Ladder::bin_read(stream, mode)?),
+            4 => Self::LadderTop(Ladder::bin_read(stream, mode)?),
+            5 => Self::Generic(Generic::bin_read(stream, mode)?),
+            _ => return Err(ProError::UnknownSubtype(subtype)),
+        })
+    }
+
+    fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        let mode = ReadMode::BE;
+        match self {
+            Self::Door(door) => door.bin_write(output, mode),
+            Self::Stairs(stairs) => stairs.bin_write(output, mode),
+            Self::Elevator(elevator) => elevator.bin_write(output, mode),
+            Self::LadderBottom(ladder) | Self::LadderTop(ladder) => ladder.bin_write(output, mode),
+            Self::Generic(generic) => generic.bin_write(output, mode),
+        }
+    }
+}
+
+impl Scenery {
+    pub(crate) fn read(stream: &mut dyn Stream) -> Result<Self, ProError> {
+        let flags_ext = stream.read_u32_be()?;
+        let script_id = stream.read_i32_be()?;
+        let subtype = stream.read_u32_be()?;
+
+        Ok(Self {
+            flags_ext,
+            script_id,
+            material: stream.read_u32_be()?,
+            sound_id: stream.read_u8()?,
+            kind: SceneryKind::read(stream, subtype)?,
+        })
+    }
+
+    pub(crate) fn write(&self, output: &mut (impl Write + Seek)) -> Result<(), BinaryWriteError> {
+        output.write_u32_be(self.flags_ext)?;
+        output.write_i32_be(self.script_id)?;
+        output.write_u32_be(self.kind.subtype())?;
+        output.write_u32_be(self.material)?;
+        output.write_u8(self.sound_id)?;
+        self.kind.write(output)
+    }
+}
+
+/// * `walk_through` - non zero if critters can walk through the open door
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Door {
+    pub walk_through: u32,
+    pub unknown: u32,
+}
+
+/// * `destination` - tile and elevation packed together, `tile | elevation << 29`
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Stairs {
+    pub destination: u32,
+    pub destination_map: i32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Elevator {
+    pub elevator_type: i32,
+    pub level: i32,
+}
+
+/// * `destination` - tile and elevation packed like `Stairs::destination`
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Ladder {
+    pub destination: u32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bin(big)]
+pub struct Generic {
+    pub unknown: u32,
+}
@@ -0,0 +1,328 @@
+use std::io::Cursor;
+use art::{ ArtType, Fid };
+use common::BinaryWriter;
+use dat::Dat2Writer;
+use crate::{
+    *,
+    item::*,
+    scenery::*,
+};
+
+//common header, then `values` as big endian i32s
+fn make_proto(pid: Pid, text_id: i32, values: &[i32]) -> Cursor<Vec<u8>> {
+    let mut out = Cursor::new(Vec::new());
+    let fid = Fid::new(ArtType::Items, 7, 0, 0, 0);
+    for v in [pid.0 as i32, text_id, fid.0 as i32, 0, 0x10000, 0x8] {
+        out.write_i32_be(v).unwrap();
+    }
+    for v in values {
+        out.write_i32_be(*v).unwrap();
+    }
+
+    out
+}
+
+fn weapon_proto() -> Vec<u8> {
+    //flags, script, subtype, material, size, weight, cost, inventory fid
+    let mut out = make_proto(Pid::new(ProtoType::Item, 4), 400, &[0x1200, -1, 3, 1, 2, 3, 200, -1]);
+    out.write_u8(b'K').unwrap();
+    for v in [1, 1, 6, 0, 1, 1, -1, 2, 3, 3, 0, -1, 1, 0, -1, 0] {
+        out.write_i32_be(v).unwrap();
+    }
+    out.write_u8(b'A').unwrap();
+
+    out.into_inner()
+}
+
+#[test]
+fn item_test() {
+    let proto = Proto::open(&mut Cursor::new(weapon_proto())).unwrap();
+    assert_eq!(proto.pid().proto_type(), Some(ProtoType::Item));
+    assert_eq!(proto.pid().id(), 4);
+    assert_eq!(proto.header.fid.art_type(), Some(ArtType::Items));
+    assert_eq!(proto.header.flags, 0x8);
+    assert!(proto.extra.is_empty());
+
+    let ProtoData::Item(item) = &proto.data else { panic!("not an item") };
+    assert_eq!(item.script_id, -1);
+    assert_eq!(item.cost, 200);
+    assert_eq!(item.sound_id, b'K');
+
+    let ItemKind::Weapon(weapon) = &item.kind else { panic!("not a weapon") };
+    assert_eq!(weapon.max_damage, 6);
+    assert_eq!(weapon.range, [1, 1]);
+    assert_eq!(weapon.ap_cost, [3, 3]);
+    assert_eq!(weapon.ammo_pid, -1);
+    assert_eq!(weapon.sound_id, b'A');
+    assert_eq!(item.kind.subtype(), 3);
+
+    let mut out = make_proto(Pid::new(ProtoType::Item, 1), 100, &[0, -1, 0, 0, 0, 0, 0, 0]);
+    out.write_u8(0).unwrap();
+    let armor: Vec<i32> = (0 .. 18).collect();
+    for v in armor {
+        out.write_i32_be(v).unwrap();
+    }
+    out.set_position(0);
+
+    let proto = Proto::open(&mut out).unwrap();
+    let ProtoData::Item(Item { kind: ItemKind::Armor(armor), .. }) = proto.data else { panic!("not armor") };
+    assert_eq!(armor.armor_class, 0);
+    assert_eq!(armor.damage_resist[6], 7);
+    assert_eq!(armor.damage_threshold[0], 8);
+    assert_eq!(armor.female_fid, 17);
+}
+
+#[test]
+fn critter_test() {
+    let mut values = vec![0x20, 0x400_0001, 0x800_0011, 12, 1, 0x2000];
+    values.extend(0 .. 35 + 35 + 18);
+    values.extend([0, 60, 3]);
+
+    //fallout 1 protos end before the damage type
+    let mut f1 = make_proto(Pid::new(ProtoType::Critter, 2), 200, &values);
+    f1.set_position(0);
+    let mut f2 = make_proto(Pid::new(ProtoType::Critter, 2), 200, &values);
+    f2.write_i32_be(4).unwrap();
+    f2.set_position(0);
+
+    let ProtoData::Critter(critter) = Proto::open(&mut f1).unwrap().data else { panic!("not a critter") };
+    assert_eq!(critter.ai_packet, 12);
+    assert_eq!(critter.critter_flags, 0x2000);
+    assert_eq!(critter.base_stats[0], 0);
+    assert_eq!(critter.bonus_stats[0], 35);
+    assert_eq!(critter.skills[17], 87);
+    assert_eq!(critter.experience, 60);
+    assert_eq!(critter.damage_type, None);
+
+    let ProtoData::Critter(critter) = Proto::open(&mut f2).unwrap().data else { panic!("not a critter") };
+    assert_eq!(critter.damage_type, Some(4));
+}
+
+#[test]
+fn scenery_test() {
+    let scenery = |subtype, values: &[i32]| {
+        let mut out = make_proto(Pid::new(ProtoType::Scenery, 9), 900, &[0, -1, subtype, 2]);
+        out.write_u8(b'A').unwrap();
+        for v in values {
+            out.write_i32_be(*v).unwrap();
+        }
+        out.set_position(0);
+        Proto::open(&mut out).unwrap()
+    };
+
+    let proto = scenery(0, &[1, -0x3333_3334]);
+    let ProtoData::Scenery(door) = &proto.data else { panic!("not scenery") };
+    assert_eq!(door.material, 2);
+    assert_eq!(door.kind, SceneryKind::Door(Door { walk_through: 1, unknown: 0xCCCC_CCCC }));
+
+    let proto = scenery(1, &[0x2000_1234, 5]);
+    let ProtoData::Scenery(stairs) = &proto.data else { panic!("not scenery") };
+    assert_eq!(stairs.kind, SceneryKind::Stairs(Stairs { destination: 0x2000_1234, destination_map: 5 }));
+
+    //bytes past the known layout are kept
+    let proto = scenery(4, &[0x1234, 8]);
+    let ProtoData::Scenery(ladder) = &proto.data else { panic!("not scenery") };
+    assert_eq!(ladder.kind, SceneryKind::LadderTop(Ladder { destination: 0x1234 }));
+    assert_eq!(proto.extra, [0, 0, 0, 8]);
+}
+
+#[test]
+fn other_types_test() {
+    let mut wall = make_proto(Pid::new(ProtoType::Wall, 1), 0, &[0, -1, 3]);
+    wall.set_position(0);
+    assert_eq!(Proto::open(&mut wall).unwrap().data, ProtoData::Wall(Wall { flags_ext: 0, script_id: -1, material: 3 }));
+
+    let mut tile = make_proto(Pid::new(ProtoType::Tile, 1), 0, &[5]);
+    tile.set_position(0);
+    assert_eq!(Proto::open(&mut tile).unwrap().data, ProtoData::Tile(Tile { material: 5 }));
+
+    let mut misc = make_proto(Pid::new(ProtoType::Misc, 1), 0, &[0]);
+    misc.set_position(0);
+    assert_eq!(Proto::open(&mut misc).unwrap().data, ProtoData::Misc(Misc { unknown: 0 }));
+}
+
+#[test]
+fn error_test() {
+    let mut bad_type = make_proto(Pid(0x0700_0001), 0, &[0]);
+    bad_type.set_position(0);
+    assert!(matches!(Proto::open(&mut bad_type), Err(ProError::UnknownType(7))));
+
+    let mut bad_subtype = make_proto(Pid::new(ProtoType::Item, 1), 0, &[0, -1, 9, 0, 0, 0, 0, 0]);
+    bad_subtype.write_u8(0).unwrap();
+    bad_subtype.set_position(0);
+    assert!(matches!(Proto::open(&mut bad_subtype), Err(ProError::UnknownSubtype(9))));
+
+    let data = weapon_proto();
+    let truncated = Proto::open(&mut Cursor::new(&data[.. data.len() - 2]));
+    assert!(matches!(truncated, Err(ProError::ReadError(_))));
+}
+
+#[test]
+fn library_test() {
+    let mut files = vec![
+        ("proto\\items\\items.lst", b"00000001.pro\n00000002.pro\n00000003.pro\n00000004.pro\n".to_vec()),
+        ("proto\\items\\00000004.pro", weapon_proto()),
+        ("text\\english\\game\\pro_item.msg", b"{400}{}{Knife}\n{401}{}{A sharp blade.}\n".to_vec()),
+    ];
+    for proto_type in &ProtoType::ALL[1 ..] {
+        files.push((Box::leak(proto_type.lst_path().into_boxed_str()), Vec::new()));
+    }
+    let dat = Dat2Writer::build(&files).unwrap();
+
+    let library = ProtoLibrary::new(&dat, "english").unwrap();
+    let pid = Pid::new(ProtoType::Item, 4);
+    assert_eq!(library.path(pid).unwrap(), "proto\\items\\00000004.pro");
+
+    let knife = library.get(pid).unwrap();
+    assert_eq!(library.name(&knife), Some("Knife"));
+    assert_eq!(library.description(&knife), Some("A sharp blade."));
+
+    assert!(matches!(library.get(Pid::new(ProtoType::Item, 1)), Err(ProError::NotFound(_))));
+    assert!(matches!(library.get(Pid::new(ProtoType::Item, 0)), Err(ProError::NotFound(_))));
+    assert!(library.path(Pid::new(ProtoType::Critter, 1)).is_none());
+}
+
+//bytes to proto and back, then through both text forms
+fn round_trip(data: &[u8]) -> Proto {
+    let proto = Proto::open(&mut Cursor::new(data)).unwrap();
+
+    let mut out = Cursor::new(Vec::new());
+    proto.write(&mut out).unwrap();
+    assert_eq!(out.into_inner(), data);
+
+    let toml = proto.to_toml().unwrap();
+    assert_eq!(Proto::from_toml(&toml).unwrap(), proto, "{}", toml);
+    let json = proto.to_json().unwrap();
+    assert_eq!(Proto::from_json(&json).unwrap(), proto, "{}", json);
+
+    proto
+}
+
+#[test]
+fn item_round_trip_test() {
+    //field counts of armor, container, drug, weapon, ammo, misc and key
+    for (subtype, count) in [(0, 18), (1, 2), (2, 17), (4, 6), (5, 3), (6, 1)] {
+        let mut out = make_proto(Pid::new(ProtoType::Item, subtype + 1), 100, &[0x8800, -1, subtype as i32, 1, 2, 3, 4, -1]);
+        out.write_u8(b'Z').unwrap();
+        for v in 0 .. count {
+            out.write_i32_be(v * 3 - 1).unwrap();
+        }
+
+        let proto = round_trip(&out.into_inner());
+        let ProtoData::Item(item) = proto.data else { panic!("not an item") };
+        assert_eq!(item.kind.subtype(), subtype);
+    }
+
+    round_trip(&weapon_proto());
+}
+
+#[test]
+fn critter_round_trip_test() {
+    let mut values = vec![0x20, 0x400_0001, -1, 12, 1, 0x6002];
+    values.extend(0 .. 35 + 35 + 18);
+    values.extend([0, 60, 3]);
+
+    let f1 = make_proto(Pid::new(ProtoType::Critter, 2), 200, &values).into_inner();
+    assert!(!round_trip(&f1).to_toml().unwrap().contains("damage_type"));
+
+    values.push(4);
+    let f2 = make_proto(Pid::new(ProtoType::Critter, 2), 200, &values).into_inner();
+    round_trip(&f2);
+}
+
+#[test]
+fn scenery_round_trip_test() {
+    for (subtype, count) in [(0, 2), (1, 2), (2, 2), (3, 1), (4, 1), (5, 1)] {
+        let mut out = make_proto(Pid::new(ProtoType::Scenery, 1), 900, &[0x2000, -1, subtype as i32, 2]);
+        out.write_u8(b'A').unwrap();
+        for v in 0 .. count {
+            out.write_i32_be(v - 1).unwrap();
+        }
+
+        let proto = round_trip(&out.into_inner());
+        let ProtoData::Scenery(scenery) = proto.data else { panic!("not scenery") };
+        assert_eq!(scenery.kind.subtype(), subtype);
+    }
+
+    //trailing bytes are written back after the data
+    let mut out = make_proto(Pid::new(ProtoType::Scenery, 1), 900, &[0, -1, 4, 2]);
+    out.write_u8(b'A').unwrap();
+    out.write_bytes(&[0, 0, 0, 1, 0xCC, 0xCC]).unwrap();
+    assert_eq!(round_trip(&out.into_inner()).extra, [0xCC, 0xCC]);
+}
+
+#[test]
+fn other_types_round_trip_test() {
+    round_trip(&make_proto(Pid::new(ProtoType::Wall, 1), 0, &[0x800, -1, 3]).into_inner());
+    round_trip(&make_proto(Pid::new(ProtoType::Tile, 1), 0, &[5]).into_inner());
+    round_trip(&make_proto(Pid::new(ProtoType::Misc, 1), 0, &[0]).into_inner());
+}
+
+//one hand assembled proto per type and subtype, laid out like the game's
+const PROTOS: [(&str, &[u8]); 17] = [
+    ("armor", include_bytes!("./protos/armor.pro")),
+    ("container", include_bytes!("./protos/container.pro")),
+    ("drug", include_bytes!("./protos/drug.pro")),
+    ("weapon", include_bytes!("./protos/weapon.pro")),
+    ("ammo", include_bytes!("./protos/ammo.pro")),
+    ("misc_item", include_bytes!("./protos/misc_item.pro")),
+    ("key", include_bytes!("./protos/key.pro")),
+    ("critter", include_bytes!("./protos/critter.pro")),
+    ("door", include_bytes!("./protos/door.pro")),
+    ("stairs", include_bytes!("./protos/stairs.pro")),
+    ("elevator", include_bytes!("./protos/elevator.pro")),
+    ("ladder_bottom", include_bytes!("./protos/ladder_bottom.pro")),
+    ("ladder_top", include_bytes!("./protos/ladder_top.pro")),
+    ("generic", include_bytes!("./protos/generic.pro")),
+    ("wall", include_bytes!("./protos/wall.pro")),
+    ("tile", include_bytes!("./protos/tile.pro")),
+    ("misc", include_bytes!("./protos/misc.pro")),
+];
+
+#[test]
+fn fixture_round_trip_test() {
+    for (name, data) in PROTOS {
+        let proto = round_trip(data);
+        let kind = match &proto.data {
+            ProtoData::Item(item) => ["armor", "container", "drug", "weapon", "ammo", "misc_item", "key"][item.kind.subtype() as usize],
+            ProtoData::Scenery(scenery) => ["door", "stairs", "elevator", "ladder_bottom", "ladder_top", "generic"][scenery.kind.subtype() as usize],
+            ProtoData::Critter(_) => "critter",
+            ProtoData::Wall(_) => "wall",
+            ProtoData::Tile(_) => "tile",
+            ProtoData::Misc(_) => "misc",
+        };
+        assert_eq!(kind, name);
+    }
+
+    let ProtoData::Critter(critter) = round_trip(PROTOS[7].1).data else { panic!("not a critter") };
+    assert_eq!(critter.damage_type, Some(0));
+    assert_eq!(round_trip(PROTOS[12].1).extra, [0, 0, 0, 12]);
+}
+
+#[test]
+fn text_test() {
+    let proto = Proto::open(&mut Cursor::new(weapon_proto())).unwrap();
+    let toml = proto.to_toml().unwrap();
+    assert!(toml.contains(r#"flags = ["flat"]"#), "{}", toml);
+    assert!(toml.contains(r#"flags_ext = ["two_handed", "use_on"]"#), "{}", toml);
+    assert!(toml.contains(r#"sound_id = "K""#), "{}", toml);
+    assert!(toml.contains("[data.item.kind.weapon]"), "{}", toml);
+
+    //edited by hand, bits without a name are given in hex
+    let edited = toml
+        .replace(r#"flags = ["flat"]"#, r#"flags = ["flat", "no_block", "0x00000002"]"#)
+        .replace("cost = 200", "cost = 250");
+    let edited = Proto::from_toml(&edited).unwrap();
+    assert_eq!(edited.header.flags, 0x1A);
+    let ProtoData::Item(item) = &edited.data else { panic!("not an item") };
+    assert_eq!(item.cost, 250);
+
+    let bad_flag = toml.replace(r#"flags = ["flat"]"#, r#"flags = ["flatt"]"#);
+    assert!(matches!(Proto::from_toml(&bad_flag), Err(ProError::TextError(_))));
+
+    //the pid has to match the kind of data
+    let mut wrong_type = proto.clone();
+    wrong_type.header.pid = Pid::new(ProtoType::Critter, 4);
+    let json = wrong_type.to_json().unwrap();
+    assert!(matches!(Proto::from_json(&json), Err(ProError::TextError(_))));
+}
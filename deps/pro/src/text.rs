@@ -0,0 +1,204 @@
+//! JSON and TOML forms of a proto, for editing them as text
+//!
+//! Flags are written as lists of names, bits without a name as a hex string
+//! such as `"0x00000031"`, so converting back gives the same value.
+
+use crate::{ Proto, ProtoData, ProtoType, error::ProError };
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serializer,
+    de::Error,
+    ser::SerializeSeq,
+};
+
+impl Proto {
+    pub fn to_toml(&self) -> Result<String, ProError> {
+        toml::to_string(self).map_err(|e| ProError::TextError(e.to_string()))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ProError> {
+        let proto: Self = toml::from_str(text).map_err(|e| ProError::TextError(e.to_string()))?;
+        proto.check_type()?;
+        Ok(proto)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProError> {
+        serde_json::to_string_pretty(self).map_err(|e| ProError::TextError(e.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ProError> {
+        let proto: Self = serde_json::from_str(text).map_err(|e| ProError::TextError(e.to_string()))?;
+        proto.check_type()?;
+        Ok(proto)
+    }
+
+    //the pid decides how the data is read back, so they have to agree
+    fn check_type(&self) -> Result<(), ProError> {
+        let data_type = match self.data {
+            ProtoData::Item(_) => ProtoType::Item,
+            ProtoData::Critter(_) => ProtoType::Critter,
+            ProtoData::Scenery(_) => ProtoType::Scenery,
+            ProtoData::Wall(_) => ProtoType::Wall,
+            ProtoData::Tile(_) => ProtoType::Tile,
+            ProtoData::Misc(_) => ProtoType::Misc,
+        };
+
+        match self.pid().proto_type() {
+            Some(t) if t == data_type => Ok(()),
+            Some(t) => Err(ProError::TextError(format!("pid is a {} proto but the data is {}", t.name(), data_type.name()))),
+            None => Err(ProError::UnknownType(self.pid().0 >> 24)),
+        }
+    }
+}
+
+fn serialize_flags<S: Serializer>(value: u32, names: &[(u32, &str)], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut rest = value;
+    let mut seq = serializer.serialize_seq(None)?;
+    for (bit, name) in names {
+        if value & bit != 0 {
+            seq.serialize_element(name)?;
+            rest &= !bit;
+        }
+    }
+    if rest != 0 {
+        seq.serialize_element(&format!("{:#010x}", rest))?;
+    }
+    seq.end()
+}
+
+fn deserialize_flags<'de, D: Deserializer<'de>>(names: &[(u32, &str)], deserializer: D) -> Result<u32, D::Error> {
+    let list = Vec::<String>::deserialize(deserializer)?;
+    list.iter().try_fold(0, |flags, item| {
+        let bits = match names.iter().find(|(_, name)| name == item) {
+            Some((bit, _)) => *bit,
+            None => item.strip_prefix("0x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| D::Error::custom(format!("unknown flag {}", item)))?,
+        };
+        Ok(flags | bits)
+    })
+}
+
+macro_rules! flag_names {
+    ($(#[$meta:meta])* $module:ident { $($bit:literal => $name:literal,)* }) => {
+        $(#[$meta])*
+        pub(crate) mod $module {
+            use serde::{ Deserializer, Serializer };
+
+            pub const NAMES: &[(u32, &str)] = &[$(($bit, $name),)*];
+
+            pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+                super::serialize_flags(*value, NAMES, serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+                super::deserialize_flags(NAMES, deserializer)
+            }
+        }
+    };
+}
+
+flag_names! {
+    /// Flags of the common header, shared by every object
+    object_flags {
+        0x0000_0001 => "hidden",
+        0x0000_0004 => "no_save",
+        0x0000_0008 => "flat",
+        0x0000_0010 => "no_block",
+        0x0000_0020 => "lighting",
+        0x0000_0400 => "no_remove",
+        0x0000_0800 => "multi_hex",
+        0x0000_1000 => "no_highlight",
+        0x0000_2000 => "queued",
+        0x0000_4000 => "trans_red",
+        0x0000_8000 => "trans_none",
+        0x0001_0000 => "trans_wall",
+        0x0002_0000 => "trans_glass",
+        0x0004_0000 => "trans_steam",
+        0x0008_0000 => "trans_energy",
+        0x0100_0000 => "in_left_hand",
+        0x0200_0000 => "in_right_hand",
+        0x0400_0000 => "worn",
+        0x1000_0000 => "wall_trans_end",
+        0x2000_0000 => "light_thru",
+        0x4000_0000 => "seen",
+        0x8000_0000 => "shoot_thru",
+    }
+}
+
+flag_names! {
+    /// Actions the player can take on items and scenery, the attack modes in
+    /// the low byte of weapons stay a number
+    action_flags {
+        0x0000_0100 => "big_gun",
+        0x0000_0200 => "two_handed",
+        0x0000_0800 => "use",
+        0x0000_1000 => "use_on",
+        0x0000_2000 => "look",
+        0x0000_4000 => "talk",
+        0x0000_8000 => "pick_up",
+        0x0800_0000 => "hidden_item",
+    }
+}
+
+flag_names! {
+    critter_flags {
+        0x0000_0002 => "barter",
+        0x0000_0020 => "no_steal",
+        0x0000_0040 => "no_drop",
+        0x0000_0080 => "no_limbs",
+        0x0000_0100 => "no_age",
+        0x0000_0200 => "no_heal",
+        0x0000_0400 => "invulnerable",
+        0x0000_0800 => "flat",
+        0x0000_1000 => "special_death",
+        0x0000_2000 => "long_limbs",
+        0x0000_4000 => "no_knockback",
+    }
+}
+
+/// FIDs as plain numbers, `art` doesn't depend on serde
+pub(crate) mod fid {
+    use art::Fid;
+    use serde::{ Deserialize, Deserializer, Serializer };
+
+    pub fn serialize<S: Serializer>(value: &Fid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(value.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fid, D::Error> {
+        u32::deserialize(deserializer).map(Fid)
+    }
+}
+
+/// Sound ids as the letter they stand for
+pub(crate) mod letter {
+    use serde::{ Deserialize, Deserializer, Serializer, de::Error };
+
+    pub fn serialize<S: Serializer>(value: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(*value as char)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        let c = char::deserialize(deserializer)?;
+        u8::try_from(c).map_err(|_| D::Error::custom(format!("sound id {} isn't a single byte", c)))
+    }
+}
+
+/// Arrays longer than serde handles itself, like the critter stats
+pub(crate) mod array {
+    use serde::{ Deserialize, Deserializer, Serialize, Serializer, de::Error };
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(value: &[T; N], serializer: S) -> Result<S::Ok, S::Error> {
+        value.as_slice().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(deserializer: D) -> Result<[T; N], D::Error> {
+        let values = Vec::<T>::deserialize(deserializer)?;
+        let len = values.len();
+        values.try_into()
+            .map_err(|_| D::Error::custom(format!("expected {} values, found {}", N, len)))
+    }
+}
@@ -0,0 +1,579 @@
+//! Compiles SSL to `.int` scripts
+//!
+//! The start up code jumps to code that sets up the globals and exported
+//! variables, then exits. Procedures follow the calling convention described
+//! in `vm`, and always return a value, 0 if they don't `return` one.
+//! Procedures without a body must be imported, and calling one with the wrong
+//! number of arguments is an error.
+
+use crate::{
+    HEADER_SIZE,
+    IntFile,
+    PROCEDURE_EXPORTED,
+    PROCEDURE_IMPORTED,
+    Procedure,
+    error::{ CompileError, Location },
+    opcode::*,
+    parser::{ Expr, Linkage, Parser, ProcedureDef, Program, Stmt, Variable },
+    preprocess::{ IncludeFn, Preprocessor },
+};
+
+use std::{
+    collections::{ BTreeMap, HashMap },
+    fs,
+    path::{ Path, PathBuf },
+};
+
+//same end marker the reader expects
+const NAMESPACE_END: u32 = 0xFFFF_FFFF;
+
+/// Compiles `source`, `include` gives the name and text of each `#include`
+pub fn compile(name: &str, source: &str, include: &mut IncludeFn) -> Result<IntFile, CompileError> {
+    let tokens = Preprocessor::new(include).run(name, source)?;
+    let end = Location { file: name.to_string(), line: source.lines().count().max(1) };
+    let program = Parser::new(&tokens, &end).program()?;
+
+    Codegen::default().program(&program)
+}
+
+/// Compiles the file at `path`
+///
+/// Includes are looked for next to the file including them, then in
+/// `include_dirs`. Backslashes in include paths are taken as separators.
+pub fn compile_file(path: &Path, include_dirs: &[PathBuf]) -> Result<IntFile, CompileError> {
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path)
+        .map_err(|_| CompileError::FileNotFound { name: name.clone(), location: None })?;
+
+    let mut include = |from: &str, path: &str| {
+        let path = path.replace('\\', "/");
+        let dir = Path::new(from).parent().map(Path::to_path_buf).unwrap_or_default();
+        std::iter::once(dir)
+            .chain(include_dirs.iter().cloned())
+            .map(|dir| dir.join(&path))
+            .find_map(|file| fs::read_to_string(&file).ok().map(|text| (file.display().to_string(), text)))
+    };
+
+    compile(&name, &source, &mut include)
+}
+
+//a string table, offsets are counted from the start of its size
+#[derive(Default)]
+struct Namespace {
+    names: Vec<(u32, String)>,
+    offsets: HashMap<String, u32>,
+    size: u32,
+}
+
+impl Namespace {
+    fn add(&mut self, name: &str) -> u32 {
+        if let Some(offset) = self.offsets.get(name) {
+            return *offset;
+        }
+
+        let offset = 4 + self.size + 2;
+        self.size += 2 + padded_len(name) as u32;
+        self.names.push((offset, name.to_string()));
+        self.offsets.insert(name.to_string(), offset);
+        offset
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        if self.names.is_empty() {
+            out.extend_from_slice(&NAMESPACE_END.to_be_bytes());
+            return;
+        }
+
+        out.extend_from_slice(&self.size.to_be_bytes());
+        for (_, name) in &self.names {
+            let len = padded_len(name);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.resize(out.len() + len - name.len(), 0);
+        }
+        out.extend_from_slice(&NAMESPACE_END.to_be_bytes());
+    }
+
+    fn map(&self) -> BTreeMap<u32, String> {
+        self.names.iter().cloned().collect()
+    }
+}
+
+//null terminated, padded to an even length
+fn padded_len(name: &str) -> usize {
+    (name.len() + 2) & !1
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Symbol {
+    Local(i32),
+    Global(i32),
+    /// Exported or imported variable, by identifier offset
+    External(u32),
+}
+
+struct Entry {
+    procedure: Procedure,
+    params: usize,
+    defined: bool,
+    location: Location,
+}
+
+//code addresses are relative to the end of the tables until `finish`
+#[derive(Default)]
+struct Codegen {
+    code: Vec<u8>,
+    relocations: Vec<usize>,
+    identifiers: Namespace,
+    strings: Namespace,
+    procedures: Vec<Entry>,
+    procedure_index: HashMap<String, usize>,
+    globals: HashMap<String, Symbol>,
+    locals: HashMap<String, i32>,
+}
+
+impl Codegen {
+    fn program(mut self, program: &Program) -> Result<IntFile, CompileError> {
+        for definition in &program.procedures {
+            self.declare(definition)?;
+        }
+
+        let mut global_count = 0;
+        for (variable, linkage) in &program.globals {
+            let key = variable.name.to_lowercase();
+            if self.globals.contains_key(&key) {
+                return Err(redefined(&variable.name, &variable.location));
+            }
+            let symbol = match linkage {
+                Linkage::Local => {
+                    global_count += 1;
+                    Symbol::Global(global_count - 1)
+                },
+                _ => Symbol::External(self.identifiers.add(&variable.name)),
+            };
+            self.globals.insert(key, symbol);
+        }
+
+        self.start_up(program)?;
+
+        for definition in &program.procedures {
+            if let Some(body) = &definition.body {
+                self.procedure(definition, body)?;
+            }
+        }
+
+        if let Some(entry) = self.procedures.iter().find(|e| !e.defined && e.procedure.flags & PROCEDURE_IMPORTED == 0) {
+            return Err(CompileError::Undefined { name: entry.procedure.name.clone(), location: entry.location.clone() });
+        }
+
+        Ok(self.finish())
+    }
+
+    //procedures can be declared before they're defined, but only defined once
+    fn declare(&mut self, definition: &ProcedureDef) -> Result<(), CompileError> {
+        let key = definition.name.to_lowercase();
+        let flags = match definition.linkage {
+            Linkage::Local => 0,
+            Linkage::Import => PROCEDURE_IMPORTED,
+            Linkage::Export => PROCEDURE_EXPORTED,
+        };
+
+        let index = match self.procedure_index.get(&key) {
+            Some(index) => *index,
+            None => {
+                let name_offset = self.identifiers.add(&definition.name);
+                self.procedures.push(Entry {
+                    procedure: Procedure {
+                        name: definition.name.clone(),
+                        name_offset,
+                        arg_count: definition.params.len() as i32,
+                        ..Default::default()
+                    },
+                    params: definition.params.len(),
+                    defined: false,
+                    location: definition.location.clone(),
+                });
+                self.procedure_index.insert(key, self.procedures.len() - 1);
+                self.procedures.len() - 1
+            },
+        };
+
+        let entry = &mut self.procedures[index];
+        if entry.params != definition.params.len() || (entry.defined && definition.body.is_some()) {
+            return Err(redefined(&definition.name, &definition.location));
+        }
+        entry.procedure.flags |= flags;
+        entry.defined |= definition.body.is_some();
+        Ok(())
+    }
+
+    fn start_up(&mut self, program: &Program) -> Result<(), CompileError> {
+        self.op(SET_GLOBAL);
+        for (variable, _) in program.globals.iter().filter(|(_, l)| *l == Linkage::Local) {
+            match &variable.value {
+                Some(value) => self.value(value)?,
+                None => self.int(0),
+            }
+        }
+
+        for (variable, _) in program.globals.iter().filter(|(_, l)| *l == Linkage::Export) {
+            let offset = self.identifiers.add(&variable.name);
+            self.int(offset as i32);
+            self.op(EXPORT_VARIABLE);
+            if let Some(value) = &variable.value {
+                self.value(value)?;
+                self.int(offset as i32);
+                self.op(STORE_EXTERNAL);
+            }
+        }
+
+        for index in 0 .. self.procedures.len() {
+            let procedure = &self.procedures[index].procedure;
+            if procedure.flags & PROCEDURE_EXPORTED != 0 {
+                self.int(procedure.arg_count);
+                self.int(index as i32);
+                self.op(EXPORT_PROCEDURE);
+            }
+        }
+
+        self.op(EXIT);
+        Ok(())
+    }
+
+    fn procedure(&mut self, definition: &ProcedureDef, body: &[Stmt]) -> Result<(), CompileError> {
+        self.locals.clear();
+        for param in &definition.params {
+            self.local(param, &definition.location)?;
+        }
+        let param_count = self.locals.len();
+        for statement in body {
+            self.collect_locals(statement)?;
+        }
+
+        let index = self.procedure_index[&definition.name.to_lowercase()];
+        self.procedures[index].procedure.body = self.here() as u32;
+
+        self.op(PUSH_BASE);
+        for _ in param_count .. self.locals.len() {
+            self.int(0);
+        }
+        for statement in body {
+            self.statement(statement)?;
+        }
+        self.int(0);
+        self.ret();
+        Ok(())
+    }
+
+    //locals are procedure wide, wherever they're declared
+    fn collect_locals(&mut self, statement: &Stmt) -> Result<(), CompileError> {
+        match statement {
+            Stmt::Variables(variables) => {
+                for variable in variables {
+                    self.local(&variable.name, &variable.location)?;
+                }
+            },
+            Stmt::If(_, then, otherwise) => {
+                self.collect_locals(then)?;
+                if let Some(otherwise) = otherwise {
+                    self.collect_locals(otherwise)?;
+                }
+            },
+            Stmt::While(_, body) => self.collect_locals(body)?,
+            Stmt::Block(statements) => {
+                for statement in statements {
+                    self.collect_locals(statement)?;
+                }
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn local(&mut self, name: &str, location: &Location) -> Result<(), CompileError> {
+        let slot = self.locals.len() as i32;
+        match self.locals.insert(name.to_lowercase(), slot) {
+            Some(_) => Err(redefined(name, location)),
+            None => Ok(()),
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt) -> Result<(), CompileError> {
+        match statement {
+            Stmt::Variables(variables) => {
+                for Variable { name, value, location } in variables {
+                    if let Some(value) = value {
+                        self.value(value)?;
+                        self.store(name, location)?;
+                    }
+                }
+            },
+            Stmt::Assign { name, op, value, location } => {
+                if let Some(op) = op {
+                    self.load(name, location)?;
+                    self.value(value)?;
+                    self.op(*op);
+                } else {
+                    self.value(value)?;
+                }
+                self.store(name, location)?;
+            },
+            Stmt::If(condition, then, otherwise) => {
+                let skip = self.forward();
+                self.value(condition)?;
+                self.op(IF);
+                self.statement(then)?;
+
+                match otherwise {
+                    Some(otherwise) => {
+                        let end = self.forward();
+                        self.op(JUMP);
+                        self.patch(skip);
+                        self.statement(otherwise)?;
+                        self.patch(end);
+                    },
+                    None => self.patch(skip),
+                }
+            },
+            Stmt::While(condition, body) => {
+                let top = self.here();
+                let end = self.forward();
+                self.value(condition)?;
+                self.op(WHILE);
+                self.statement(body)?;
+                self.address(top);
+                self.op(JUMP);
+                self.patch(end);
+            },
+            Stmt::Block(statements) => {
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+            },
+            Stmt::Return(value) => {
+                match value {
+                    Some(value) => self.value(value)?,
+                    None => self.int(0),
+                }
+                self.ret();
+            },
+            Stmt::Expr(expr) => {
+                if self.expression(expr)? {
+                    self.op(POP);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    //an expression that must leave a value
+    fn value(&mut self, expr: &Expr) -> Result<(), CompileError> {
+        if self.expression(expr)? {
+            return Ok(());
+        }
+
+        match expr {
+            Expr::Call(name, _, location) | Expr::Name(name, location) => {
+                Err(CompileError::Syntax { message: format!("{} has no result", name), location: location.clone() })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    //returns whether a value was left on the stack
+    fn expression(&mut self, expr: &Expr) -> Result<bool, CompileError> {
+        match expr {
+            Expr::Int(v) => self.int(*v),
+            Expr::Float(v) => {
+                self.op(PUSH_FLOAT);
+                self.code.extend_from_slice(&v.to_bits().to_be_bytes());
+            },
+            Expr::Str(v) => {
+                let offset = self.strings.add(v);
+                self.op(PUSH_STRING);
+                self.code.extend_from_slice(&offset.to_be_bytes());
+            },
+            Expr::Name(name, location) => match self.symbol(name) {
+                Some(_) => self.load(name, location)?,
+                None => return self.call(name, &[], location),
+            },
+            Expr::Call(name, args, location) => return self.call(name, args, location),
+            Expr::Unary(op, value) => {
+                self.value(value)?;
+                self.op(*op);
+            },
+            Expr::Binary(op, a, b) => {
+                self.value(a)?;
+                self.value(b)?;
+                self.op(*op);
+            },
+        }
+        Ok(true)
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], location: &Location) -> Result<bool, CompileError> {
+        let argument_count = |expected: usize| match args.len() == expected {
+            true => Ok(()),
+            false => Err(CompileError::ArgumentCount {
+                name: name.to_string(),
+                expected,
+                given: args.len(),
+                location: location.clone(),
+            }),
+        };
+
+        if let Some(index) = self.procedure_index.get(&name.to_lowercase()).copied() {
+            argument_count(self.procedures[index].params)?;
+
+            let back = self.forward();
+            self.op(D_TO_A);
+            for arg in args {
+                self.value(arg)?;
+            }
+            self.int(args.len() as i32);
+            self.int(index as i32);
+            self.op(CALL);
+            self.patch(back);
+            self.op(A_TO_D);
+            return Ok(true);
+        }
+
+        let opcode = game_function(name)
+            .ok_or_else(|| CompileError::Undefined { name: name.to_string(), location: location.clone() })?;
+        let (arg_count, result) = signature(opcode).unwrap_or_default();
+        argument_count(arg_count)?;
+
+        for arg in args {
+            self.value(arg)?;
+        }
+        self.op(opcode);
+        Ok(result)
+    }
+
+    fn symbol(&self, name: &str) -> Option<Symbol> {
+        let key = name.to_lowercase();
+        self.locals.get(&key)
+            .map(|slot| Symbol::Local(*slot))
+            .or_else(|| self.globals.get(&key).copied())
+    }
+
+    fn load(&mut self, name: &str, location: &Location) -> Result<(), CompileError> {
+        match self.symbol(name) {
+            Some(Symbol::Local(slot)) => {
+                self.int(slot);
+                self.op(FETCH);
+            },
+            Some(Symbol::Global(index)) => {
+                self.int(index);
+                self.op(FETCH_GLOBAL);
+            },
+            Some(Symbol::External(offset)) => {
+                self.int(offset as i32);
+                self.op(FETCH_EXTERNAL);
+            },
+            None => return Err(CompileError::Undefined { name: name.to_string(), location: location.clone() }),
+        }
+        Ok(())
+    }
+
+    fn store(&mut self, name: &str, location: &Location) -> Result<(), CompileError> {
+        match self.symbol(name) {
+            Some(Symbol::Local(slot)) => {
+                self.int(slot);
+                self.op(STORE);
+            },
+            Some(Symbol::Global(index)) => {
+                self.int(index);
+                self.op(STORE_GLOBAL);
+            },
+            Some(Symbol::External(offset)) => {
+                self.int(offset as i32);
+                self.op(STORE_EXTERNAL);
+            },
+            None => return Err(CompileError::Undefined { name: name.to_string(), location: location.clone() }),
+        }
+        Ok(())
+    }
+
+    fn here(&self) -> i32 {
+        self.code.len() as i32
+    }
+
+    fn op(&mut self, opcode: u16) {
+        self.code.extend_from_slice(&opcode.to_be_bytes());
+    }
+
+    fn int(&mut self, value: i32) {
+        self.op(PUSH_INT);
+        self.code.extend_from_slice(&value.to_be_bytes());
+    }
+
+    //pushes a code address, moved past the tables by `finish`
+    fn address(&mut self, address: i32) {
+        self.int(address);
+        self.relocations.push(self.code.len() - 4);
+    }
+
+    //pushes an address filled in by `patch`
+    fn forward(&mut self) -> usize {
+        self.address(0);
+        self.code.len() - 4
+    }
+
+    //points a forward address here
+    fn patch(&mut self, at: usize) {
+        let here = self.here();
+        self.code[at .. at + 4].copy_from_slice(&here.to_be_bytes());
+    }
+
+    fn ret(&mut self) {
+        for opcode in [D_TO_A, SWAPA, POP_TO_BASE, POP_BASE, SWAPA, POP_RETURN] {
+            self.op(opcode);
+        }
+    }
+
+    fn finish(mut self) -> IntFile {
+        let mut tables = Vec::new();
+        self.identifiers.write(&mut tables);
+        self.strings.write(&mut tables);
+        let code_start = HEADER_SIZE + 4 + 24 * self.procedures.len() + tables.len();
+
+        for at in &self.relocations {
+            let address = i32::from_be_bytes(self.code[*at .. at + 4].try_into().unwrap()) + code_start as i32;
+            self.code[*at .. at + 4].copy_from_slice(&address.to_be_bytes());
+        }
+        for entry in self.procedures.iter_mut().filter(|e| e.defined) {
+            entry.procedure.body += code_start as u32;
+        }
+
+        //the start up code jumps over the tables
+        let mut data = Vec::new();
+        data.extend_from_slice(&PUSH_INT.to_be_bytes());
+        data.extend_from_slice(&(code_start as u32).to_be_bytes());
+        data.extend_from_slice(&JUMP.to_be_bytes());
+        while data.len() < HEADER_SIZE {
+            data.extend_from_slice(&NOOP.to_be_bytes());
+        }
+
+        data.extend_from_slice(&(self.procedures.len() as u32).to_be_bytes());
+        for Entry { procedure, .. } in &self.procedures {
+            for value in [procedure.name_offset, procedure.flags, procedure.time as u32, procedure.condition, procedure.body, procedure.arg_count as u32] {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        data.extend(tables);
+        data.extend(self.code);
+
+        IntFile {
+            data,
+            procedures: self.procedures.into_iter().map(|e| e.procedure).collect(),
+            identifiers: self.identifiers.map(),
+            strings: self.strings.map(),
+            code_start,
+        }
+    }
+}
+
+fn redefined(name: &str, location: &Location) -> CompileError {
+    CompileError::Redefined { name: name.to_string(), location: location.clone() }
+}
@@ -0,0 +1,171 @@
+//! Readable listings of compiled scripts
+//!
+//! Each line holds the address, the opcode and its operand. Pushes are
+//! annotated with what they're used as: a jump target, a procedure, an
+//! exported variable, or the text of a string.
+//!
+//! Most arguments are pushed right before the instruction using them. `if`
+//! and `while` take their condition first, so their address is found by
+//! stepping back over the condition, which only works while every opcode in
+//! it has a known `stack_effect`.
+
+use crate::{
+    IntFile,
+    Instruction,
+    Operand,
+    PROCEDURE_CONDITIONAL,
+    PROCEDURE_CRITICAL,
+    PROCEDURE_EXPORTED,
+    PROCEDURE_IMPORTED,
+    PROCEDURE_TIMED,
+    opcode::*,
+};
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+};
+
+/// What an instruction's pushed value is used as
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    /// Address jumped to, with its label
+    Target(u32),
+    Procedure(usize),
+    Identifier(u32),
+    String(u32),
+}
+
+impl IntFile {
+    /// Instructions with the argument each push turned out to be
+    pub fn disassemble(&self) -> Vec<(Instruction, Option<Argument>)> {
+        let instructions: Vec<_> = self.instructions().collect();
+        let mut arguments: Vec<_> = instructions.iter()
+            .enumerate()
+            .map(|(i, instruction)| match (instruction.operand, instructions.get(i + 1)) {
+                (Some(Operand::String(offset)), _) => Some(Argument::String(offset)),
+                (Some(Operand::Int(value)), Some(next)) => argument(next.opcode, value),
+                _ => None,
+            })
+            .collect();
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            if !matches!(instruction.opcode, IF | WHILE) {
+                continue;
+            }
+            if let Some(start) = condition_start(&instructions[.. i]) {
+                if let Some(Operand::Int(address)) = start.checked_sub(1).and_then(|s| instructions[s].operand) {
+                    arguments[start - 1] = Some(Argument::Target(address as u32));
+                }
+            }
+        }
+
+        instructions.into_iter().zip(arguments).collect()
+    }
+
+    /// Labels of every address jumped to, procedures by name and the rest by address
+    pub fn labels(&self) -> BTreeMap<u32, String> {
+        let mut labels: BTreeMap<u32, String> = self.disassemble()
+            .into_iter()
+            .filter_map(|(_, argument)| match argument {
+                Some(Argument::Target(address)) => Some((address, format!("L_{:08x}", address))),
+                _ => None,
+            })
+            .collect();
+
+        for procedure in self.procedures.iter().filter(|p| p.flags & PROCEDURE_IMPORTED == 0) {
+            labels.insert(procedure.body, procedure.name.clone());
+            if procedure.flags & PROCEDURE_CONDITIONAL != 0 {
+                labels.insert(procedure.condition, format!("{}_condition", procedure.name));
+            }
+        }
+
+        labels
+    }
+
+    /// The procedure table followed by the code, one instruction per line
+    pub fn listing(&self) -> String {
+        let mut out = String::new();
+        let labels = self.labels();
+
+        writeln!(out, "; {} procedures", self.procedures.len()).unwrap();
+        for (i, procedure) in self.procedures.iter().enumerate() {
+            write!(out, ";   {:<3} {:<24} body {:#010x} args {}", i, procedure.name, procedure.body, procedure.arg_count).unwrap();
+            for (flag, name) in FLAG_NAMES {
+                if procedure.flags & flag != 0 {
+                    write!(out, " {}", name).unwrap();
+                }
+            }
+            if procedure.flags & PROCEDURE_TIMED != 0 {
+                write!(out, " time {}", procedure.time).unwrap();
+            }
+            out.push('\n');
+        }
+
+        for (instruction, argument) in self.disassemble() {
+            if instruction.offset as usize == self.code_start || instruction.offset == 0 {
+                out.push('\n');
+            }
+            if let Some(label) = labels.get(&instruction.offset) {
+                writeln!(out, "{}:", label).unwrap();
+            }
+
+            let text = match (instruction.name(), instruction.operand) {
+                (_, Some(Operand::Int(value))) => format!("push {}", value),
+                (_, Some(Operand::Float(value))) => format!("push {:?}", value),
+                (_, Some(Operand::String(offset))) => format!("push string {}", offset),
+                (Some(name), None) => name.to_string(),
+                (None, None) => format!(".word {:#06x}", instruction.opcode),
+            };
+            write!(out, "{:08x}  {:04x}  {}", instruction.offset, instruction.opcode, text).unwrap();
+
+            if let Some(comment) = argument.and_then(|a| self.describe(&a, &labels)) {
+                write!(out, "{:1$}; {2}", "", 36usize.saturating_sub(text.len()), comment).unwrap();
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn describe(&self, argument: &Argument, labels: &BTreeMap<u32, String>) -> Option<String> {
+        match argument {
+            Argument::Target(address) => labels.get(address).cloned(),
+            Argument::Procedure(index) => self.procedures.get(*index).map(|p| p.name.clone()),
+            Argument::Identifier(offset) => self.identifier(*offset).map(str::to_string),
+            Argument::String(offset) => self.string(*offset).map(|s| format!("{:?}", s)),
+        }
+    }
+}
+
+const FLAG_NAMES: [(u32, &str); 5] = [
+    (PROCEDURE_TIMED, "timed"),
+    (PROCEDURE_CONDITIONAL, "conditional"),
+    (PROCEDURE_IMPORTED, "imported"),
+    (PROCEDURE_EXPORTED, "exported"),
+    (PROCEDURE_CRITICAL, "critical"),
+];
+
+//the compiler pushes each of these right before the instruction using it
+fn argument(next: u16, value: i32) -> Option<Argument> {
+    match next {
+        JUMP => Some(Argument::Target(value as u32)),
+        CALL | FETCH_PROCEDURE_ADDRESS | EXPORT_PROCEDURE => Some(Argument::Procedure(value as usize)),
+        FETCH_EXTERNAL | STORE_EXTERNAL | EXPORT_VARIABLE => Some(Argument::Identifier(value as u32)),
+        _ => None,
+    }
+}
+
+//index of the first instruction of the value left on top by `code`
+fn condition_start(code: &[Instruction]) -> Option<usize> {
+    let mut needed = 1;
+    for (i, instruction) in code.iter().enumerate().rev() {
+        let (pops, pushes) = stack_effect(instruction.opcode)?;
+        needed = (needed + pops).checked_sub(pushes)?;
+        if needed == 0 {
+            return Some(i);
+        }
+    }
+
+    None
+}
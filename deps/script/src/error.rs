@@ -0,0 +1,122 @@
+use common::BinaryReadError;
+use std::{
+    error::Error,
+    fmt::{ Result, Display },
+};
+
+#[derive(Debug)]
+pub enum ScriptError {
+    ReadError(BinaryReadError),
+    /// An identifier or string table isn't closed by 0xFFFFFFFF
+    InvalidNamespace { position: u64 },
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use ScriptError::*;
+        match self {
+            ReadError(e) => write!(f, "Error reading int file: {}", e),
+            InvalidNamespace { position } => write!(f, "Invalid name table at offset {:#x}", position),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+impl From<BinaryReadError> for ScriptError {
+    fn from(e: BinaryReadError) -> Self {
+        ScriptError::ReadError(e)
+    }
+}
+
+/// A fault while running a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum VmError {
+    StackUnderflow,
+    /// A value of the wrong type was popped
+    WrongType { expected: &'static str },
+    DivideByZero,
+    /// Opcode that isn't core and wasn't handled by the game functions
+    UnknownOpcode(u16),
+    /// Core opcode the VM doesn't run, such as timed calls
+    Unsupported(u16),
+    InvalidAddress(u32),
+    /// No string or identifier at this offset
+    InvalidName(u32),
+    /// Local or global variable past the end of the stack
+    InvalidVariable(i32),
+    UnknownExport(String),
+    UnknownProcedure(String),
+    ImportedProcedure(String),
+    ArgumentCount { procedure: String, expected: i32, given: i32 },
+    /// The step budget ran out
+    OutOfSteps,
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use VmError::*;
+        match self {
+            StackUnderflow => write!(f, "Stack underflow"),
+            WrongType { expected } => write!(f, "Expected {} on the stack", expected),
+            DivideByZero => write!(f, "Division by zero"),
+            UnknownOpcode(opcode) => write!(f, "Unknown opcode {:#06x}", opcode),
+            Unsupported(opcode) => write!(f, "Unsupported opcode {:#06x}", opcode),
+            InvalidAddress(address) => write!(f, "Invalid address {:#x}", address),
+            InvalidName(offset) => write!(f, "No name at offset {}", offset),
+            InvalidVariable(index) => write!(f, "Invalid variable {}", index),
+            UnknownExport(name) => write!(f, "Unknown exported variable {}", name),
+            UnknownProcedure(name) => write!(f, "Unknown procedure {}", name),
+            ImportedProcedure(name) => write!(f, "Procedure {} is imported", name),
+            ArgumentCount { procedure, expected, given } => {
+                write!(f, "Procedure {} takes {} arguments, given {}", procedure, expected, given)
+            },
+            OutOfSteps => write!(f, "Step budget exceeded"),
+        }
+    }
+}
+
+impl Error for VmError {}
+
+/// File and line a token came from, macros take the place they were used
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompileError {
+    /// A source or header that couldn't be found, with the `#include` asking for it
+    FileNotFound { name: String, location: Option<Location> },
+    Preprocessor { message: String, location: Location },
+    Syntax { message: String, location: Location },
+    Undefined { name: String, location: Location },
+    Redefined { name: String, location: Location },
+    ArgumentCount { name: String, expected: usize, given: usize, location: Location },
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result {
+        use CompileError::*;
+        match self {
+            FileNotFound { name, location: Some(location) } => write!(f, "{}: Can't find {}", location, name),
+            FileNotFound { name, location: None } => write!(f, "Can't find {}", name),
+            Preprocessor { message, location } => write!(f, "{}: {}", location, message),
+            Syntax { message, location } => write!(f, "{}: {}", location, message),
+            Undefined { name, location } => write!(f, "{}: {} is not defined", location, name),
+            Redefined { name, location } => write!(f, "{}: {} is already defined", location, name),
+            ArgumentCount { name, expected, given, location } => {
+                write!(f, "{}: {} takes {} arguments, given {}", location, name, expected, given)
+            },
+        }
+    }
+}
+
+impl Error for CompileError {}
@@ -0,0 +1,161 @@
+//! Splits SSL source into tokens
+//!
+//! Comments are dropped, and a backslash at the end of a line joins it to the
+//! next so long `#define`s can be split.
+
+use crate::error::{ CompileError, Location };
+
+//longest first, so `:=` isn't read as `:` then `=`
+const PUNCTUATION: [&str; 31] = [
+    ":=", "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "&&", "||",
+    "<", ">", "+", "-", "*", "/", "%", "(", ")", ",", ";", "=", "#", ":",
+    "[", "]", "{", "}", "!", ".",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    Int(i32),
+    Float(f32),
+    Str(String),
+    Punct(&'static str),
+}
+
+/// * `line_start` - first token on its line, directives start with one
+/// * `space_before` - whitespace before the token, tells `#define F(x)` from `#define F (x)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub location: Location,
+    pub line_start: bool,
+    pub space_before: bool,
+}
+
+impl Token {
+    pub fn is(&self, punct: &str) -> bool {
+        matches!(self.kind, TokenKind::Punct(p) if p == punct)
+    }
+
+    /// Whether this is the identifier `word`, ignoring case like keywords do
+    pub fn is_word(&self, word: &str) -> bool {
+        matches!(&self.kind, TokenKind::Ident(name) if name.eq_ignore_ascii_case(word))
+    }
+}
+
+pub fn tokenize(file: &str, source: &str) -> Result<Vec<Token>, CompileError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut line_start = true;
+    let mut space_before = false;
+    let mut i = 0;
+
+    let location = |line| Location { file: file.to_string(), line };
+    let error = |message: &str, line| CompileError::Syntax { message: message.to_string(), location: location(line) };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                space_before = true;
+                i += 1;
+                continue;
+            },
+            '\\' if next == Some('\n') || (next == Some('\r') && chars.get(i + 2) == Some(&'\n')) => {
+                line += 1;
+                space_before = true;
+                i += if next == Some('\n') { 2 } else { 3 };
+                continue;
+            },
+            c if c.is_whitespace() => {
+                space_before = true;
+                i += 1;
+                continue;
+            },
+            '/' if next == Some('/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            },
+            '/' if next == Some('*') => {
+                let start = line;
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    line += (chars[i] == '\n') as usize;
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(error("Unterminated comment", start));
+                }
+                space_before = true;
+                i += 2;
+                continue;
+            },
+            _ => (),
+        }
+
+        let start = i;
+        let kind = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            TokenKind::Ident(chars[start .. i].iter().collect())
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            number(&chars, &mut i).ok_or_else(|| error("Invalid number", line))?
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' && chars[i] != '\n' {
+                i += 1;
+            }
+            if chars.get(i) != Some(&'"') {
+                return Err(error("Unterminated string", line));
+            }
+            i += 1;
+            TokenKind::Str(chars[start + 1 .. i - 1].iter().collect())
+        } else {
+            let punct = PUNCTUATION.iter()
+                .find(|p| p.chars().enumerate().all(|(n, pc)| chars.get(i + n) == Some(&pc)))
+                .ok_or_else(|| error(&format!("Unexpected character {:?}", c), line))?;
+            i += punct.len();
+            TokenKind::Punct(punct)
+        };
+
+        tokens.push(Token { kind, location: location(line), line_start, space_before });
+        line_start = false;
+        space_before = false;
+    }
+
+    Ok(tokens)
+}
+
+//decimal, 0x hex or floats with a point
+fn number(chars: &[char], i: &mut usize) -> Option<TokenKind> {
+    let start = *i;
+    if chars[start] == '0' && matches!(chars.get(start + 1), Some('x' | 'X')) {
+        *i += 2;
+        while *i < chars.len() && chars[*i].is_ascii_hexdigit() {
+            *i += 1;
+        }
+        let digits: String = chars[start + 2 .. *i].iter().collect();
+        return u32::from_str_radix(&digits, 16).ok().map(|v| TokenKind::Int(v as i32));
+    }
+
+    while *i < chars.len() && (chars[*i].is_ascii_digit() || chars[*i] == '.') {
+        *i += 1;
+    }
+    if *i < chars.len() && (chars[*i].is_ascii_alphabetic() || chars[*i] == '_') {
+        return None;
+    }
+
+    let text: String = chars[start .. *i].iter().collect();
+    if text.contains('.') {
+        text.parse().ok().map(TokenKind::Float)
+    } else {
+        text.parse::<i32>().or(text.parse::<u32>().map(|v| v as i32)).ok().map(TokenKind::Int)
+    }
+}
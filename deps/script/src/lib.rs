@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests;
+
+pub mod compiler;
+pub mod disasm;
+pub mod error;
+pub mod lexer;
+pub mod opcode;
+pub mod parser;
+pub mod preprocess;
+pub mod vm;
+
+pub use compiler::{ compile, compile_file };
+pub use error::{ CompileError, ScriptError, VmError };
+pub use opcode::{ Instruction, Operand };
+pub use vm::{ GameFunctions, Stack, Value, Vm };
+
+use common::{
+    BinaryReadError,
+    BinaryReader,
+};
+
+use std::{
+    collections::BTreeMap,
+    io::{ Cursor, Read, Seek, SeekFrom },
+};
+
+/// Bytes of start up code before the procedure table
+pub const HEADER_SIZE: usize = 42;
+
+//namespaces end with this, or are just this when empty
+const NAMESPACE_END: u32 = 0xFFFF_FFFF;
+
+/// Procedure flags
+pub const PROCEDURE_TIMED: u32 = 0x01;
+pub const PROCEDURE_CONDITIONAL: u32 = 0x02;
+pub const PROCEDURE_IMPORTED: u32 = 0x04;
+pub const PROCEDURE_EXPORTED: u32 = 0x08;
+pub const PROCEDURE_CRITICAL: u32 = 0x10;
+
+/// An entry of the procedure table
+///
+/// * `name_offset` - offset of the name in the identifier table
+/// * `time` - delay of timed procedures
+/// * `condition` - address of the condition of conditional procedures
+/// * `body` - address of the code
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Procedure {
+    pub name: String,
+    pub name_offset: u32,
+    pub flags: u32,
+    pub time: i32,
+    pub condition: u32,
+    pub body: u32,
+    pub arg_count: i32,
+}
+
+/// A compiled script, as stored in `scripts/<name>.int`
+///
+/// Every value is big endian. The file starts with a little start up code,
+/// then the procedure table, the identifier and string tables, and the code
+/// of the procedures. Tables map the offset of each name, counted from the
+/// start of the table, to the name.
+///
+/// * `data` - the whole file, addresses in the code are offsets in it
+/// * `code_start` - offset of the first procedure's code
+#[derive(Debug, Default, Clone)]
+pub struct IntFile {
+    pub data: Vec<u8>,
+    pub procedures: Vec<Procedure>,
+    pub identifiers: BTreeMap<u32, String>,
+    pub strings: BTreeMap<u32, String>,
+    pub code_start: usize,
+}
+
+impl IntFile {
+    pub fn open(stream: &mut impl Read) -> Result<Self, ScriptError> {
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).map_err(BinaryReadError::from)?;
+
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, ScriptError> {
+        let mut stream = Cursor::new(&data[..]);
+        stream.seek(SeekFrom::Start(HEADER_SIZE as u64)).map_err(BinaryReadError::from)?;
+
+        let count = stream.read_i32_be()?.max(0);
+        let mut procedures = Vec::new();
+        for _ in 0 .. count {
+            procedures.push(Procedure {
+                name: String::new(),
+                name_offset: stream.read_u32_be()?,
+                flags: stream.read_u32_be()?,
+                time: stream.read_i32_be()?,
+                condition: stream.read_u32_be()?,
+                body: stream.read_u32_be()?,
+                arg_count: stream.read_i32_be()?,
+            });
+        }
+
+        let identifiers = read_namespace(&mut stream)?;
+        let strings = read_namespace(&mut stream)?;
+
+        for procedure in procedures.iter_mut() {
+            if let Some(name) = identifiers.get(&procedure.name_offset) {
+                procedure.name = name.clone();
+            }
+        }
+
+        let code_start = stream.position() as usize;
+        Ok(Self { data, procedures, identifiers, strings, code_start })
+    }
+
+    pub fn identifier(&self, offset: u32) -> Option<&str> {
+        self.identifiers.get(&offset).map(String::as_str)
+    }
+
+    pub fn string(&self, offset: u32) -> Option<&str> {
+        self.strings.get(&offset).map(String::as_str)
+    }
+
+    /// Index and entry of the procedure called `name`, ignoring case like the engine
+    pub fn procedure(&self, name: &str) -> Option<(usize, &Procedure)> {
+        self.procedures.iter()
+            .enumerate()
+            .find(|(_, p)| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Instructions of the start up code, then of the procedures
+    pub fn instructions(&self) -> impl Iterator<Item = Instruction> + '_ {
+        let header = Instructions { code: &self.data[.. HEADER_SIZE.min(self.data.len())], offset: 0 };
+        let code = Instructions { code: &self.data, offset: self.code_start };
+        header.chain(code)
+    }
+}
+
+//decodes one after another until the code runs out
+struct Instructions<'a> {
+    code: &'a [u8],
+    offset: usize,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = Instruction;
+
+    fn next(&mut self) -> Option<Instruction> {
+        let instruction = Instruction::decode(self.code, self.offset)?;
+        self.offset += instruction.size();
+        Some(instruction)
+    }
+}
+
+//size, then strings each after a 16 bit length, then the end marker
+fn read_namespace(stream: &mut Cursor<&[u8]>) -> Result<BTreeMap<u32, String>, ScriptError> {
+    let start = stream.position();
+    let mut names = BTreeMap::new();
+
+    let size = stream.read_u32_be()?;
+    if size == NAMESPACE_END {
+        return Ok(names);
+    }
+
+    let end = start + 4 + size as u64;
+    while stream.position() < end {
+        let len = stream.read_u16_be()? as usize;
+        let offset = stream.position() - start;
+        let name = stream.read_fixed_string(len)?;
+        names.insert(offset as u32, name);
+    }
+
+    if stream.position() != end || stream.read_u32_be()? != NAMESPACE_END {
+        return Err(ScriptError::InvalidNamespace { position: start });
+    }
+
+    Ok(names)
+}
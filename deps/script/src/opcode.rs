@@ -0,0 +1,254 @@
+//! Opcodes of the script interpreter
+//!
+//! Instructions are a big endian 16 bit opcode with the top bit set. Only the
+//! push opcodes are followed by an operand, 4 bytes holding the value.
+
+macro_rules! opcodes {
+    ($($(#[$meta:meta])* $name:ident = $value:literal => $text:literal,)*) => {
+        $(
+            $(#[$meta])*
+            pub const $name: u16 = $value;
+        )*
+
+        /// Name used in listings, none for opcodes not known yet
+        pub fn name(opcode: u16) -> Option<&'static str> {
+            match opcode {
+                $($value => Some($text),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+opcodes! {
+    NOOP = 0x8000 => "noop",
+    CRITICAL_START = 0x8002 => "critical_start",
+    CRITICAL_DONE = 0x8003 => "critical_done",
+    /// Jumps to the address on the stack
+    JUMP = 0x8004 => "jmp",
+    /// Calls the procedure with the index on the stack
+    CALL = 0x8005 => "call",
+    CALL_AT = 0x8006 => "call_at",
+    CALL_CONDITION = 0x8007 => "call_condition",
+    CALLSTART = 0x8008 => "callstart",
+    EXEC = 0x8009 => "exec",
+    SPAWN = 0x800A => "spawn",
+    FORK = 0x800B => "fork",
+    /// Moves the top of the data stack to the return stack
+    A_TO_D = 0x800C => "a_to_d",
+    D_TO_A = 0x800D => "d_to_a",
+    EXIT = 0x800E => "exit",
+    DETACH = 0x800F => "detach",
+    EXIT_PROGRAM = 0x8010 => "exit_prog",
+    STOP_PROGRAM = 0x8011 => "stop_prog",
+    FETCH_GLOBAL = 0x8012 => "fetch_global",
+    STORE_GLOBAL = 0x8013 => "store_global",
+    /// Exported variables are named by an identifier offset
+    FETCH_EXTERNAL = 0x8014 => "fetch_external",
+    STORE_EXTERNAL = 0x8015 => "store_external",
+    EXPORT_VARIABLE = 0x8016 => "export_var",
+    EXPORT_PROCEDURE = 0x8017 => "export_proc",
+    SWAP = 0x8018 => "swap",
+    SWAPA = 0x8019 => "swapa",
+    POP = 0x801A => "pop",
+    DUP = 0x801B => "dup",
+    POP_RETURN = 0x801C => "pop_return",
+    POP_EXIT = 0x801D => "pop_exit",
+    POP_ADDRESS = 0x801E => "pop_address",
+    POP_FLAGS = 0x801F => "pop_flags",
+    POP_FLAGS_RETURN = 0x8020 => "pop_flags_return",
+    POP_FLAGS_EXIT = 0x8021 => "pop_flags_exit",
+    POP_FLAGS_RETURN_EXTERN = 0x8022 => "pop_flags_return_extern",
+    POP_FLAGS_EXIT_EXTERN = 0x8023 => "pop_flags_exit_extern",
+    POP_FLAGS_RETURN_VAL_EXTERN = 0x8024 => "pop_flags_return_val_extern",
+    POP_FLAGS_RETURN_VAL_EXIT = 0x8025 => "pop_flags_return_val_exit",
+    POP_FLAGS_RETURN_VAL_EXIT_EXTERN = 0x8026 => "pop_flags_return_val_exit_extern",
+    CHECK_ARG_COUNT = 0x8027 => "check_arg_count",
+    LOOKUP_PROCEDURE = 0x8028 => "lookup_string_proc",
+    POP_BASE = 0x8029 => "pop_base",
+    POP_TO_BASE = 0x802A => "pop_to_base",
+    PUSH_BASE = 0x802B => "push_base",
+    SET_GLOBAL = 0x802C => "set_global",
+    FETCH_PROCEDURE_ADDRESS = 0x802D => "fetch_proc_address",
+    DUMP = 0x802E => "dump",
+    /// Jumps to the address on the stack if the value under it is false
+    IF = 0x802F => "if",
+    WHILE = 0x8030 => "while",
+    STORE = 0x8031 => "store",
+    FETCH = 0x8032 => "fetch",
+    EQUAL = 0x8033 => "equal",
+    NOT_EQUAL = 0x8034 => "not_equal",
+    LESS_EQUAL = 0x8035 => "less_equal",
+    GREATER_EQUAL = 0x8036 => "greater_equal",
+    LESS = 0x8037 => "less",
+    GREATER = 0x8038 => "greater",
+    ADD = 0x8039 => "add",
+    SUB = 0x803A => "sub",
+    MUL = 0x803B => "mul",
+    DIV = 0x803C => "div",
+    MOD = 0x803D => "mod",
+    AND = 0x803E => "and",
+    OR = 0x803F => "or",
+    BITWISE_AND = 0x8040 => "bwand",
+    BITWISE_OR = 0x8041 => "bwor",
+    BITWISE_XOR = 0x8042 => "bwxor",
+    BITWISE_NOT = 0x8043 => "bwnot",
+    FLOOR = 0x8044 => "floor",
+    NOT = 0x8045 => "not",
+    NEGATE = 0x8046 => "negate",
+    WAIT = 0x8047 => "wait",
+    CANCEL = 0x8048 => "cancel",
+    CANCEL_ALL = 0x8049 => "cancelall",
+    START_CRITICAL = 0x804A => "startcritical",
+    END_CRITICAL = 0x804B => "endcritical",
+
+    PUSH_STRING = 0x9001 => "push",
+    PUSH_FLOAT = 0xA001 => "push",
+    PUSH_INT = 0xC001 => "push",
+
+    //game functions
+    GIVE_EXP_POINTS = 0x80A1 => "give_exp_points",
+    SCR_RETURN = 0x80A2 => "scr_return",
+    PLAY_SFX = 0x80A3 => "play_sfx",
+    OBJ_NAME = 0x80A4 => "obj_name",
+    SFX_BUILD_OPEN_NAME = 0x80A5 => "sfx_build_open_name",
+    GET_PC_STAT = 0x80A6 => "get_pc_stat",
+    TILE_CONTAINS_PID_OBJ = 0x80A7 => "tile_contains_pid_obj",
+    SET_MAP_START = 0x80A8 => "set_map_start",
+    OVERRIDE_MAP_START = 0x80A9 => "override_map_start",
+    HAS_SKILL = 0x80AA => "has_skill",
+    USING_SKILL = 0x80AB => "using_skill",
+    ROLL_VS_SKILL = 0x80AC => "roll_vs_skill",
+    SKILL_CONTEST = 0x80AD => "skill_contest",
+    DO_CHECK = 0x80AE => "do_check",
+    IS_SUCCESS = 0x80AF => "is_success",
+    IS_CRITICAL = 0x80B0 => "is_critical",
+    HOW_MUCH = 0x80B1 => "how_much",
+    REACTION_ROLL = 0x80B2 => "reaction_roll",
+    REACTION_INFLUENCE = 0x80B3 => "reaction_influence",
+    RANDOM = 0x80B4 => "random",
+    ROLL_DICE = 0x80B5 => "roll_dice",
+    MOVE_TO = 0x80B6 => "move_to",
+    CREATE_OBJECT_SID = 0x80B7 => "create_object_sid",
+    DISPLAY_MSG = 0x80B8 => "display_msg",
+    SCRIPT_OVERRIDES = 0x80B9 => "script_overrides",
+    OBJ_IS_CARRYING_OBJ_PID = 0x80BA => "obj_is_carrying_obj_pid",
+    TILE_CONTAINS_OBJ_PID = 0x80BB => "tile_contains_obj_pid",
+    SELF_OBJ = 0x80BC => "self_obj",
+    SOURCE_OBJ = 0x80BD => "source_obj",
+    TARGET_OBJ = 0x80BE => "target_obj",
+    DUDE_OBJ = 0x80BF => "dude_obj",
+    OBJ_BEING_USED_WITH = 0x80C0 => "obj_being_used_with",
+    LOCAL_VAR = 0x80C1 => "local_var",
+    SET_LOCAL_VAR = 0x80C2 => "set_local_var",
+    MAP_VAR = 0x80C3 => "map_var",
+    SET_MAP_VAR = 0x80C4 => "set_map_var",
+    GLOBAL_VAR = 0x80C5 => "global_var",
+    SET_GLOBAL_VAR = 0x80C6 => "set_global_var",
+    SCRIPT_ACTION = 0x80C7 => "script_action",
+    OBJ_TYPE = 0x80C8 => "obj_type",
+    OBJ_ITEM_SUBTYPE = 0x80C9 => "obj_item_subtype",
+    GET_CRITTER_STAT = 0x80CA => "get_critter_stat",
+    SET_CRITTER_STAT = 0x80CB => "set_critter_stat",
+    OBJ_PID = 0x8100 => "obj_pid",
+    MESSAGE_STR = 0x8105 => "message_str",
+    FLOAT_MSG = 0x810A => "float_msg",
+    GSAY_START = 0x811C => "gsay_start",
+    GSAY_END = 0x811D => "gsay_end",
+    GSAY_REPLY = 0x811E => "gsay_reply",
+    GSAY_OPTION = 0x811F => "gsay_option",
+    GSAY_MESSAGE = 0x8120 => "gsay_message",
+    GIQ_OPTION = 0x8121 => "giq_option",
+}
+
+/// Values an expression opcode pops and pushes, none for statements and
+/// functions whose arguments aren't known yet
+pub fn stack_effect(opcode: u16) -> Option<(usize, usize)> {
+    Some(match opcode {
+        PUSH_INT | PUSH_FLOAT | PUSH_STRING => (0, 1),
+        SELF_OBJ | SOURCE_OBJ | TARGET_OBJ | DUDE_OBJ | OBJ_BEING_USED_WITH | SCRIPT_ACTION => (0, 1),
+        DUP => (1, 2),
+        FETCH | FETCH_GLOBAL | FETCH_EXTERNAL | FETCH_PROCEDURE_ADDRESS => (1, 1),
+        BITWISE_NOT | FLOOR | NOT | NEGATE => (1, 1),
+        OBJ_NAME | GET_PC_STAT | IS_SUCCESS | IS_CRITICAL | HOW_MUCH => (1, 1),
+        LOCAL_VAR | MAP_VAR | GLOBAL_VAR | OBJ_TYPE | OBJ_ITEM_SUBTYPE | OBJ_PID => (1, 1),
+        EQUAL ..= BITWISE_XOR => (2, 1),
+        HAS_SKILL | USING_SKILL | RANDOM | OBJ_IS_CARRYING_OBJ_PID | GET_CRITTER_STAT | MESSAGE_STR => (2, 1),
+        ROLL_VS_SKILL | SKILL_CONTEST | DO_CHECK | TILE_CONTAINS_OBJ_PID | TILE_CONTAINS_PID_OBJ => (3, 1),
+        _ => return None,
+    })
+}
+
+/// Arguments of a game function the compiler can call, and whether it has a result
+pub fn signature(opcode: u16) -> Option<(usize, bool)> {
+    match opcode {
+        SCRIPT_OVERRIDES => Some((0, false)),
+        GIVE_EXP_POINTS | SCR_RETURN | PLAY_SFX | DISPLAY_MSG => Some((1, false)),
+        SET_LOCAL_VAR | SET_MAP_VAR | SET_GLOBAL_VAR => Some((2, false)),
+        FLOAT_MSG => Some((3, false)),
+        GIVE_EXP_POINTS ..= GIQ_OPTION => stack_effect(opcode).map(|(pops, pushes)| (pops, pushes > 0)),
+        _ => None,
+    }
+}
+
+/// Opcode of the game function called `name`, ignoring case
+pub fn game_function(name: &str) -> Option<u16> {
+    (GIVE_EXP_POINTS ..= GIQ_OPTION).find(|opcode| {
+        signature(*opcode).is_some() && self::name(*opcode).is_some_and(|n| n.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Value following a push opcode
+///
+/// * `String` - offset in the string table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Int(i32),
+    Float(f32),
+    String(u32),
+}
+
+/// * `offset` - position in the file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instruction {
+    pub offset: u32,
+    pub opcode: u16,
+    pub operand: Option<Operand>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `offset`, none if the code ends first
+    ///
+    /// A push without room for its operand is decoded without one.
+    pub fn decode(code: &[u8], offset: usize) -> Option<Self> {
+        let opcode = u16::from_be_bytes(code.get(offset .. offset + 2)?.try_into().ok()?);
+        let value = code.get(offset + 2 .. offset + 6)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()));
+
+        let operand = match opcode {
+            PUSH_INT => value.map(|v| Operand::Int(v as i32)),
+            PUSH_FLOAT => value.map(|v| Operand::Float(f32::from_bits(v))),
+            PUSH_STRING => value.map(Operand::String),
+            _ => None,
+        };
+
+        Some(Self { offset: offset as u32, opcode, operand })
+    }
+
+    /// Size in bytes, including the operand
+    pub fn size(&self) -> usize {
+        match self.operand {
+            Some(_) => 6,
+            None => 2,
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        name(self.opcode)
+    }
+
+    /// Address after this instruction
+    pub fn next(&self) -> u32 {
+        self.offset + self.size() as u32
+    }
+}
@@ -0,0 +1,409 @@
+//! Parses preprocessed SSL into procedures and statements
+//!
+//! Keywords ignore case. Operators from lowest to highest precedence:
+//! `or`, `and`, `bwor` `bwxor`, `bwand`, comparisons, `+` `-`, `*` `/` `%`,
+//! then the unary `not`, `bwnot` and `-`.
+
+use crate::{
+    error::{ CompileError, Location },
+    lexer::{ Token, TokenKind },
+    opcode::*,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Int(i32),
+    Float(f32),
+    Str(String),
+    /// A variable, a procedure or a game function without arguments
+    Name(String, Location),
+    Call(String, Vec<Expr>, Location),
+    Unary(u16, Box<Expr>),
+    Binary(u16, Box<Expr>, Box<Expr>),
+}
+
+/// * `Assign` - the operator of `+=` and similar, none for `:=`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Variables(Vec<Variable>),
+    Assign { name: String, op: Option<u16>, value: Expr, location: Location },
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    While(Expr, Box<Stmt>),
+    Block(Vec<Stmt>),
+    Return(Option<Expr>),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub value: Option<Expr>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    Local,
+    Import,
+    Export,
+}
+
+/// A procedure, `body` is none for declarations
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcedureDef {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Option<Vec<Stmt>>,
+    pub linkage: Linkage,
+    pub location: Location,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Program {
+    pub globals: Vec<(Variable, Linkage)>,
+    pub procedures: Vec<ProcedureDef>,
+}
+
+pub struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    end: &'a Location,
+}
+
+impl<'a> Parser<'a> {
+    /// * `end` - where errors at the end of the tokens are reported
+    pub fn new(tokens: &'a [Token], end: &'a Location) -> Self {
+        Self { tokens, position: 0, end }
+    }
+
+    pub fn program(mut self) -> Result<Program, CompileError> {
+        let mut program = Program::default();
+
+        while self.peek().is_some() {
+            let linkage = if self.eat_word("import") {
+                Linkage::Import
+            } else if self.eat_word("export") {
+                Linkage::Export
+            } else {
+                Linkage::Local
+            };
+
+            if self.eat_word("variable") {
+                for variable in self.variables()? {
+                    program.globals.push((variable, linkage));
+                }
+            } else if self.eat_word("procedure") {
+                program.procedures.push(self.procedure(linkage)?);
+            } else if linkage == Linkage::Local && self.eat(";") {
+                continue;
+            } else {
+                return Err(self.expected("variable or procedure"));
+            }
+        }
+
+        Ok(program)
+    }
+
+    /// A single expression using every token
+    pub fn expression_only(mut self) -> Result<Expr, CompileError> {
+        let expr = self.expression()?;
+        match self.peek() {
+            Some(_) => Err(self.expected("end of expression")),
+            None => Ok(expr),
+        }
+    }
+
+    fn procedure(&mut self, linkage: Linkage) -> Result<ProcedureDef, CompileError> {
+        let location = self.location();
+        let name = self.name()?;
+
+        let mut params = Vec::new();
+        if self.eat("(") && !self.eat(")") {
+            loop {
+                self.eat_word("variable");
+                params.push(self.name()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        let body = match self.eat(";") {
+            true => None,
+            false => {
+                self.expect_word("begin")?;
+                Some(self.block()?)
+            },
+        };
+
+        Ok(ProcedureDef { name, params, body, linkage, location })
+    }
+
+    //`a, b := 1;` or `begin a; b := 1; end`
+    fn variables(&mut self) -> Result<Vec<Variable>, CompileError> {
+        let block = self.eat_word("begin");
+        let mut variables = Vec::new();
+
+        loop {
+            if block && self.eat_word("end") {
+                self.eat(";");
+                return Ok(variables);
+            }
+
+            let location = self.location();
+            let name = self.name()?;
+            let value = match self.eat(":=") || self.eat("=") {
+                true => Some(self.expression()?),
+                false => None,
+            };
+            variables.push(Variable { name, value, location });
+
+            if !self.eat(",") {
+                self.expect(";")?;
+                if !block {
+                    return Ok(variables);
+                }
+            }
+        }
+    }
+
+    //statements up to `end`
+    fn block(&mut self) -> Result<Vec<Stmt>, CompileError> {
+        let mut statements = Vec::new();
+        while !self.eat_word("end") {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, CompileError> {
+        let location = self.location();
+
+        if self.eat(";") {
+            return Ok(Stmt::Block(Vec::new()));
+        }
+        if self.eat_word("begin") {
+            return Ok(Stmt::Block(self.block()?));
+        }
+        if self.eat_word("variable") {
+            return Ok(Stmt::Variables(self.variables()?));
+        }
+        if self.eat_word("if") {
+            let condition = self.expression()?;
+            self.expect_word("then")?;
+            let then = Box::new(self.statement()?);
+            let otherwise = match self.eat_word("else") {
+                true => Some(Box::new(self.statement()?)),
+                false => None,
+            };
+            return Ok(Stmt::If(condition, then, otherwise));
+        }
+        if self.eat_word("while") {
+            let condition = self.expression()?;
+            self.expect_word("do")?;
+            return Ok(Stmt::While(condition, Box::new(self.statement()?)));
+        }
+        if self.eat_word("return") {
+            let value = match self.eat(";") {
+                true => return Ok(Stmt::Return(None)),
+                false => self.expression()?,
+            };
+            self.expect(";")?;
+            return Ok(Stmt::Return(Some(value)));
+        }
+
+        let statement = if self.eat_word("call") {
+            let name = self.name()?;
+            let args = self.arguments()?;
+            Stmt::Expr(Expr::Call(name, args, location))
+        } else if let Some(op) = self.assignment() {
+            let name = self.name()?;
+            self.position += 1;
+            Stmt::Assign { name, op, value: self.expression()?, location }
+        } else {
+            Stmt::Expr(self.expression()?)
+        };
+
+        self.expect(";")?;
+        Ok(statement)
+    }
+
+    //the operator if the next tokens are a name and an assignment
+    fn assignment(&self) -> Option<Option<u16>> {
+        if !matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Ident(_))) {
+            return None;
+        }
+
+        match self.tokens.get(self.position + 1)?.kind {
+            TokenKind::Punct(":=" | "=") => Some(None),
+            TokenKind::Punct("+=") => Some(Some(ADD)),
+            TokenKind::Punct("-=") => Some(Some(SUB)),
+            TokenKind::Punct("*=") => Some(Some(MUL)),
+            TokenKind::Punct("/=") => Some(Some(DIV)),
+            _ => None,
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expr>, CompileError> {
+        let mut args = Vec::new();
+        if !self.eat("(") || self.eat(")") {
+            return Ok(args);
+        }
+
+        loop {
+            args.push(self.expression()?);
+            if self.eat(")") {
+                return Ok(args);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    pub fn expression(&mut self) -> Result<Expr, CompileError> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, CompileError> {
+        const LEVELS: [&[(&str, u16)]; 6] = [
+            &[("or", OR), ("||", OR)],
+            &[("and", AND), ("&&", AND)],
+            &[("bwor", BITWISE_OR), ("bwxor", BITWISE_XOR)],
+            &[("bwand", BITWISE_AND)],
+            &[("==", EQUAL), ("!=", NOT_EQUAL), ("<=", LESS_EQUAL), (">=", GREATER_EQUAL), ("<", LESS), (">", GREATER)],
+            &[("+", ADD), ("-", SUB)],
+        ];
+
+        if level == LEVELS.len() {
+            return self.term();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.operator(LEVELS[level]) {
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, CompileError> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.operator(&[("*", MUL), ("/", DIV), ("%", MOD)]) {
+            let right = self.unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CompileError> {
+        match self.operator(&[("not", NOT), ("!", NOT), ("bwnot", BITWISE_NOT), ("-", NEGATE)]) {
+            Some(op) => Ok(Expr::Unary(op, Box::new(self.unary()?))),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, CompileError> {
+        let location = self.location();
+        let token = self.peek().ok_or_else(|| self.expected("expression"))?;
+
+        let expr = match &token.kind {
+            TokenKind::Int(v) => Expr::Int(*v),
+            TokenKind::Float(v) => Expr::Float(*v),
+            TokenKind::Str(v) => Expr::Str(v.clone()),
+            TokenKind::Punct("(") => {
+                self.position += 1;
+                let expr = self.expression()?;
+                self.expect(")")?;
+                return Ok(expr);
+            },
+            TokenKind::Ident(_) => {
+                let name = self.name()?;
+                return match self.peek().is_some_and(|t| t.is("(")) {
+                    true => Ok(Expr::Call(name, self.arguments()?, location)),
+                    false => Ok(Expr::Name(name, location)),
+                };
+            },
+            _ => return Err(self.expected("expression")),
+        };
+
+        self.position += 1;
+        Ok(expr)
+    }
+
+    fn operator(&mut self, operators: &[(&str, u16)]) -> Option<u16> {
+        let token = self.peek()?;
+        let (_, op) = operators.iter().find(|(text, _)| token.is(text) || token.is_word(text))?;
+        self.position += 1;
+        Some(*op)
+    }
+
+    fn name(&mut self) -> Result<String, CompileError> {
+        match self.peek().map(|t| &t.kind) {
+            Some(TokenKind::Ident(name)) if !is_keyword(name) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            },
+            _ => Err(self.expected("name")),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn location(&self) -> Location {
+        self.peek().map_or_else(|| self.end.clone(), |t| t.location.clone())
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is(punct));
+        self.position += found as usize;
+        found
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is_word(word));
+        self.position += found as usize;
+        found
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), CompileError> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(self.expected(punct)),
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), CompileError> {
+        match self.eat_word(word) {
+            true => Ok(()),
+            false => Err(self.expected(word)),
+        }
+    }
+
+    fn expected(&self, what: &str) -> CompileError {
+        let found = match self.peek().map(|t| &t.kind) {
+            Some(TokenKind::Ident(name)) => name.clone(),
+            Some(TokenKind::Int(v)) => v.to_string(),
+            Some(TokenKind::Float(v)) => v.to_string(),
+            Some(TokenKind::Str(v)) => format!("{:?}", v),
+            Some(TokenKind::Punct(p)) => p.to_string(),
+            None => "end of file".to_string(),
+        };
+
+        CompileError::Syntax {
+            message: format!("Expected {}, found {}", what, found),
+            location: self.location(),
+        }
+    }
+}
+
+const KEYWORDS: [&str; 20] = [
+    "procedure", "begin", "end", "variable", "if", "then", "else", "while", "do", "return", "call",
+    "import", "export", "and", "or", "not", "bwand", "bwor", "bwxor", "bwnot",
+];
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(name))
+}
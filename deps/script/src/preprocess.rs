@@ -0,0 +1,338 @@
+//! The C style preprocessor SSL headers are written for
+//!
+//! Handles `#include`, `#define` with or without parameters, `#undef`,
+//! `#ifdef`, `#ifndef`, `#if`, `#elif`, `#else` and `#endif`. `#if` takes
+//! integer expressions, with `defined` and the usual operators. Stringizing
+//! and token pasting aren't supported.
+
+use crate::{
+    error::{ CompileError, Location },
+    lexer::{ Token, TokenKind, tokenize },
+    parser::{ Expr, Parser },
+};
+
+use std::collections::HashMap;
+
+//stops headers including each other forever
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Gives the name and text of the file included as `path` by `from`
+pub type IncludeFn<'a> = dyn FnMut(&str, &str) -> Option<(String, String)> + 'a;
+
+struct Macro {
+    params: Option<Vec<String>>,
+    body: Vec<Token>,
+}
+
+//whether lines are kept, whether a branch of this #if was already taken,
+//whether the #if itself was kept and whether its #else was reached
+struct Conditional {
+    active: bool,
+    taken: bool,
+    parent: bool,
+    else_seen: bool,
+}
+
+pub struct Preprocessor<'a, 'b> {
+    include: &'a mut IncludeFn<'b>,
+    macros: HashMap<String, Macro>,
+    output: Vec<Token>,
+    depth: usize,
+}
+
+impl<'a, 'b> Preprocessor<'a, 'b> {
+    pub fn new(include: &'a mut IncludeFn<'b>) -> Self {
+        Self { include, macros: HashMap::new(), output: Vec::new(), depth: 0 }
+    }
+
+    /// Defines an object like macro, as `#define name value` would
+    pub fn define(&mut self, name: &str, value: &str) -> Result<(), CompileError> {
+        let body = tokenize("<define>", value)?;
+        self.macros.insert(name.to_string(), Macro { params: None, body });
+        Ok(())
+    }
+
+    /// Tokens of `source` with directives run and macros expanded
+    pub fn run(mut self, name: &str, source: &str) -> Result<Vec<Token>, CompileError> {
+        self.file(name, source)?;
+        Ok(self.output)
+    }
+
+    fn file(&mut self, name: &str, source: &str) -> Result<(), CompileError> {
+        let tokens = tokenize(name, source)?;
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        let mut pending = Vec::new();
+
+        let mut lines = &tokens[..];
+        while !lines.is_empty() {
+            let end = lines.iter().skip(1).position(|t| t.line_start).map_or(lines.len(), |e| e + 1);
+            let (line, rest) = lines.split_at(end);
+            lines = rest;
+
+            let active = conditionals.iter().all(|c| c.active);
+            if !line[0].is("#") || !line[0].line_start {
+                if active {
+                    pending.extend_from_slice(line);
+                }
+                continue;
+            }
+
+            let expanded = self.expand(&pending, &mut Vec::new())?;
+            self.output.extend(expanded);
+            pending.clear();
+            self.directive(line, &mut conditionals, active)?;
+        }
+
+        if let Some(token) = tokens.last().filter(|_| !conditionals.is_empty()) {
+            return Err(error("#if without #endif", &token.location));
+        }
+
+        let expanded = self.expand(&pending, &mut Vec::new())?;
+        self.output.extend(expanded);
+        Ok(())
+    }
+
+    fn directive(&mut self, line: &[Token], conditionals: &mut Vec<Conditional>, active: bool) -> Result<(), CompileError> {
+        let location = &line[0].location;
+        let name = match line.get(1).map(|t| &t.kind) {
+            Some(TokenKind::Ident(name)) => name.as_str(),
+            None => return Ok(()),
+            _ => return Err(error("Expected a directive", location)),
+        };
+        let args = &line[2 ..];
+
+        match name {
+            "ifdef" | "ifndef" | "if" => {
+                let result = match name {
+                    _ if !active => false,
+                    "if" => self.condition(args, location)?,
+                    _ => self.macros.contains_key(&macro_name(args, location)?) == (name == "ifdef"),
+                };
+                conditionals.push(Conditional { active: result, taken: result, parent: active, else_seen: false });
+            },
+            "elif" | "else" => {
+                let current = conditionals.last()
+                    .ok_or_else(|| error(&format!("#{} without #if", name), location))?;
+                if current.else_seen {
+                    return Err(error(&format!("#{} after #else", name), location));
+                }
+                let result = current.parent && !current.taken && (name == "else" || self.condition(args, location)?);
+
+                let current = conditionals.last_mut().unwrap();
+                current.active = result;
+                current.taken |= result;
+                current.else_seen = name == "else";
+            },
+            "endif" => {
+                conditionals.pop().ok_or_else(|| error("#endif without #if", location))?;
+            },
+            _ if !active => (),
+            "define" => {
+                let name = macro_name(args, location)?;
+                let mut body = &args[1 ..];
+                let params = match body.first() {
+                    Some(open) if open.is("(") && !open.space_before => {
+                        let close = body.iter().position(|t| t.is(")"))
+                            .ok_or_else(|| error("Expected ) after macro parameters", location))?;
+                        let params = body[1 .. close].iter()
+                            .filter(|t| !t.is(","))
+                            .map(|t| match &t.kind {
+                                TokenKind::Ident(param) => Ok(param.clone()),
+                                _ => Err(error("Expected a parameter name", &t.location)),
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        body = &body[close + 1 ..];
+                        Some(params)
+                    },
+                    _ => None,
+                };
+                self.macros.insert(name, Macro { params, body: body.to_vec() });
+            },
+            "undef" => {
+                self.macros.remove(&macro_name(args, location)?);
+            },
+            "include" => {
+                let path = match args.first().map(|t| &t.kind) {
+                    Some(TokenKind::Str(path)) => path,
+                    _ => return Err(error("Expected a file name after #include", location)),
+                };
+                let (name, source) = (self.include)(&location.file, path)
+                    .ok_or_else(|| CompileError::FileNotFound { name: path.clone(), location: Some(location.clone()) })?;
+
+                if self.depth >= MAX_INCLUDE_DEPTH {
+                    return Err(error("Includes nested too deep", location));
+                }
+                self.depth += 1;
+                self.file(&name, &source)?;
+                self.depth -= 1;
+            },
+            "pragma" => (),
+            _ => return Err(error(&format!("Unknown directive #{}", name), location)),
+        }
+
+        Ok(())
+    }
+
+    //macros currently being expanded are left alone, so they can't recurse
+    fn expand(&self, tokens: &[Token], active: &mut Vec<String>) -> Result<Vec<Token>, CompileError> {
+        let mut output = Vec::new();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+            i += 1;
+
+            let (name, definition) = match &token.kind {
+                TokenKind::Ident(name) if !active.contains(name) => match self.macros.get(name) {
+                    Some(definition) => (name, definition),
+                    None => {
+                        output.push(token.clone());
+                        continue;
+                    },
+                },
+                _ => {
+                    output.push(token.clone());
+                    continue;
+                },
+            };
+
+            let body = match &definition.params {
+                None => definition.body.clone(),
+                Some(_) if !tokens.get(i).is_some_and(|t| t.is("(")) => {
+                    output.push(token.clone());
+                    continue;
+                },
+                Some(params) => {
+                    let (args, next) = arguments(tokens, i, &token.location)?;
+                    i = next;
+
+                    let given = if params.is_empty() && args.len() == 1 && args[0].is_empty() { 0 } else { args.len() };
+                    if given != params.len() {
+                        return Err(CompileError::ArgumentCount {
+                            name: name.clone(),
+                            expected: params.len(),
+                            given,
+                            location: token.location.clone(),
+                        });
+                    }
+
+                    let args = args.iter()
+                        .map(|arg| self.expand(arg, active))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    let mut body = Vec::new();
+                    for t in &definition.body {
+                        match &t.kind {
+                            TokenKind::Ident(word) => match params.iter().position(|p| p == word) {
+                                Some(n) => body.extend(args[n].iter().cloned()),
+                                None => body.push(t.clone()),
+                            },
+                            _ => body.push(t.clone()),
+                        }
+                    }
+                    body
+                },
+            };
+
+            let body: Vec<_> = body.into_iter()
+                .map(|t| Token { location: token.location.clone(), line_start: false, ..t })
+                .collect();
+
+            active.push(name.clone());
+            let expanded = self.expand(&body, active)?;
+            active.pop();
+            output.extend(expanded);
+        }
+
+        Ok(output)
+    }
+
+    fn condition(&self, args: &[Token], location: &Location) -> Result<bool, CompileError> {
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < args.len() {
+            if args[i].is_word("defined") {
+                let parens = args.get(i + 1).is_some_and(|t| t.is("("));
+                let name = macro_name(&args[i + 1 + parens as usize ..], location)?;
+                let value = self.macros.contains_key(&name) as i32;
+                tokens.push(Token { kind: TokenKind::Int(value), ..args[i].clone() });
+                i += 2 + 2 * parens as usize;
+            } else {
+                tokens.push(args[i].clone());
+                i += 1;
+            }
+        }
+
+        let tokens = self.expand(&tokens, &mut Vec::new())?;
+        let expr = Parser::new(&tokens, location).expression_only()?;
+        Ok(constant(&expr) != 0)
+    }
+}
+
+fn error(message: &str, location: &Location) -> CompileError {
+    CompileError::Preprocessor { message: message.to_string(), location: location.clone() }
+}
+
+fn macro_name(args: &[Token], location: &Location) -> Result<String, CompileError> {
+    match args.first().map(|t| &t.kind) {
+        Some(TokenKind::Ident(name)) => Ok(name.clone()),
+        _ => Err(error("Expected a macro name", location)),
+    }
+}
+
+//splits the arguments of a macro call at top level commas, `start` is the `(`
+fn arguments(tokens: &[Token], start: usize, location: &Location) -> Result<(Vec<Vec<Token>>, usize), CompileError> {
+    let mut args = vec![Vec::new()];
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(start + 1) {
+        match &token.kind {
+            TokenKind::Punct("(") => depth += 1,
+            TokenKind::Punct(")") if depth == 0 => return Ok((args, i + 1)),
+            TokenKind::Punct(")") => depth -= 1,
+            TokenKind::Punct(",") if depth == 0 => {
+                args.push(Vec::new());
+                continue;
+            },
+            _ => (),
+        }
+        args.last_mut().unwrap().push(token.clone());
+    }
+
+    Err(error("Unterminated macro arguments", location))
+}
+
+//integer value of an #if expression, names that aren't macros are 0 like in C
+fn constant(expr: &Expr) -> i32 {
+    use crate::opcode::*;
+
+    match expr {
+        Expr::Int(v) => *v,
+        Expr::Float(v) => *v as i32,
+        Expr::Unary(NOT, e) => (constant(e) == 0) as i32,
+        Expr::Unary(NEGATE, e) => constant(e).wrapping_neg(),
+        Expr::Unary(_, e) => !constant(e),
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (constant(a), constant(b));
+            match *op {
+                EQUAL => (a == b) as i32,
+                NOT_EQUAL => (a != b) as i32,
+                LESS_EQUAL => (a <= b) as i32,
+                GREATER_EQUAL => (a >= b) as i32,
+                LESS => (a < b) as i32,
+                GREATER => (a > b) as i32,
+                ADD => a.wrapping_add(b),
+                SUB => a.wrapping_sub(b),
+                MUL => a.wrapping_mul(b),
+                DIV => a.checked_div(b).unwrap_or(0),
+                MOD => a.checked_rem(b).unwrap_or(0),
+                AND => (a != 0 && b != 0) as i32,
+                OR => (a != 0 || b != 0) as i32,
+                BITWISE_AND => a & b,
+                BITWISE_OR => a | b,
+                _ => a ^ b,
+            }
+        },
+        Expr::Str(_) | Expr::Name(..) | Expr::Call(..) => 0,
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use crate::{
+    *,
+    error::Location,
+    opcode::*,
+};
+
+const DEFINE_H: &str = r#"
+#ifndef DEFINE_H
+#define DEFINE_H
+
+#define TRUE            1
+#define EXP_REWARD      (50 * 2)
+#define reward(x)       give_exp_points(x)
+#define is_dude(obj)    (obj == dude_obj)
+#define say(who, text)  \
+    float_msg(who, text, 0)
+
+#endif
+"#;
+
+const TEMPLE: &str = r#"
+#include "headers\define.h"
+#include "headers\define.h"
+
+/* declared before use */
+procedure start;
+procedure add(variable a, variable b);
+
+variable visits := 0;
+variable greeting := "Hello, ";
+
+procedure add(variable a, variable b) begin
+    return a + b;
+end
+
+procedure sum_to(variable n) begin
+    variable total := 0, i;
+    i := 1;
+    while i <= n do begin
+        total += i;
+        i := i + 1;
+    end
+    return total;
+end
+
+procedure talk_p_proc begin
+    visits += 1;
+    if is_dude(source_obj) then begin
+        reward(EXP_REWARD);
+        say(self_obj, greeting + visits);
+    end else
+        display_msg("Not you");
+end
+
+procedure start begin
+    return add(sum_to(4), 5) * 2;
+end
+"#;
+
+fn files(list: &[(&str, &str)]) -> HashMap<String, String> {
+    list.iter().map(|(name, text)| (name.to_string(), text.to_string())).collect()
+}
+
+fn compile_with(source: &str, headers: &[(&str, &str)]) -> Result<IntFile, CompileError> {
+    let headers = files(headers);
+    let mut include = |_: &str, path: &str| {
+        let path = path.replace('\\', "/");
+        headers.get(&path).map(|text| (path.clone(), text.clone()))
+    };
+    compile("test.ssl", source, &mut include)
+}
+
+//records messages, the player is object 1 and the script's object 2
+struct Game {
+    source: i32,
+    calls: Vec<(u16, Vec<Value>)>,
+}
+
+impl GameFunctions for Game {
+    fn call(&mut self, opcode: u16, stack: &mut Stack) -> Result<(), VmError> {
+        match opcode {
+            DUDE_OBJ => stack.push(1),
+            SELF_OBJ => stack.push(2),
+            SOURCE_OBJ => stack.push(self.source),
+            GIVE_EXP_POINTS | DISPLAY_MSG => {
+                let value = stack.pop()?;
+                self.calls.push((opcode, vec![value]));
+            },
+            FLOAT_MSG => {
+                let kind = stack.pop()?;
+                let text = stack.pop()?;
+                let object = stack.pop()?;
+                self.calls.push((opcode, vec![object, text, kind]));
+            },
+            _ => return Err(VmError::UnknownOpcode(opcode)),
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn compile_and_run() {
+    let file = compile_with(TEMPLE, &[("headers/define.h", DEFINE_H)]).unwrap();
+    let names: Vec<_> = file.procedures.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["start", "add", "sum_to", "talk_p_proc"]);
+    assert_eq!(file.procedures[1].arg_count, 2);
+
+    let mut vm = Vm::new(&file, Game { source: 1, calls: Vec::new() });
+    vm.init(100).unwrap();
+
+    assert_eq!(vm.call("start", &[], 1000), Ok(Some(Value::Int(30))));
+    assert_eq!(vm.call("sum_to", &[100.into()], 10_000), Ok(Some(Value::Int(5050))));
+    assert_eq!(vm.call("talk_p_proc", &[], 1000), Ok(Some(Value::Int(0))));
+    assert_eq!(vm.game.calls, vec![
+        (GIVE_EXP_POINTS, vec![Value::Int(100)]),
+        (FLOAT_MSG, vec![Value::Int(2), Value::from("Hello, 1"), Value::Int(0)]),
+    ]);
+
+    vm.game.source = 3;
+    vm.game.calls.clear();
+    vm.call("talk_p_proc", &[], 1000).unwrap();
+    assert_eq!(vm.game.calls, vec![(DISPLAY_MSG, vec![Value::from("Not you")])]);
+    assert_eq!(vm.stack.0, vec![Value::Int(2), Value::from("Hello, ")]);
+}
+
+#[test]
+fn disassemble() {
+    let file = compile_with(TEMPLE, &[("headers/define.h", DEFINE_H)]).unwrap();
+    let read = IntFile::from_bytes(file.data.clone()).unwrap();
+
+    assert_eq!(read.procedures, file.procedures);
+    assert_eq!(read.identifiers, file.identifiers);
+    assert_eq!(read.strings, file.strings);
+    assert_eq!(read.code_start, file.code_start);
+    assert_eq!(read.string(6), Some("Hello, "));
+
+    let listing = read.listing();
+    assert!(listing.contains("talk_p_proc:\n"));
+    assert!(listing.contains("sum_to:\n"));
+    assert!(listing.contains("; \"Not you\""));
+    assert!(listing.contains("float_msg"));
+    assert!(listing.contains("; add"));
+    assert!(!listing.contains(".word"));
+
+    //every if and while found its target
+    let targets = read.disassemble().iter()
+        .filter(|(_, a)| matches!(a, Some(disasm::Argument::Target(_))))
+        .count();
+    let branches = read.instructions().filter(|i| matches!(i.opcode, IF | WHILE | JUMP)).count();
+    assert_eq!(targets, branches);
+}
+
+#[test]
+fn preprocessor() {
+    let source = r#"
+#define LEVEL 2
+#define TWICE(x) ((x) + (x))
+
+#if LEVEL > 2
+    #define RESULT 1
+#elif defined(TWICE) and LEVEL == 2
+    #ifdef MISSING
+        #define RESULT 2
+    #else
+        #define RESULT TWICE(LEVEL * 10)
+    #endif
+#else
+    #define RESULT 4
+#endif
+
+#ifndef LEVEL
+    this is not compiled
+#endif
+
+procedure start begin
+    variable SELF := 1;
+    #define SELF SELF + 1
+    return RESULT + SELF;
+    #undef SELF
+end
+"#;
+
+    let file = compile_with(source, &[]).unwrap();
+    let mut vm = Vm::new(&file, ());
+    vm.init(10).unwrap();
+    assert_eq!(vm.call("start", &[], 100), Ok(Some(Value::Int(42))));
+}
+
+#[test]
+fn exports_and_imports() {
+    let source = "
+export variable shared := 5;
+import variable elsewhere;
+import procedure remote(variable x);
+
+export procedure bump begin
+    shared += elsewhere;
+    return shared;
+end
+
+procedure far begin
+    return remote(1);
+end
+";
+
+    let file = compile_with(source, &[]).unwrap();
+    assert_eq!(file.procedures[0].flags, PROCEDURE_IMPORTED);
+    assert_eq!(file.procedures[1].flags, PROCEDURE_EXPORTED);
+
+    let mut vm = Vm::new(&file, ());
+    vm.init(100).unwrap();
+    vm.exports.insert("elsewhere".to_string(), 2.into());
+
+    assert_eq!(vm.call("bump", &[], 100), Ok(Some(Value::Int(7))));
+    assert_eq!(vm.exports.get("shared"), Some(&Value::Int(7)));
+    assert_eq!(vm.call("remote", &[1.into()], 100), Err(VmError::ImportedProcedure("remote".to_string())));
+    assert_eq!(vm.call("far", &[], 100), Err(VmError::ImportedProcedure("remote".to_string())));
+}
+
+#[test]
+fn errors() {
+    let at = |line| Location { file: "test.ssl".to_string(), line };
+    let compile = |source: &str| compile_with(source, &[]).unwrap_err();
+
+    assert_eq!(
+        compile("procedure start begin\n  x := 1;\nend"),
+        CompileError::Undefined { name: "x".to_string(), location: at(2) },
+    );
+    assert_eq!(
+        compile("procedure start begin\n  give_exp_points(1, 2);\nend"),
+        CompileError::ArgumentCount { name: "give_exp_points".to_string(), expected: 1, given: 2, location: at(2) },
+    );
+    assert_eq!(
+        compile("procedure start begin\n  return give_exp_points(1);\nend"),
+        CompileError::Syntax { message: "give_exp_points has no result".to_string(), location: at(2) },
+    );
+    assert_eq!(
+        compile("procedure start;\nprocedure other begin end"),
+        CompileError::Undefined { name: "start".to_string(), location: at(1) },
+    );
+    assert_eq!(
+        compile("variable a;\nvariable A;"),
+        CompileError::Redefined { name: "A".to_string(), location: at(2) },
+    );
+    assert_eq!(
+        compile("procedure start begin\n  if 1 then\nend"),
+        CompileError::Syntax { message: "Expected name, found end".to_string(), location: at(3) },
+    );
+    assert_eq!(
+        compile("#include \"missing.h\""),
+        CompileError::FileNotFound { name: "missing.h".to_string(), location: Some(at(1)) },
+    );
+    assert!(matches!(compile("#ifdef X\n"), CompileError::Preprocessor { .. }));
+    assert_eq!(
+        compile("#if 1\n#else\n#else\n#endif"),
+        CompileError::Preprocessor { message: "#else after #else".to_string(), location: at(3) },
+    );
+    assert!(matches!(compile("#if 0\n#else\n#elif 1\n#endif"), CompileError::Preprocessor { .. }));
+    assert!(matches!(compile("#define F(a) a\nprocedure start begin return F(1, 2); end"), CompileError::ArgumentCount { .. }));
+}
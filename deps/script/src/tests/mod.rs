@@ -0,0 +1,164 @@
+use std::io::Cursor;
+use common::{ BinaryWriter, LengthPrefix };
+use crate::{
+    *,
+    disasm::Argument,
+    opcode::*,
+};
+
+mod compiler;
+mod vm;
+
+fn write_namespace(out: &mut Cursor<Vec<u8>>, names: &[&str]) {
+    let size: usize = names.iter().map(|n| n.len() + 2).sum();
+    out.write_u32_be(size as u32).unwrap();
+    for name in names {
+        out.write_prefixed_string(name, LengthPrefix::U16Be).unwrap();
+    }
+    out.write_u32_be(0xFFFF_FFFF).unwrap();
+}
+
+fn push(out: &mut Cursor<Vec<u8>>, opcode: u16, value: u32) {
+    out.write_u16_be(opcode).unwrap();
+    out.write_u32_be(value).unwrap();
+}
+
+//start checks a condition and calls talk_p_proc, which just exits
+fn make_int() -> Vec<u8> {
+    let mut out = Cursor::new(Vec::new());
+    for _ in 0 .. HEADER_SIZE / 2 {
+        out.write_u16_be(NOOP).unwrap();
+    }
+
+    //name, flags, time, condition, body, args
+    out.write_u32_be(2).unwrap();
+    for entry in [[6, 0, 0, 0, 140, 0], [14, PROCEDURE_TIMED, 10, 0, 182, 1]] {
+        for value in entry {
+            out.write_u32_be(value).unwrap();
+        }
+    }
+
+    write_namespace(&mut out, &["start\0", "talk_p_proc\0"]);
+    write_namespace(&mut out, &["Hello\0"]);
+    assert_eq!(out.position(), 140);
+
+    push(&mut out, PUSH_INT, 180);
+    push(&mut out, PUSH_INT, 0);
+    out.write_u16_be(LOCAL_VAR).unwrap();
+    push(&mut out, PUSH_INT, 1);
+    out.write_u16_be(EQUAL).unwrap();
+    out.write_u16_be(IF).unwrap();
+    push(&mut out, PUSH_STRING, 6);
+    out.write_u16_be(POP).unwrap();
+    push(&mut out, PUSH_INT, 1);
+    out.write_u16_be(CALL).unwrap();
+    out.write_u16_be(EXIT).unwrap();
+    assert_eq!(out.position(), 182);
+    out.write_u16_be(EXIT).unwrap();
+
+    out.into_inner()
+}
+
+#[test]
+fn procedures() {
+    let file = IntFile::open(&mut Cursor::new(make_int())).unwrap();
+
+    assert_eq!(file.code_start, 140);
+    assert_eq!(file.procedures.len(), 2);
+    assert_eq!(file.procedures[0].name, "start");
+    assert_eq!(file.procedures[1].name, "talk_p_proc");
+    assert_eq!(file.procedures[1].time, 10);
+    assert_eq!(file.procedures[1].arg_count, 1);
+
+    assert_eq!(file.procedure("TALK_P_PROC").map(|(i, _)| i), Some(1));
+    assert!(file.procedure("critter_p_proc").is_none());
+    assert_eq!(file.identifier(14), Some("talk_p_proc"));
+    assert_eq!(file.string(6), Some("Hello"));
+    assert_eq!(file.string(7), None);
+}
+
+#[test]
+fn instructions() {
+    let file = IntFile::from_bytes(make_int()).unwrap();
+    let instructions: Vec<_> = file.instructions().collect();
+
+    //21 start up noops, then the code
+    assert!(instructions[.. 21].iter().all(|i| i.opcode == NOOP));
+    let code = &instructions[21 ..];
+    assert_eq!(code[0], Instruction { offset: 140, opcode: PUSH_INT, operand: Some(Operand::Int(180)) });
+    assert_eq!(code[0].size(), 6);
+    assert_eq!(code[0].next(), 146);
+    assert_eq!(code[2].name(), Some("local_var"));
+    assert_eq!(code[2].size(), 2);
+    assert_eq!(code[6].operand, Some(Operand::String(6)));
+    assert_eq!(code.last().unwrap().opcode, EXIT);
+
+    //a push cut short by the end of the code has no operand
+    let cut = Instruction::decode(&[0xC0, 0x01, 0x00], 0).unwrap();
+    assert_eq!(cut.operand, None);
+    assert!(Instruction::decode(&[0xC0], 0).is_none());
+}
+
+#[test]
+fn disassemble() {
+    let file = IntFile::from_bytes(make_int()).unwrap();
+    let code = &file.disassemble()[21 ..];
+
+    //the if's address is pushed before its condition
+    assert_eq!(code[0].1, Some(Argument::Target(180)));
+    assert_eq!(code[1].1, None);
+    assert_eq!(code[6].1, Some(Argument::String(6)));
+    assert_eq!(code[8].1, Some(Argument::Procedure(1)));
+
+    let labels = file.labels();
+    assert_eq!(labels.get(&140).map(String::as_str), Some("start"));
+    assert_eq!(labels.get(&180).map(String::as_str), Some("L_000000b4"));
+
+    let listing = file.listing();
+    assert!(listing.contains("talk_p_proc"));
+    assert!(listing.contains(" timed time 10"));
+    assert!(listing.contains("start:\n0000008c  c001  push 180"));
+    assert!(listing.contains("; L_000000b4"));
+    assert!(listing.contains("; \"Hello\""));
+    assert!(listing.contains("000000ac  c001  push 1"));
+    assert!(listing.contains("talk_p_proc:\n000000b6  800e  exit"));
+}
+
+#[test]
+fn unknown_opcode() {
+    let mut data = make_int();
+    data[182 .. 184].copy_from_slice(&0x8FFFu16.to_be_bytes());
+    let file = IntFile::from_bytes(data).unwrap();
+
+    assert!(file.listing().contains(".word 0x8fff"));
+}
+
+#[test]
+fn empty_namespace() {
+    let mut out = Cursor::new(Vec::new());
+    out.write_bytes(&[0; HEADER_SIZE]).unwrap();
+    out.write_u32_be(0).unwrap();
+    out.write_u32_be(0xFFFF_FFFF).unwrap();
+    out.write_u32_be(0xFFFF_FFFF).unwrap();
+    out.write_u16_be(EXIT).unwrap();
+
+    let file = IntFile::from_bytes(out.into_inner()).unwrap();
+    assert!(file.procedures.is_empty());
+    assert!(file.identifiers.is_empty());
+    assert!(file.strings.is_empty());
+    assert_eq!(file.code_start, HEADER_SIZE + 12);
+}
+
+#[test]
+fn error() {
+    //string table not closed
+    let mut data = make_int();
+    data[136 .. 140].copy_from_slice(&0u32.to_be_bytes());
+    let result = IntFile::from_bytes(data);
+    assert!(matches!(result, Err(ScriptError::InvalidNamespace { position: 124 })));
+
+    let mut data = make_int();
+    data.truncate(100);
+    let result = IntFile::from_bytes(data);
+    assert!(matches!(result, Err(ScriptError::ReadError(e)) if e.is_eof()));
+}
@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+use crate::{
+    *,
+    opcode::*,
+};
+
+//writes code after a start up header that jumps to `start_up`
+struct Asm {
+    data: Vec<u8>,
+}
+
+impl Asm {
+    fn new() -> Self {
+        let mut asm = Self { data: Vec::new() };
+        asm.int(0).op(JUMP);
+        while asm.data.len() < HEADER_SIZE {
+            asm.op(NOOP);
+        }
+        asm
+    }
+
+    fn here(&self) -> i32 {
+        self.data.len() as i32
+    }
+
+    fn op(&mut self, opcode: u16) -> &mut Self {
+        self.data.extend_from_slice(&opcode.to_be_bytes());
+        self
+    }
+
+    fn int(&mut self, value: i32) -> &mut Self {
+        self.op(PUSH_INT);
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn float(&mut self, value: f32) -> &mut Self {
+        self.op(PUSH_FLOAT);
+        self.data.extend_from_slice(&value.to_bits().to_be_bytes());
+        self
+    }
+
+    fn string(&mut self, offset: u32) -> &mut Self {
+        self.op(PUSH_STRING);
+        self.data.extend_from_slice(&offset.to_be_bytes());
+        self
+    }
+
+    //pushes an address filled in later by `patch`
+    fn forward(&mut self) -> usize {
+        self.int(0);
+        self.data.len() - 4
+    }
+
+    fn patch(&mut self, at: usize, address: i32) {
+        self.data[at .. at + 4].copy_from_slice(&address.to_be_bytes());
+    }
+
+    fn start_up(&mut self) {
+        let here = self.here();
+        self.patch(2, here);
+    }
+
+    fn fetch(&mut self, variable: i32) -> &mut Self {
+        self.int(variable).op(FETCH)
+    }
+
+    fn store(&mut self, variable: i32) -> &mut Self {
+        self.int(variable).op(STORE)
+    }
+
+    fn ret(&mut self) -> &mut Self {
+        for opcode in [D_TO_A, SWAPA, POP_TO_BASE, POP_BASE, SWAPA, POP_RETURN] {
+            self.op(opcode);
+        }
+        self
+    }
+
+    //`args` pushes the arguments, the result is left on the stack
+    fn call(&mut self, index: i32, arg_count: i32, args: impl FnOnce(&mut Self)) -> &mut Self {
+        let back = self.forward();
+        self.op(D_TO_A);
+        args(self);
+        self.int(arg_count).int(index).op(CALL);
+        let here = self.here();
+        self.patch(back, here);
+        self.op(A_TO_D)
+    }
+
+    fn finish(self, procedures: &[(&str, i32, i32)], strings: &[(u32, &str)]) -> IntFile {
+        let procedures = procedures.iter()
+            .map(|(name, arg_count, body)| Procedure {
+                name: name.to_string(),
+                arg_count: *arg_count,
+                body: *body as u32,
+                ..Default::default()
+            })
+            .collect();
+        let strings: BTreeMap<_, _> = strings.iter().map(|(o, s)| (*o, s.to_string())).collect();
+
+        IntFile {
+            data: self.data,
+            procedures,
+            identifiers: strings.clone(),
+            strings,
+            code_start: HEADER_SIZE,
+        }
+    }
+}
+
+//records the calls it gets, the player's pid is 0x100
+#[derive(Default)]
+struct Mock {
+    calls: Vec<(u16, Vec<Value>)>,
+}
+
+impl GameFunctions for Mock {
+    fn call(&mut self, opcode: u16, stack: &mut Stack) -> Result<(), VmError> {
+        let args = match opcode {
+            GIVE_EXP_POINTS => vec![stack.pop()?],
+            FLOAT_MSG => {
+                let kind = stack.pop()?;
+                let text = stack.pop()?;
+                vec![stack.pop()?, text, kind]
+            },
+            DUDE_OBJ => {
+                stack.push(1);
+                vec![]
+            },
+            OBJ_PID => {
+                let object = stack.pop_int()?;
+                stack.push(if object == 1 { 0x100 } else { 0 });
+                vec![Value::Int(object)]
+            },
+            _ => return Err(VmError::UnknownOpcode(opcode)),
+        };
+
+        self.calls.push((opcode, args));
+        Ok(())
+    }
+}
+
+#[test]
+fn arithmetic() {
+    let mut asm = Asm::new();
+    asm.start_up();
+    asm.op(EXIT);
+
+    let add = asm.here();
+    asm.op(PUSH_BASE).fetch(0).fetch(1).op(ADD).ret();
+    let compare = asm.here();
+    asm.op(PUSH_BASE).fetch(0).fetch(1).op(LESS).fetch(0).fetch(1).op(EQUAL).op(BITWISE_OR).ret();
+    let divide = asm.here();
+    asm.op(PUSH_BASE).fetch(0).fetch(1).op(DIV).ret();
+    let misc = asm.here();
+    asm.op(PUSH_BASE).float(-2.5).op(FLOOR).op(NEGATE).int(6).op(BITWISE_AND).int(0).op(NOT).op(OR).ret();
+
+    let file = asm.finish(&[("add", 2, add), ("compare", 2, compare), ("divide", 2, divide), ("misc", 0, misc)], &[]);
+    let mut vm = Vm::new(&file, ());
+    vm.init(10).unwrap();
+
+    let mut run = |name: &str, a: Value, b: Value| vm.call(name, &[a, b], 100);
+    assert_eq!(run("add", 2.into(), 3.into()), Ok(Some(Value::Int(5))));
+    assert_eq!(run("add", i32::MAX.into(), 1.into()), Ok(Some(Value::Int(i32::MIN))));
+    assert_eq!(run("add", 1.into(), 0.5.into()), Ok(Some(Value::Float(1.5))));
+    assert_eq!(run("add", "Hi ".into(), 5.into()), Ok(Some(Value::from("Hi 5"))));
+    assert_eq!(run("add", 1.5.into(), "!".into()), Ok(Some(Value::from("1.50000!"))));
+
+    assert_eq!(run("compare", 1.into(), 2.into()), Ok(Some(Value::Int(1))));
+    assert_eq!(run("compare", 3.into(), 2.5.into()), Ok(Some(Value::Int(0))));
+    assert_eq!(run("compare", "abc".into(), "abd".into()), Ok(Some(Value::Int(1))));
+    assert_eq!(run("compare", "abc".into(), 1.into()), Err(VmError::WrongType { expected: "number" }));
+
+    assert_eq!(run("divide", 7.into(), 2.into()), Ok(Some(Value::Int(3))));
+    assert_eq!(run("divide", 7.into(), 2.0.into()), Ok(Some(Value::Float(3.5))));
+    assert_eq!(run("divide", 7.into(), 0.into()), Err(VmError::DivideByZero));
+
+    //-floor(-2.5) & 6 is 2, then 2 || !0
+    assert_eq!(vm.call("misc", &[], 100), Ok(Some(Value::Int(1))));
+    assert!(vm.stack.is_empty());
+    assert!(vm.returns.is_empty());
+}
+
+//sums n down to 1 in a while loop
+fn make_count() -> IntFile {
+    let mut asm = Asm::new();
+    asm.start_up();
+    asm.op(EXIT);
+
+    let count = asm.here();
+    asm.op(PUSH_BASE).int(0);
+    let top = asm.here();
+    let end = asm.forward();
+    asm.fetch(0).int(0).op(GREATER).op(WHILE);
+    asm.fetch(1).fetch(0).op(ADD).store(1);
+    asm.fetch(0).int(1).op(SUB).store(0);
+    asm.int(top).op(JUMP);
+    let here = asm.here();
+    asm.patch(end, here);
+    asm.fetch(1).ret();
+
+    asm.finish(&[("count", 1, count)], &[])
+}
+
+#[test]
+fn loops() {
+    let file = make_count();
+    let mut vm = Vm::new(&file, ());
+
+    assert_eq!(vm.call("COUNT", &[10.into()], 1000), Ok(Some(Value::Int(55))));
+    assert_eq!(vm.call("count", &[0.into()], 1000), Ok(Some(Value::Int(0))));
+
+    //runs out of steps, then works again with a bigger budget
+    assert_eq!(vm.call("count", &[1000.into()], 100), Err(VmError::OutOfSteps));
+    assert!(vm.stack.is_empty());
+    assert!(vm.returns.is_empty());
+    assert_eq!(vm.call("count", &[1000.into()], 100_000), Ok(Some(Value::Int(500500))));
+
+    //the disassembler finds where the loop ends
+    let labels = file.labels();
+    assert!(labels.values().any(|l| l.starts_with("L_")));
+}
+
+#[test]
+fn calls_and_globals() {
+    let mut asm = Asm::new();
+    asm.start_up();
+    asm.op(SET_GLOBAL).int(7).op(EXIT);
+
+    let double = asm.here();
+    asm.op(PUSH_BASE).fetch(0).int(2).op(MUL).ret();
+    let start = asm.here();
+    asm.op(PUSH_BASE);
+    asm.call(0, 1, |asm| {
+        asm.int(0).op(FETCH_GLOBAL);
+    });
+    asm.int(0).op(STORE_GLOBAL).int(0).op(FETCH_GLOBAL).ret();
+
+    let file = asm.finish(&[("double", 1, double), ("start", 0, start)], &[]);
+    let mut vm = Vm::new(&file, ());
+    vm.init(10).unwrap();
+    assert_eq!(vm.stack.0, vec![Value::Int(7)]);
+
+    assert_eq!(vm.call("start", &[], 100), Ok(Some(Value::Int(14))));
+    assert_eq!(vm.call("start", &[], 100), Ok(Some(Value::Int(28))));
+    assert_eq!(vm.stack.0, vec![Value::Int(28)]);
+    assert_eq!(vm.call("double", &[4.into()], 100), Ok(Some(Value::Int(8))));
+}
+
+#[test]
+fn game_functions() {
+    let mut asm = Asm::new();
+    asm.start_up();
+    asm.op(EXIT);
+
+    let talk = asm.here();
+    asm.int(100).op(GIVE_EXP_POINTS);
+    let skip = asm.forward();
+    asm.op(DUDE_OBJ).op(OBJ_PID).int(0x100).op(EQUAL).op(IF);
+    asm.op(DUDE_OBJ).string(6).int(2).op(FLOAT_MSG);
+    let here = asm.here();
+    asm.patch(skip, here);
+    asm.op(EXIT);
+
+    let file = asm.finish(&[("talk_p_proc", 0, talk)], &[(6, "Hello")]);
+    let mut vm = Vm::new(&file, Mock::default());
+
+    //exits without a result
+    assert_eq!(vm.call("talk_p_proc", &[], 100), Ok(None));
+    assert_eq!(vm.game.calls, vec![
+        (GIVE_EXP_POINTS, vec![Value::Int(100)]),
+        (DUDE_OBJ, vec![]),
+        (OBJ_PID, vec![Value::Int(1)]),
+        (DUDE_OBJ, vec![]),
+        (FLOAT_MSG, vec![Value::Int(1), Value::from("Hello"), Value::Int(2)]),
+    ]);
+
+    let mut vm = Vm::new(&file, ());
+    assert_eq!(vm.call("talk_p_proc", &[], 100), Err(VmError::UnknownOpcode(GIVE_EXP_POINTS)));
+}
+
+#[test]
+fn exports() {
+    let mut asm = Asm::new();
+    asm.start_up();
+    asm.int(6).op(EXPORT_VARIABLE).int(3).int(6).op(STORE_EXTERNAL).op(EXIT);
+
+    let bump = asm.here();
+    asm.int(6).op(FETCH_EXTERNAL).int(1).op(ADD).int(6).op(STORE_EXTERNAL);
+    asm.int(14).op(FETCH_EXTERNAL).op(EXIT);
+
+    let file = asm.finish(&[("bump", 0, bump)], &[(6, "counter"), (14, "missing")]);
+    let mut vm = Vm::new(&file, ());
+    vm.init(10).unwrap();
+    assert_eq!(vm.exports.get("counter"), Some(&Value::Int(3)));
+
+    assert_eq!(vm.call("bump", &[], 100), Err(VmError::UnknownExport("missing".to_string())));
+    assert_eq!(vm.exports.get("counter"), Some(&Value::Int(4)));
+
+    vm.exports.insert("missing".to_string(), 1.into());
+    assert_eq!(vm.call("bump", &[], 100), Ok(None));
+}
+
+#[test]
+fn errors() {
+    let mut asm = Asm::new();
+    asm.start_up();
+    asm.op(EXIT);
+
+    let underflow = asm.here();
+    asm.op(POP).op(POP).op(EXIT);
+    let bad_jump = asm.here();
+    asm.int(0xFFFF).op(JUMP);
+    let subtract = asm.here();
+    asm.string(6).int(1).op(SUB).op(EXIT);
+    let timed = asm.here();
+    asm.op(WAIT);
+
+    let procedures = [("underflow", 0, underflow), ("bad_jump", 0, bad_jump), ("subtract", 0, subtract), ("timed", 0, timed)];
+    let file = asm.finish(&procedures, &[(6, "text")]);
+    let mut vm = Vm::new(&file, ());
+
+    assert_eq!(vm.call("underflow", &[], 100), Err(VmError::StackUnderflow));
+    assert_eq!(vm.call("bad_jump", &[], 100), Err(VmError::InvalidAddress(0xFFFF)));
+    assert_eq!(vm.call("subtract", &[], 100), Err(VmError::WrongType { expected: "number" }));
+    assert_eq!(vm.call("timed", &[], 100), Err(VmError::Unsupported(WAIT)));
+    assert_eq!(vm.call("start", &[], 100), Err(VmError::UnknownProcedure("start".to_string())));
+    assert_eq!(vm.call("timed", &[1.into()], 100), Err(VmError::ArgumentCount {
+        procedure: "timed".to_string(),
+        expected: 0,
+        given: 1,
+    }));
+}
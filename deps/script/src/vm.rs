@@ -0,0 +1,517 @@
+//! Runs compiled scripts
+//!
+//! The VM has a data stack for values and a return stack for return
+//! addresses and saved frames. Core opcodes are run here, everything else is
+//! handed to `GameFunctions` so the game side can be mocked.
+//!
+//! Procedures follow this calling convention:
+//!
+//! * the caller moves the return address to the return stack with `d_to_a`,
+//!   then pushes the arguments, their count and the procedure index, and `call`s
+//! * the callee starts with `push_base`, after which `fetch` and `store` address
+//!   the arguments then the locals from 0
+//! * it returns by moving the result to the return stack with `d_to_a`,
+//!   then `swapa`, `pop_to_base`, `pop_base`, `swapa`, `pop_return`, leaving the
+//!   result on the return stack for the caller to take back with `a_to_d`
+//!
+//! Globals live at the bottom of the data stack, above the point marked by
+//! `set_global` in the start up code.
+
+use crate::{
+    IntFile,
+    Instruction,
+    Operand,
+    PROCEDURE_IMPORTED,
+    error::VmError,
+    opcode::*,
+};
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Display,
+};
+
+//return address pushed by the host, returning to it ends the call
+const RETURN_TO_HOST: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl Value {
+    /// Zero is false, strings are always true
+    pub fn is_true(&self) -> bool {
+        match self {
+            Value::Int(v) => *v != 0,
+            Value::Float(v) => *v != 0.0,
+            Value::String(_) => true,
+        }
+    }
+
+    fn as_float(&self) -> Result<f32, VmError> {
+        match self {
+            Value::Int(v) => Ok(*v as f32),
+            Value::Float(v) => Ok(*v),
+            Value::String(_) => Err(VmError::WrongType { expected: "number" }),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{:.5}", v),
+            Value::String(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stack(pub Vec<Value>);
+
+impl Stack {
+    pub fn push(&mut self, value: impl Into<Value>) {
+        self.0.push(value.into());
+    }
+
+    pub fn pop(&mut self) -> Result<Value, VmError> {
+        self.0.pop().ok_or(VmError::StackUnderflow)
+    }
+
+    pub fn pop_int(&mut self) -> Result<i32, VmError> {
+        match self.pop()? {
+            Value::Int(v) => Ok(v),
+            _ => Err(VmError::WrongType { expected: "int" }),
+        }
+    }
+
+    /// Pops a float, converting ints
+    pub fn pop_float(&mut self) -> Result<f32, VmError> {
+        self.pop()?.as_float()
+    }
+
+    pub fn pop_string(&mut self) -> Result<String, VmError> {
+        match self.pop()? {
+            Value::String(v) => Ok(v),
+            _ => Err(VmError::WrongType { expected: "string" }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Opcodes the game provides, such as `give_exp_points` or `float_msg`
+pub trait GameFunctions {
+    /// Pops the arguments of `opcode` and pushes its result, if it has one
+    ///
+    /// Opcodes that aren't handled should return `VmError::UnknownOpcode`.
+    fn call(&mut self, opcode: u16, stack: &mut Stack) -> Result<(), VmError>;
+}
+
+/// No game functions, for scripts that only use core opcodes
+impl GameFunctions for () {
+    fn call(&mut self, opcode: u16, _stack: &mut Stack) -> Result<(), VmError> {
+        Err(VmError::UnknownOpcode(opcode))
+    }
+}
+
+/// Why running stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// Returned to the host
+    Returned,
+    /// Ran an `exit` or similar
+    Exited,
+}
+
+/// * `exports` - variables exported by `export_var`, by name
+/// * `ip` - address of the next instruction
+pub struct Vm<'a, G: GameFunctions> {
+    pub file: &'a IntFile,
+    pub game: G,
+    pub stack: Stack,
+    pub returns: Stack,
+    pub exports: HashMap<String, Value>,
+    pub ip: u32,
+    frame: usize,
+    globals: usize,
+}
+
+impl<'a, G: GameFunctions> Vm<'a, G> {
+    pub fn new(file: &'a IntFile, game: G) -> Self {
+        Self {
+            file,
+            game,
+            stack: Stack::default(),
+            returns: Stack::default(),
+            exports: HashMap::new(),
+            ip: 0,
+            frame: 0,
+            globals: 0,
+        }
+    }
+
+    /// Runs the start up code, which sets up the globals, until it exits
+    pub fn init(&mut self, budget: usize) -> Result<(), VmError> {
+        self.ip = 0;
+        self.run(budget).map(|_| ())
+    }
+
+    /// Calls the procedure `name`, ignoring case, running at most `budget` instructions
+    ///
+    /// Returns the procedure's result, none if it exited instead of returning.
+    /// The stacks are put back as they were, whether the call worked or not.
+    pub fn call(&mut self, name: &str, args: &[Value], budget: usize) -> Result<Option<Value>, VmError> {
+        let (_, procedure) = self.file.procedure(name)
+            .ok_or_else(|| VmError::UnknownProcedure(name.to_string()))?;
+
+        if procedure.flags & PROCEDURE_IMPORTED != 0 {
+            return Err(VmError::ImportedProcedure(procedure.name.clone()));
+        }
+        if procedure.arg_count != args.len() as i32 {
+            return Err(VmError::ArgumentCount {
+                procedure: procedure.name.clone(),
+                expected: procedure.arg_count,
+                given: args.len() as i32,
+            });
+        }
+
+        let (stack_len, returns_len, frame) = (self.stack.len(), self.returns.len(), self.frame);
+        self.returns.push(RETURN_TO_HOST);
+        self.stack.0.extend_from_slice(args);
+        self.stack.push(args.len() as i32);
+        self.ip = procedure.body;
+
+        let result = self.run(budget);
+        let value = match result {
+            Ok(Stop::Returned) if self.returns.len() > returns_len => self.returns.0.pop(),
+            _ => None,
+        };
+
+        self.stack.0.truncate(stack_len);
+        self.returns.0.truncate(returns_len);
+        self.frame = frame;
+
+        result.map(|_| value)
+    }
+
+    /// Runs until the code returns to the host or exits
+    pub fn run(&mut self, budget: usize) -> Result<Stop, VmError> {
+        for _ in 0 .. budget {
+            if let Some(stop) = self.step()? {
+                return Ok(stop);
+            }
+        }
+
+        Err(VmError::OutOfSteps)
+    }
+
+    /// Runs one instruction
+    pub fn step(&mut self) -> Result<Option<Stop>, VmError> {
+        let Instruction { opcode, operand, .. } = Instruction::decode(&self.file.data, self.ip as usize)
+            .filter(|i| i.opcode & 0x8000 != 0)
+            .ok_or(VmError::InvalidAddress(self.ip))?;
+        self.ip += 2 + operand.map_or(0, |_| 4);
+
+        match (opcode, operand) {
+            (_, Some(Operand::Int(v))) => self.stack.push(v),
+            (_, Some(Operand::Float(v))) => self.stack.push(v),
+            (_, Some(Operand::String(offset))) => {
+                let text = self.file.string(offset).ok_or(VmError::InvalidName(offset))?;
+                self.stack.push(text);
+            },
+            (PUSH_INT | PUSH_FLOAT | PUSH_STRING, None) => return Err(VmError::InvalidAddress(self.ip)),
+
+            (NOOP | CRITICAL_START | CRITICAL_DONE | START_CRITICAL | END_CRITICAL, _) => (),
+            (EXIT | EXIT_PROGRAM | STOP_PROGRAM | DETACH, _) => return Ok(Some(Stop::Exited)),
+
+            (JUMP, _) => self.ip = self.pop_address()?,
+            (IF | WHILE, _) => {
+                let condition = self.stack.pop()?;
+                let address = self.pop_address()?;
+                if !condition.is_true() {
+                    self.ip = address;
+                }
+            },
+
+            (CALL, _) => {
+                let index = self.stack.pop_int()?;
+                let procedure = self.file.procedures.get(index as usize)
+                    .ok_or_else(|| VmError::UnknownProcedure(index.to_string()))?;
+                if procedure.flags & PROCEDURE_IMPORTED != 0 {
+                    return Err(VmError::ImportedProcedure(procedure.name.clone()));
+                }
+                self.ip = procedure.body;
+            },
+            (CHECK_ARG_COUNT, _) => {
+                let given = self.stack.pop_int()?;
+                let index = self.stack.pop_int()?;
+                let procedure = self.file.procedures.get(index as usize)
+                    .ok_or_else(|| VmError::UnknownProcedure(index.to_string()))?;
+                if procedure.arg_count != given {
+                    return Err(VmError::ArgumentCount {
+                        procedure: procedure.name.clone(),
+                        expected: procedure.arg_count,
+                        given,
+                    });
+                }
+            },
+            (LOOKUP_PROCEDURE, _) => {
+                let name = self.stack.pop_string()?;
+                let (index, _) = self.file.procedure(&name).ok_or(VmError::UnknownProcedure(name))?;
+                self.stack.push(index as i32);
+            },
+            (EXPORT_PROCEDURE, _) => {
+                self.stack.pop_int()?;
+                self.stack.pop_int()?;
+            },
+
+            (POP_RETURN, _) => return self.pop_return(),
+            (POP_FLAGS_RETURN, _) => {
+                self.returns.pop()?;
+                return self.pop_return();
+            },
+            (POP_EXIT, _) => {
+                self.returns.pop()?;
+                return Ok(Some(Stop::Exited));
+            },
+            (POP_FLAGS | POP_ADDRESS, _) => {
+                self.returns.pop()?;
+            },
+            (POP_FLAGS_EXIT, _) => {
+                self.returns.pop()?;
+                self.returns.pop()?;
+                return Ok(Some(Stop::Exited));
+            },
+
+            (A_TO_D, _) => {
+                let value = self.returns.pop()?;
+                self.stack.push(value);
+            },
+            (D_TO_A, _) => {
+                let value = self.stack.pop()?;
+                self.returns.push(value);
+            },
+            (SWAP, _) => swap(&mut self.stack)?,
+            (SWAPA, _) => swap(&mut self.returns)?,
+            (POP, _) => {
+                self.stack.pop()?;
+            },
+            (DUP, _) => {
+                let value = self.stack.0.last().cloned().ok_or(VmError::StackUnderflow)?;
+                self.stack.push(value);
+            },
+
+            (PUSH_BASE, _) => {
+                let count = self.stack.pop_int()?;
+                self.returns.push(self.frame as i32);
+                self.frame = self.stack.len()
+                    .checked_sub(count.max(0) as usize)
+                    .ok_or(VmError::StackUnderflow)?;
+            },
+            (POP_BASE, _) => self.frame = self.returns.pop_int()?.max(0) as usize,
+            (POP_TO_BASE, _) => self.stack.0.truncate(self.frame),
+            (SET_GLOBAL, _) => self.globals = self.stack.len(),
+
+            (FETCH, _) => self.fetch(self.frame)?,
+            (STORE, _) => self.store(self.frame)?,
+            (FETCH_GLOBAL, _) => self.fetch(self.globals)?,
+            (STORE_GLOBAL, _) => self.store(self.globals)?,
+
+            (EXPORT_VARIABLE, _) => {
+                let name = self.pop_identifier()?;
+                self.exports.entry(name).or_insert(Value::Int(0));
+            },
+            (FETCH_EXTERNAL, _) => {
+                let name = self.pop_identifier()?;
+                let value = self.exports.get(&name).cloned().ok_or(VmError::UnknownExport(name))?;
+                self.stack.push(value);
+            },
+            (STORE_EXTERNAL, _) => {
+                let name = self.pop_identifier()?;
+                let value = self.stack.pop()?;
+                match self.exports.get_mut(&name) {
+                    Some(export) => *export = value,
+                    None => return Err(VmError::UnknownExport(name)),
+                }
+            },
+
+            (EQUAL ..= BITWISE_XOR, _) => {
+                let b = self.stack.pop()?;
+                let a = self.stack.pop()?;
+                self.stack.push(binary(opcode, a, b)?);
+            },
+            (BITWISE_NOT, _) => {
+                let value = self.stack.pop_int()?;
+                self.stack.push(!value);
+            },
+            (NOT, _) => {
+                let value = self.stack.pop()?;
+                self.stack.push(!value.is_true() as i32);
+            },
+            (NEGATE, _) => {
+                let value = match self.stack.pop()? {
+                    Value::Int(v) => Value::Int(v.wrapping_neg()),
+                    Value::Float(v) => Value::Float(-v),
+                    Value::String(_) => return Err(VmError::WrongType { expected: "number" }),
+                };
+                self.stack.push(value);
+            },
+            (FLOOR, _) => {
+                let value = self.stack.pop_float()?;
+                self.stack.push(value.floor() as i32);
+            },
+
+            (0x8000 ..= END_CRITICAL, _) => return Err(VmError::Unsupported(opcode)),
+            _ => self.game.call(opcode, &mut self.stack)?,
+        }
+
+        Ok(None)
+    }
+
+    fn pop_address(&mut self) -> Result<u32, VmError> {
+        let address = self.stack.pop_int()?;
+        u32::try_from(address).map_err(|_| VmError::InvalidAddress(address as u32))
+    }
+
+    fn pop_return(&mut self) -> Result<Option<Stop>, VmError> {
+        match self.returns.pop_int()? {
+            RETURN_TO_HOST => Ok(Some(Stop::Returned)),
+            address => {
+                self.ip = u32::try_from(address).map_err(|_| VmError::InvalidAddress(address as u32))?;
+                Ok(None)
+            },
+        }
+    }
+
+    fn pop_identifier(&mut self) -> Result<String, VmError> {
+        let offset = self.stack.pop_int()? as u32;
+        self.file.identifier(offset)
+            .map(str::to_string)
+            .ok_or(VmError::InvalidName(offset))
+    }
+
+    fn variable(&self, base: usize, index: i32) -> Result<usize, VmError> {
+        usize::try_from(index).ok()
+            .map(|i| base + i)
+            .filter(|i| *i < self.stack.len())
+            .ok_or(VmError::InvalidVariable(index))
+    }
+
+    fn fetch(&mut self, base: usize) -> Result<(), VmError> {
+        let index = self.stack.pop_int()?;
+        let value = self.stack.0[self.variable(base, index)?].clone();
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn store(&mut self, base: usize) -> Result<(), VmError> {
+        let index = self.stack.pop_int()?;
+        let value = self.stack.pop()?;
+        let slot = self.variable(base, index)?;
+        self.stack.0[slot] = value;
+        Ok(())
+    }
+}
+
+fn swap(stack: &mut Stack) -> Result<(), VmError> {
+    let len = stack.len();
+    if len < 2 {
+        return Err(VmError::StackUnderflow);
+    }
+    stack.0.swap(len - 1, len - 2);
+    Ok(())
+}
+
+//comparisons, logic and arithmetic, adding to a string joins the text
+fn binary(opcode: u16, a: Value, b: Value) -> Result<Value, VmError> {
+    use Value::*;
+
+    match opcode {
+        EQUAL ..= GREATER => {
+            let ordering = match (&a, &b) {
+                (String(a), String(b)) => Some(a.cmp(b)),
+                (Int(a), Int(b)) => Some(a.cmp(b)),
+                _ => a.as_float()?.partial_cmp(&b.as_float()?),
+            };
+            let result = match opcode {
+                EQUAL => ordering == Some(Ordering::Equal),
+                NOT_EQUAL => ordering != Some(Ordering::Equal),
+                LESS_EQUAL => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                GREATER_EQUAL => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                LESS => ordering == Some(Ordering::Less),
+                _ => ordering == Some(Ordering::Greater),
+            };
+            Ok(Int(result as i32))
+        },
+        AND => Ok(Int((a.is_true() && b.is_true()) as i32)),
+        OR => Ok(Int((a.is_true() || b.is_true()) as i32)),
+        BITWISE_AND | BITWISE_OR | BITWISE_XOR => match (a, b) {
+            (Int(a), Int(b)) => Ok(Int(match opcode {
+                BITWISE_AND => a & b,
+                BITWISE_OR => a | b,
+                _ => a ^ b,
+            })),
+            _ => Err(VmError::WrongType { expected: "int" }),
+        },
+        ADD if matches!(a, String(_)) || matches!(b, String(_)) => Ok(String(format!("{}{}", a, b))),
+        _ => match (a, b) {
+            (Int(_), Int(0)) if matches!(opcode, DIV | MOD) => Err(VmError::DivideByZero),
+            (Int(a), Int(b)) => Ok(Int(match opcode {
+                ADD => a.wrapping_add(b),
+                SUB => a.wrapping_sub(b),
+                MUL => a.wrapping_mul(b),
+                DIV => a.wrapping_div(b),
+                _ => a.wrapping_rem(b),
+            })),
+            (a, b) => {
+                let (a, b) = (a.as_float()?, b.as_float()?);
+                if b == 0.0 && matches!(opcode, DIV | MOD) {
+                    return Err(VmError::DivideByZero);
+                }
+                Ok(Float(match opcode {
+                    ADD => a + b,
+                    SUB => a - b,
+                    MUL => a * b,
+                    DIV => a / b,
+                    _ => a % b,
+                }))
+            },
+        },
+    }
+}
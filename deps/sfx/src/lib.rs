@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests;
+
+pub mod names;
+
+use acm::Acm;
+use dat::DatFile;
+use mixer::Sound;
+
+use std::{
+    collections::HashMap,
+    io::Cursor,
+};
+
+/// Sample rate of the silence returned for missing sounds
+const SILENCE_RATE: u32 = 22050;
+
+/// Path of a sound effect inside a DAT archive
+///
+/// e.g. `path("WAA1XXX1")` gives `sound\sfx\waa1xxx1.acm`
+pub fn path(name: &str) -> String {
+    format!("sound\\sfx\\{}.acm", name).to_ascii_lowercase()
+}
+
+/// Loads sound effects by name from a DAT archive, keeping them once loaded
+///
+/// Sound effects are mono whatever their header claims, like in the original
+/// engine. Missing or unreadable sounds resolve to silence, which the mixer
+/// doesn't play, so game code can play whatever name it builds.
+pub struct SfxLibrary<'a> {
+    dat: &'a DatFile,
+    cache: HashMap<String, Sound>,
+}
+
+impl<'a> SfxLibrary<'a> {
+    pub fn new(dat: &'a DatFile) -> Self {
+        Self {
+            dat,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.dat.registry.get(&path(name)).is_some()
+    }
+
+    /// Returns the sound for `name`, or silence if it can't be loaded
+    pub fn get(&mut self, name: &str) -> Sound {
+        let key = name.to_ascii_uppercase();
+        if let Some(sound) = self.cache.get(&key) {
+            return sound.clone();
+        }
+
+        let sound = self.load(name)
+            .unwrap_or_else(|| Sound::new(1, SILENCE_RATE, Vec::new()));
+        self.cache.insert(key, sound.clone());
+
+        sound
+    }
+
+    fn load(&self, name: &str) -> Option<Sound> {
+        let node = self.dat.registry.get(&path(name))?;
+        let entry = node.read().ok()?.get_file_entry()?.clone();
+
+        let data = self.dat.unpack_file(&entry).ok()?;
+        let acm = Acm::open(Cursor::new(data), Some(1)).ok()?;
+
+        Some(Sound::from(acm))
+    }
+}
@@ -0,0 +1,170 @@
+//! Sound effect names, built the same way the original engine builds them
+//!
+//! Names are 8 characters and map to `sound\sfx\<name>.acm`.
+
+pub use art::anim::{ Anim, WeaponAnim, art_code };
+
+/// Stage of a weapon's use, the first code letter of weapon sounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponSound {
+    Ready,
+    Attack,
+    OutOfAmmo,
+    Flying,
+    Hit,
+}
+
+impl WeaponSound {
+    fn code(self) -> char {
+        match self {
+            Self::Ready => 'R',
+            Self::Attack => 'A',
+            Self::OutOfAmmo => 'O',
+            Self::Flying => 'F',
+            Self::Hit => 'H',
+        }
+    }
+}
+
+/// Material of what a weapon hit, from its proto
+///
+/// Hit sounds only come in four kinds, so several materials share a code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Material {
+    Glass,
+    Metal,
+    Plastic,
+    Wood,
+    Dirt,
+    Stone,
+    Cement,
+    Leather,
+}
+
+impl Material {
+    fn code(self) -> char {
+        match self {
+            Self::Glass | Self::Metal | Self::Plastic => 'M',
+            Self::Wood => 'W',
+            Self::Dirt | Self::Stone | Self::Cement => 'S',
+            Self::Leather => 'F',
+        }
+    }
+}
+
+/// What a weapon hit, only used for `WeaponSound::Hit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitTarget {
+    Critter,
+    Material(Material),
+}
+
+/// Damage types whose hits sound the same whatever they hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageType {
+    Normal,
+    Laser,
+    Fire,
+    Plasma,
+    Electrical,
+    Emp,
+    Explosion,
+}
+
+/// * `sound_id` - the weapon's sound code from its proto, e.g. `A` for fists
+/// * `secondary` - the weapon's secondary attack mode is used
+///
+/// e.g. `weapon(WeaponSound::Attack, 'A', false, ...)` gives `WAA1XXX1`
+pub fn weapon(
+    effect: WeaponSound,
+    sound_id: char,
+    secondary: bool,
+    damage: DamageType,
+    target: Option<HitTarget>,
+) -> String {
+    let mode = match effect {
+        WeaponSound::Ready | WeaponSound::OutOfAmmo => 1,
+        _ if secondary => 2,
+        _ => 1,
+    };
+
+    let generic_hit = matches!(damage, DamageType::Explosion | DamageType::Plasma | DamageType::Emp);
+    let material = match (effect, target) {
+        (WeaponSound::Hit, Some(HitTarget::Critter)) if !generic_hit => 'F',
+        (WeaponSound::Hit, Some(HitTarget::Material(m))) if !generic_hit => m.code(),
+        _ => 'X',
+    };
+
+    format!("W{}{}{:1}{}XX{:1}", effect.code(), sound_id, mode, material, 1)
+        .to_ascii_uppercase()
+}
+
+/// Why a critter sound plays, changes the sound of falls and melee hits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterSound {
+    #[default]
+    None,
+    Knockdown,
+    PassOut,
+    Die,
+    Contact,
+}
+
+/// * `art_name` - base name of the critter's art, e.g. `HMJMPS`
+/// * `weapon` - the held weapon, or the one being taken out
+///
+/// e.g. `critter("hmjmps", Anim::Walk, ...)` gives `HMJMPSAB`
+pub fn critter(art_name: &str, anim: Anim, weapon: WeaponAnim, sound: CharacterSound) -> Option<String> {
+    let base = art_name.split(['.', ',']).next().unwrap_or(art_name);
+    let (mut first, second) = art_code(anim, weapon)?;
+
+    match (anim, sound) {
+        (Anim::FallFront | Anim::FallBack, CharacterSound::PassOut) => first = 'Y',
+        (Anim::FallFront | Anim::FallBack, CharacterSound::Die) => first = 'Z',
+        (Anim::ThrowPunch | Anim::KickLeg, CharacterSound::Contact) => first = 'Z',
+        _ => {},
+    }
+
+    Some(format!("{}{}{}", base, first, second).to_ascii_uppercase())
+}
+
+/// Scenery and container interactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneryAction {
+    Open,
+    Close,
+    Locked,
+    Unlocked,
+    Use,
+}
+
+impl SceneryAction {
+    fn code(self) -> char {
+        match self {
+            Self::Open => 'O',
+            Self::Close => 'C',
+            Self::Locked => 'L',
+            Self::Unlocked => 'N',
+            Self::Use => 'U',
+        }
+    }
+}
+
+/// * `sound_id` - the door's sound code from its proto
+///
+/// e.g. `door(SceneryAction::Open, 'A')` gives `SODOORSA`
+pub fn door(action: SceneryAction, sound_id: char) -> String {
+    format!("S{}DOORS{}", action.code(), sound_id).to_ascii_uppercase()
+}
+
+/// * `sound_id` - the container's sound code from its proto
+pub fn container(action: SceneryAction, sound_id: char) -> String {
+    format!("I{}CNTNR{}", action.code(), sound_id).to_ascii_uppercase()
+}
+
+/// Other scenery, `name` is right aligned in 4 characters like the engine's `%4s`
+///
+/// e.g. `scenery(SceneryAction::Use, 'A', "ELEV")` gives `SUAELEV1`
+pub fn scenery(action: SceneryAction, kind: char, name: &str) -> String {
+    format!("S{}{}{:>4}{:1}", action.code(), kind, name, 1).to_ascii_uppercase()
+}
@@ -0,0 +1,93 @@
+use acm::Acm;
+use dat::Dat2Writer;
+use crate::{
+    SfxLibrary,
+    names::*,
+};
+
+#[test]
+fn weapon_name_test() {
+    assert_eq!(weapon(WeaponSound::Attack, 'A', false, DamageType::Normal, None), "WAA1XXX1");
+    assert_eq!(weapon(WeaponSound::Attack, 'k', true, DamageType::Normal, None), "WAK2XXX1");
+    assert_eq!(weapon(WeaponSound::Ready, 'K', true, DamageType::Normal, None), "WRK1XXX1");
+    assert_eq!(weapon(WeaponSound::OutOfAmmo, 'K', false, DamageType::Normal, None), "WOK1XXX1");
+
+    let flesh = Some(HitTarget::Critter);
+    let metal = Some(HitTarget::Material(Material::Metal));
+    assert_eq!(weapon(WeaponSound::Hit, 'A', false, DamageType::Normal, flesh), "WHA1FXX1");
+    assert_eq!(weapon(WeaponSound::Hit, 'A', false, DamageType::Normal, metal), "WHA1MXX1");
+
+    let hit = |material| weapon(WeaponSound::Hit, 'B', false, DamageType::Normal, Some(HitTarget::Material(material)));
+    assert_eq!(hit(Material::Glass), "WHB1MXX1");
+    assert_eq!(hit(Material::Plastic), "WHB1MXX1");
+    assert_eq!(hit(Material::Wood), "WHB1WXX1");
+    assert_eq!(hit(Material::Dirt), "WHB1SXX1");
+    assert_eq!(hit(Material::Stone), "WHB1SXX1");
+    assert_eq!(hit(Material::Cement), "WHB1SXX1");
+    assert_eq!(hit(Material::Leather), "WHB1FXX1");
+    assert_eq!(weapon(WeaponSound::Hit, 'P', false, DamageType::Plasma, flesh), "WHP1XXX1");
+    //only hits care about the target
+    assert_eq!(weapon(WeaponSound::Flying, 'R', false, DamageType::Normal, metal), "WFR1XXX1");
+}
+
+#[test]
+fn critter_name_test() {
+    let name = |anim, weapon, sound| critter("hmjmps", anim, weapon, sound).unwrap();
+
+    assert_eq!(name(Anim::Walk, WeaponAnim::None, CharacterSound::None), "HMJMPSAB");
+    assert_eq!(name(Anim::HitFromFront, WeaponAnim::Smg, CharacterSound::None), "HMJMPSAO");
+    assert_eq!(name(Anim::TakeOut, WeaponAnim::Knife, CharacterSound::None), "HMJMPSDC");
+    assert_eq!(name(Anim::FireBurst, WeaponAnim::Smg, CharacterSound::None), "HMJMPSIK");
+    assert_eq!(name(Anim::Throw, WeaponAnim::Spear, CharacterSound::None), "HMJMPSGM");
+    assert_eq!(name(Anim::Throw, WeaponAnim::None, CharacterSound::None), "HMJMPSAS");
+    assert_eq!(name(Anim::BigHole, WeaponAnim::None, CharacterSound::None), "HMJMPSBD");
+    assert_eq!(name(Anim::BigHoleSf, WeaponAnim::None, CharacterSound::None), "HMJMPSRD");
+    assert_eq!(name(Anim::BackToStanding, WeaponAnim::None, CharacterSound::None), "HMJMPSCJ");
+
+    assert_eq!(name(Anim::Stand, WeaponAnim::Smg, CharacterSound::None), "HMJMPSIA");
+    assert_eq!(name(Anim::Walk, WeaponAnim::Knife, CharacterSound::None), "HMJMPSDB");
+    assert_eq!(name(Anim::Dodge, WeaponAnim::Knife, CharacterSound::None), "HMJMPSDE");
+    assert_eq!(name(Anim::Dodge, WeaponAnim::None, CharacterSound::None), "HMJMPSAN");
+
+    assert_eq!(name(Anim::FallBack, WeaponAnim::None, CharacterSound::Knockdown), "HMJMPSBA");
+    assert_eq!(name(Anim::FallBack, WeaponAnim::None, CharacterSound::PassOut), "HMJMPSYA");
+    assert_eq!(name(Anim::FallFront, WeaponAnim::None, CharacterSound::Die), "HMJMPSZB");
+    assert_eq!(name(Anim::ThrowPunch, WeaponAnim::None, CharacterSound::Contact), "HMJMPSZQ");
+
+    //art list entries can be passed as they are
+    assert_eq!(critter("hmjmps,11", Anim::Stand, WeaponAnim::None, CharacterSound::None).unwrap(), "HMJMPSAA");
+
+    assert!(critter("hmjmps", Anim::FireSingle, WeaponAnim::None, CharacterSound::None).is_none());
+}
+
+#[test]
+fn scenery_name_test() {
+    assert_eq!(door(SceneryAction::Open, 'A'), "SODOORSA");
+    assert_eq!(door(SceneryAction::Locked, 'b'), "SLDOORSB");
+    assert_eq!(container(SceneryAction::Close, 'A'), "ICCNTNRA");
+    assert_eq!(scenery(SceneryAction::Use, 'A', "ELEV"), "SUAELEV1");
+    assert_eq!(scenery(SceneryAction::Open, 'b', "tv"), "SOB  TV1");
+    assert_eq!(crate::path("SODOORSA"), "sound\\sfx\\sodoorsa.acm");
+}
+
+#[test]
+fn library_test() {
+    let acm = Acm { channels: 1, sample_rate: 22050, samples: vec![1, 2, 3, 4] };
+    let dat = Dat2Writer::build(&[
+        ("sound\\sfx\\waa1xxx1.acm", acm.write_to_acm().unwrap()),
+        ("sound\\sfx\\broken.acm", b"not an acm".to_vec()),
+    ]).unwrap();
+
+    let mut library = SfxLibrary::new(&dat);
+    let name = weapon(WeaponSound::Attack, 'A', false, DamageType::Normal, None);
+    assert!(library.exists(&name));
+
+    let sound = library.get(&name);
+    assert_eq!(sound.sample_rate, 22050);
+    assert_eq!(sound.samples(), [1, 2, 3, 4]);
+
+    //missing sounds are silent
+    assert!(!library.exists("WAB1XXX1"));
+    assert_eq!(library.get("WAB1XXX1").frames(), 0);
+    assert_eq!(library.get("broken").frames(), 0);
+}
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = acm::Acm::open(Cursor::new(data), None);
+
+    //seeking back restarts from a checkpoint instead of the start
+    if let Ok(mut decoder) = acm::AcmDecoder::new(Cursor::new(data), Some(1)) {
+        let total = decoder.total_samples();
+        let _ = decoder.seek_to_sample(total / 2)
+            .and_then(|_| decoder.seek_to_sample(total / 3));
+        let _ = decoder.read_block();
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(dat) = dat::DatFile::open(Cursor::new(data.to_vec())) else {
+        return;
+    };
+
+    for node in &dat.registry {
+        if let Some(entry) = node.get_file_entry() {
+            let _ = dat.unpack_file(entry);
+        }
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+//the palette only needs to cover every index, its colors don't matter
+const PALETTE: &[u8] = &[0u8; 256 * 3 + 32768];
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(frm) = frm::FrmFile::open(&mut Cursor::new(data)) else {
+        return;
+    };
+
+    let palette = pal::PalFile::open(&mut Cursor::new(PALETTE)).unwrap();
+    frm.decode(&palette);
+});
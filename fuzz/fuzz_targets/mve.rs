@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = mve::read_mve(data);
+
+    if let Ok(mut movie) = mve::MveFile::open(Cursor::new(data)) {
+        let _ = movie.read_audio(0);
+    }
+});
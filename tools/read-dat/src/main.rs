@@ -1,6 +1,5 @@
 use std::{ 
-    io::{ stdin, Read, Cursor, stdout, Write },
-    collections::hash_map::Entry,
+    io::{ stdout, Write },
     env::{ Args, args },
     fs::File,
     path::Path,
@@ -9,7 +8,6 @@ use std::{
 use dat::{ 
     DatFile,
     tree::FileState,
-    tree::Node,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -60,7 +58,7 @@ impl Options {
 }
 
 fn print_help() {
-    println!("{} [options] FILE", args().nth(0).unwrap_or("read-dat".into()));
+    println!("{} [options] FILE", args().next().unwrap_or("read-dat".into()));
     println!("reads fallout 1/2 dat files from stdin");
     println!();
 
@@ -90,7 +88,7 @@ fn main()
 
     if let Some(file) = options.file {
         let file = file.replace("/", "\\");
-        let entry = dat.registry.get(&file).expect(&format!("file not found: {}", file));
+        let entry = dat.registry.get(&file).unwrap_or_else(|| panic!("file not found: {}", file));
 
         let entry = {
             let lock = entry.read().unwrap();
@@ -126,7 +124,6 @@ fn main()
     } else {
         let mut lines = vec![];
         for node in &dat.registry {
-            let name = node.get_name();
             let path = node.get_path();
             let file = node.get_file_entry().unwrap();
 
@@ -1,12 +1,12 @@
 use std::{
     fs::File,
+    path::Path,
     io::{
-        Write,
-        stdout, 
-        Read,
         Cursor,
+        Read,
+        Write,
+        stdout,
     },
-    mem::{ size_of, size_of_val },
 };
 
 pub enum FileType {
@@ -15,9 +15,24 @@ pub enum FileType {
     Dat,
     Acm,
     Mve,
+    Pro,
+    ProText,
+    Int,
 }
 
+//proto text files are only told apart from other toml by their full name
+const PRO_TEXT_SUFFIX: &str = ".pro.toml";
+
 impl FileType {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(PRO_TEXT_SUFFIX) {
+            return Some(FileType::ProText);
+        }
+
+        Self::new(path.extension()?.to_str()?)
+    }
+
     pub fn new(ext: &str) -> Option<Self> {
         match ext.to_lowercase().as_str() {
             "pal" => Some(FileType::Pal),
@@ -25,6 +40,8 @@ impl FileType {
             "dat" => Some(FileType::Dat),
             "acm" => Some(FileType::Acm),
             "mve" => Some(FileType::Mve),
+            "pro" => Some(FileType::Pro),
+            "int" => Some(FileType::Int),
             _ => None
         }
     }
@@ -36,6 +53,9 @@ impl FileType {
             Self::Dat => (),
             Self::Acm => open_acm(file),
             Self::Mve => open_mve(file),
+            Self::Pro => open_pro(file),
+            Self::ProText => open_pro_text(file),
+            Self::Int => open_int(file),
         }
     }
 
@@ -46,59 +66,39 @@ impl FileType {
             Self::Dat => (),
             Self::Acm => (),
             Self::Mve => inspect_mve(file),
-        }
-    }
-}
-
-#[repr(C)]
-struct WavHeader {
-    riff: [u8; 4],
-    size: u32,
-    wave: [u8; 4],
-    fmt: [u8; 4],
-    wave_size: u32,
-    wave_type: u16,
-    channels: u16,
-    sample_rate: u32,
-    bytes_per_sec: u32,
-    alignment: u16,
-    bits_per_sample: u16,
-    data_header: [u8; 4],
-    data_size: u32,
-}
-
-impl WavHeader {
-    pub fn new(acm: &acm::Acm) -> Self {
-        let word_len = size_of_val(&acm.samples[0]);
-        let data_size = acm.samples.len() * word_len;
-        let bps = acm.sample_rate as u32 * acm.channels as u32 * word_len as u32;
-        let bits = word_len * 8;
-        let alignment = bits * acm.channels as usize * 8;
-
-        WavHeader {
-            riff: ['R', 'I', 'F', 'F'].map(|x| x as u8),
-            size: (size_of::<WavHeader>() + data_size - 8) as u32,
-            wave: ['W', 'A', 'V', 'E'].map(|x| x as u8),
-            fmt:  ['f', 'm', 't', ' '].map(|x| x as u8),
-            wave_size: 16,
-            wave_type: 0x01,
-            channels: acm.channels as u16,
-            sample_rate: acm.sample_rate,
-            bytes_per_sec: bps,
-            alignment: alignment as u16,
-            bits_per_sample: bits as u16,
-            data_header: ['d', 'a', 't', 'a'].map(|x| x as u8),
-            data_size: data_size as u32,
+            Self::Pro => inspect_pro(file),
+            Self::ProText => (),
+            Self::Int => inspect_int(file),
         }
     }
 }
 
 fn open_mve(file: File) {
+    let mut mve = mve::MveFile::open(file).unwrap();
+    let wav = mve.read_audio(0).unwrap().expect("movie has no audio");
 
+    let mut output = stdout().lock();
+    wav.write(&mut output).unwrap();
 }
 
 fn inspect_mve(file: File) {
-    let mve = mve::MveFile::open(file).unwrap();
+    let mut mve = mve::MveFile::open(file).unwrap();
+
+    let mut chunks = 0;
+    let mut opcodes = 0;
+    while let Some(chunk) = mve.next_chunk().unwrap() {
+        chunks += 1;
+
+        for op in chunk.opcodes() {
+            if let mve::Opcode::CreateTimer(timer) = op.unwrap() {
+                println!("frame duration:       {:?}", timer.frame_duration());
+            }
+            opcodes += 1;
+        }
+    }
+
+    println!("chunks:               {}", chunks);
+    println!("opcodes:              {}", opcodes);
 }
 
 fn open_acm(file: File) {
@@ -162,18 +162,60 @@ fn open_frm(mut file: File, palette: String) {
     let frm = frm::FrmFile::open(&mut file).unwrap();
 
     let bitmap = frm.decode(&pal);
-    let ref first = bitmap[0];
+    let first = &bitmap[0];
 
     let mut pixels = vec![0u8; first.pixels.len() * 4];
     for (i, p) in first.pixels.iter().enumerate() {
         let offset = i * 4;
 
-        pixels[offset + 0] = p.red;
+        pixels[offset] = p.red;
         pixels[offset + 1] = p.green;
         pixels[offset + 2] = p.blue;
         pixels[offset + 3] = p.alpha;
     }
 
     let png = lodepng::encode32(&pixels, first.width as usize, first.height as usize).unwrap();
-    stdout().write(&png).unwrap();
+    stdout().write_all(&png).unwrap();
+}
+
+//prints the proto as toml, to be edited and turned back with open_pro_text
+//once saved with the `.pro.toml` extension
+fn open_pro(mut file: File) {
+    let proto = pro::Proto::open(&mut file).unwrap();
+    print!("{}", proto.to_toml().unwrap());
+}
+
+fn open_pro_text(mut file: File) {
+    let mut text = String::new();
+    file.read_to_string(&mut text).unwrap();
+    let proto = pro::Proto::from_toml(&text).unwrap();
+
+    let mut output = Cursor::new(Vec::new());
+    proto.write(&mut output).unwrap();
+    stdout().write_all(output.get_ref()).unwrap();
+}
+
+fn inspect_pro(mut file: File) {
+    let proto = pro::Proto::open(&mut file).unwrap();
+
+    println!("pid:                  {:#010x}", proto.header.pid.0);
+    println!("type:                 {:?}", proto.pid().proto_type());
+    println!("text id:              {}", proto.header.text_id);
+    println!("fid:                  {:#010x}", proto.header.fid.0);
+    println!("extra bytes:          {}", proto.extra.len());
+}
+
+fn open_int(mut file: File) {
+    let script = script::IntFile::open(&mut file).unwrap();
+    print!("{}", script.listing());
+}
+
+fn inspect_int(mut file: File) {
+    let script = script::IntFile::open(&mut file).unwrap();
+
+    println!("procedures:           {}", script.procedures.len());
+    println!("identifiers:          {}", script.identifiers.len());
+    println!("strings:              {}", script.strings.len());
+    println!("code start:           {:#x}", script.code_start);
+    println!("size:                 {}", script.data.len());
 }
@@ -1,16 +1,7 @@
 mod files;
 use files::FileType;
 
-use common::Stream;
 use std::{
-    io::{
-        stdin,
-        Read,
-        Cursor,
-        BufWriter,
-        stdout,
-        Write
-    },
     fs::File,
     path::Path
 };
@@ -39,14 +30,10 @@ fn main() {
     let file_path = Path::new(&args.input);
     let file = File::open(file_path).unwrap();
 
-    if let Some(ext) = file_path.extension() {
-        let ext = ext.to_str().unwrap();
-        let file_type = FileType::new(ext).expect("file extension not recognized");
-
-        if args.inspect {
-            file_type.inspect(file);
-        } else {
-            file_type.open(file, args.palette);
-        }
+    let file_type = FileType::from_path(file_path).expect("file extension not recognized");
+    if args.inspect {
+        file_type.inspect(file);
+    } else {
+        file_type.open(file, args.palette);
     }
 }